@@ -0,0 +1,432 @@
+use std::{borrow::Cow, collections::BTreeMap, fmt, sync::Arc};
+
+use crate::storage::{Blob, BlobStore};
+
+/// The size in bytes of the blocks that file contents are split into.
+pub const BLOCK_SIZE: usize = 4096;
+
+/// How the blocks of a [`Content`] are compressed in memory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Compression {
+  /// Blocks are stored as is.
+  #[default]
+  None,
+
+  /// Full blocks are compressed with LZ4 if doing so makes them smaller.
+  ///
+  /// Blocks shared through a [`BlobStore`] are never compressed.
+  Lz4,
+}
+
+/// The error returned when a write would extend a [`Content`] past the largest offset a `u64`
+/// can hold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TooLarge;
+
+impl fmt::Display for TooLarge {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("the content would be too large")
+  }
+}
+
+impl std::error::Error for TooLarge {}
+
+/// The error returned when the bytes read from a [`Content`] don't fit in memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutOfMemory;
+
+impl fmt::Display for OutOfMemory {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("the content read doesn't fit in memory")
+  }
+}
+
+impl std::error::Error for OutOfMemory {}
+
+/// How many bytes the blocks of a [`Content`] take up in memory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StoredLen {
+  /// The length in bytes of blocks owned by the content, after compression.
+  pub owned: u64,
+
+  /// The length in bytes of blocks shared through a [`BlobStore`].
+  pub shared: u64,
+}
+
+impl StoredLen {
+  /// Returns the total length in bytes of all blocks.
+  #[must_use]
+  pub fn total(&self) -> u64 {
+    self.owned + self.shared
+  }
+}
+
+/// The content of a [`File`], split into blocks of [`BLOCK_SIZE`] bytes.
+///
+/// Content is sparse: only blocks that have been written are stored, and missing blocks are holes
+/// that read as zeros. Every stored block is full except possibly the one containing the end of
+/// the content.
+///
+/// Cloning content is constant time, as the blocks are only copied once either clone is modified.
+///
+/// [`File`]: crate::file::File
+#[derive(Clone, Default)]
+pub struct Content {
+  blocks: Arc<BTreeMap<u64, Block>>,
+
+  len: u64,
+
+  allocated: u64,
+
+  compression: Compression,
+}
+
+impl Content {
+  /// Returns the length in bytes of the content, including holes.
+  #[must_use]
+  pub fn len(&self) -> u64 {
+    self.len
+  }
+
+  /// Returns whether the content is empty.
+  #[must_use]
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns the number of bytes of the content backed by stored blocks, which excludes holes.
+  #[must_use]
+  pub fn allocated(&self) -> u64 {
+    self.allocated
+  }
+
+  /// Returns how many bytes the content's blocks take up in memory.
+  #[must_use]
+  pub fn stored_len(&self) -> StoredLen {
+    let mut stored_len = StoredLen::default();
+
+    for block in self.blocks.values() {
+      match block {
+        Block::Owned(bytes) => stored_len.owned += bytes.len() as u64,
+        Block::Compressed { data, .. } => stored_len.owned += data.len() as u64,
+        Block::Shared(blob) => stored_len.shared += blob.len() as u64,
+      }
+    }
+
+    stored_len
+  }
+
+  /// Returns how the content's blocks are compressed.
+  #[must_use]
+  pub fn compression(&self) -> Compression {
+    self.compression
+  }
+
+  /// Sets how the content's blocks are compressed, compressing or decompressing existing blocks.
+  pub fn set_compression(&mut self, compression: Compression) {
+    if self.compression == compression {
+      return;
+    }
+
+    self.compression = compression;
+
+    for block in Arc::make_mut(&mut self.blocks).values_mut() {
+      *block = match std::mem::replace(block, Block::Owned(Vec::new())) {
+        Block::Shared(blob) => Block::Shared(blob),
+        block => Block::new(block.into_vec(), None, compression),
+      };
+    }
+  }
+
+  /// Returns a copy of the entire content.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if the content doesn't fit in memory.
+  pub fn to_vec(&self) -> Result<Vec<u8>, OutOfMemory> {
+    self.read_at(0, usize::try_from(self.len).map_err(|_| OutOfMemory)?)
+  }
+
+  /// Returns a copy of at most `len` bytes of the content starting at `offset`.
+  ///
+  /// Only the blocks overlapping the requested range are decompressed. Memory is reserved up front
+  /// for at most the stored bytes, and for the rest of the range once a hole is reached, with
+  /// fallible allocations, so a read that can't fit fails before filling any memory.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if the bytes read don't fit in memory.
+  pub fn read_at(&self, offset: u64, len: usize) -> Result<Vec<u8>, OutOfMemory> {
+    let end = offset.saturating_add(len as u64).min(self.len);
+    if offset >= end {
+      return Ok(Vec::new());
+    }
+
+    let range = (end - offset) as usize;
+    let mut content = Vec::new();
+    content
+      .try_reserve_exact(range.min(self.allocated as usize))
+      .map_err(|_| OutOfMemory)?;
+
+    for (index, start, stop) in block_ranges(offset, end) {
+      if content.capacity() - content.len() < stop - start {
+        content.try_reserve_exact(range - content.len()).map_err(|_| OutOfMemory)?;
+      }
+
+      match self.blocks.get(&index) {
+        Some(block) => content.extend_from_slice(&block.bytes()[start..stop]),
+        None => content.resize(content.len() + stop - start, 0),
+      }
+    }
+
+    Ok(content)
+  }
+
+  /// Appends `data` to the content, storing new blocks in `store` if one is given.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error, leaving the content unchanged, if its length would
+  /// overflow.
+  pub fn append(&mut self, data: &[u8], store: Option<&BlobStore>) -> Result<(), TooLarge> {
+    self.write_at(self.len, data, store)
+  }
+
+  /// Writes `data` at `offset`, storing new blocks in `store` if one is given.
+  ///
+  /// Writing past the end of the content extends it, leaving a hole between the previous end and
+  /// `offset`.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error, leaving the content unchanged, if the written range
+  /// would end past the largest offset a `u64` can hold.
+  pub fn write_at(&mut self, offset: u64, data: &[u8], store: Option<&BlobStore>) -> Result<(), TooLarge> {
+    let end = offset.checked_add(data.len() as u64).ok_or(TooLarge)?;
+    if data.is_empty() {
+      return Ok(());
+    }
+
+    if end > self.len {
+      let tail = self.len / BLOCK_SIZE as u64;
+      self.len = end;
+
+      // a partial tail block before the written range is now followed by more content
+      if tail < offset / BLOCK_SIZE as u64 {
+        self.resize_block(tail, store);
+      }
+    }
+
+    for (index, start, stop) in block_ranges(offset, end) {
+      let block_len = self.block_len(index);
+      let data_start = (index * BLOCK_SIZE as u64 + start as u64 - offset) as usize;
+      let data = &data[data_start..data_start + stop - start];
+
+      let bytes = if start == 0 && stop == block_len {
+        data.to_vec()
+      } else {
+        let mut bytes = self.remove_block(index).map(Block::into_vec).unwrap_or_default();
+        bytes.resize(block_len, 0);
+        bytes[start..stop].copy_from_slice(data);
+        bytes
+      };
+
+      self.insert_block(index, bytes, store);
+    }
+
+    Ok(())
+  }
+
+  /// Sets the length of the content, discarding anything past `len` or extending it with a hole.
+  pub fn truncate(&mut self, len: u64, store: Option<&BlobStore>) {
+    if len < self.len {
+      let removed = Arc::make_mut(&mut self.blocks).split_off(&len.div_ceil(BLOCK_SIZE as u64));
+      self.allocated -= removed.values().map(|block| block.len() as u64).sum::<u64>();
+      self.len = len;
+
+      self.resize_block(len / BLOCK_SIZE as u64, store);
+    } else if len > self.len {
+      let tail = self.len / BLOCK_SIZE as u64;
+      self.len = len;
+
+      self.resize_block(tail, store);
+    }
+  }
+
+  /// Deallocates `len` bytes starting at `offset`, turning them into a hole. The length of the
+  /// content is unchanged.
+  ///
+  /// Blocks only partially inside the range are kept, with the range zeroed.
+  pub fn punch_hole(&mut self, offset: u64, len: u64, store: Option<&BlobStore>) {
+    let end = offset.saturating_add(len).min(self.len);
+    if offset >= end {
+      return;
+    }
+
+    let first = offset / BLOCK_SIZE as u64;
+    let last = (end - 1) / BLOCK_SIZE as u64;
+    let stored: Vec<_> = self.blocks.range(first..=last).map(|(index, _)| *index).collect();
+
+    for index in stored {
+      let block_offset = index * BLOCK_SIZE as u64;
+      let start = offset.saturating_sub(block_offset) as usize;
+      let stop = (end - block_offset).min(BLOCK_SIZE as u64) as usize;
+
+      let Some(block) = self.remove_block(index) else {
+        continue;
+      };
+
+      if start != 0 || stop != self.block_len(index) {
+        let mut bytes = block.into_vec();
+        bytes[start..stop].fill(0);
+
+        self.insert_block(index, bytes, store);
+      }
+    }
+  }
+
+  /// Returns the first offset at or after `offset` that is backed by a stored block, or `None` if
+  /// there is no data after `offset`. This is like `lseek` with `SEEK_DATA`.
+  #[must_use]
+  pub fn seek_data(&self, offset: u64) -> Option<u64> {
+    if offset >= self.len {
+      return None;
+    }
+
+    let (index, _) = self.blocks.range(offset / BLOCK_SIZE as u64..).next()?;
+
+    Some(offset.max(index * BLOCK_SIZE as u64))
+  }
+
+  /// Returns the first offset at or after `offset` that is in a hole, or `None` if `offset` is past
+  /// the end of the content. The end of the content is always considered a hole. This is like
+  /// `lseek` with `SEEK_HOLE`.
+  #[must_use]
+  pub fn seek_hole(&self, offset: u64) -> Option<u64> {
+    if offset >= self.len {
+      return None;
+    }
+
+    let mut index = offset / BLOCK_SIZE as u64;
+
+    for (stored, _) in self.blocks.range(index..) {
+      if *stored != index {
+        break;
+      }
+
+      index += 1;
+    }
+
+    Some(offset.max(index * BLOCK_SIZE as u64).min(self.len))
+  }
+
+  /// Returns the length the block at `index` has, given the current length of the content.
+  fn block_len(&self, index: u64) -> usize {
+    self.len.saturating_sub(index * BLOCK_SIZE as u64).min(BLOCK_SIZE as u64) as usize
+  }
+
+  /// Truncates or zero-extends the block at `index`, if it is stored, to the length it should have.
+  fn resize_block(&mut self, index: u64, store: Option<&BlobStore>) {
+    let block_len = self.block_len(index);
+
+    if self.blocks.get(&index).is_none_or(|block| block.len() == block_len) {
+      return;
+    }
+
+    if let Some(block) = self.remove_block(index) {
+      let mut bytes = block.into_vec();
+      bytes.resize(block_len, 0);
+
+      self.insert_block(index, bytes, store);
+    }
+  }
+
+  fn insert_block(&mut self, index: u64, bytes: Vec<u8>, store: Option<&BlobStore>) {
+    self.allocated += bytes.len() as u64;
+
+    let block = Block::new(bytes, store, self.compression);
+    Arc::make_mut(&mut self.blocks).insert(index, block);
+  }
+
+  fn remove_block(&mut self, index: u64) -> Option<Block> {
+    let block = Arc::make_mut(&mut self.blocks).remove(&index)?;
+    self.allocated -= block.len() as u64;
+
+    Some(block)
+  }
+}
+
+/// Returns the blocks overlapping the range from `offset` to `end`, as the block's index and the
+/// start and end of the range within the block.
+fn block_ranges(offset: u64, end: u64) -> impl Iterator<Item = (u64, usize, usize)> {
+  let first = offset / BLOCK_SIZE as u64;
+  let last = (end - 1) / BLOCK_SIZE as u64;
+
+  (first..=last).map(move |index| {
+    let block_offset = index * BLOCK_SIZE as u64;
+    let start = offset.saturating_sub(block_offset) as usize;
+    let stop = (end - block_offset).min(BLOCK_SIZE as u64) as usize;
+
+    (index, start, stop)
+  })
+}
+
+/// A block of content, either owned by a single file, possibly compressed, or shared through a
+/// [`BlobStore`].
+#[derive(Clone)]
+enum Block {
+  Owned(Vec<u8>),
+  Compressed { data: Box<[u8]>, len: usize },
+  Shared(Arc<Blob>),
+}
+
+impl Block {
+  /// Creates a block containing `bytes`, storing it in `store` if one is given and compressing it
+  /// otherwise if it is full.
+  fn new(bytes: Vec<u8>, store: Option<&BlobStore>, compression: Compression) -> Self {
+    match (store, compression) {
+      (Some(store), _) => Self::Shared(store.intern(&bytes)),
+      (None, Compression::Lz4) if bytes.len() == BLOCK_SIZE => {
+        let data = lz4_flex::compress(&bytes);
+
+        if data.len() < bytes.len() {
+          Self::Compressed {
+            data: data.into(),
+            len: bytes.len(),
+          }
+        } else {
+          Self::Owned(bytes)
+        }
+      }
+      (None, _) => Self::Owned(bytes),
+    }
+  }
+
+  /// Returns the uncompressed length in bytes of the block.
+  fn len(&self) -> usize {
+    match self {
+      Self::Owned(bytes) => bytes.len(),
+      Self::Compressed { len, .. } => *len,
+      Self::Shared(blob) => blob.len(),
+    }
+  }
+
+  /// Returns the uncompressed bytes of the block.
+  fn bytes(&self) -> Cow<'_, [u8]> {
+    match self {
+      Self::Owned(bytes) => Cow::Borrowed(bytes),
+      Self::Compressed { data, len } => {
+        Cow::Owned(lz4_flex::decompress(data, *len).expect("compressed blocks are only created from valid lz4"))
+      }
+      Self::Shared(blob) => Cow::Borrowed(blob),
+    }
+  }
+
+  fn into_vec(self) -> Vec<u8> {
+    match self {
+      Self::Owned(bytes) => bytes,
+      block => block.bytes().into_owned(),
+    }
+  }
+}
@@ -3,6 +3,7 @@ use std::{collections::BTreeMap, ffi::OsString};
 use crate::{metadata::Metadata, Entry};
 
 /// A directory.
+#[derive(Clone)]
 pub struct Directory {
   pub metadata: Metadata,
 
@@ -1,11 +1,12 @@
 use std::{
-  borrow::Borrow,
+  borrow::{Borrow, BorrowMut},
   ffi::{OsStr, OsString},
 };
 
 use crate::{metadata::Metadata, Directory, File};
 
 /// An entry in a directory.
+#[derive(Clone)]
 pub enum Entry<F = File, D = Directory>
 where
   F: Borrow<File>,
@@ -45,6 +46,19 @@ where
   }
 }
 
+impl<F, D> Entry<F, D>
+where
+  F: BorrowMut<File>,
+  D: BorrowMut<Directory>,
+{
+  pub fn metadata_mut(&mut self) -> &mut Metadata {
+    match self {
+      Self::File(file) => &mut file.borrow_mut().metadata,
+      Self::Directory(directory) => &mut directory.borrow_mut().metadata,
+    }
+  }
+}
+
 impl Entry<File, Directory> {
   pub fn rename(&mut self, name: OsString) {
     match self {
@@ -66,9 +80,40 @@ impl<'a> From<&'a Entry> for Borrowed<'a> {
   }
 }
 
+impl<'a> Borrowed<'a> {
+  /// Returns this entry's metadata, borrowed for as long as the entry's inner structure.
+  #[must_use]
+  pub fn into_metadata(self) -> &'a Metadata {
+    match self {
+      Self::File(file) => &file.metadata,
+      Self::Directory(directory) => &directory.metadata,
+    }
+  }
+
+  /// Returns an owned deep copy of this entry.
+  #[must_use]
+  pub fn cloned(&self) -> Entry {
+    match self {
+      Self::Directory(directory) => Entry::Directory((*directory).clone()),
+      Self::File(file) => Entry::File((*file).clone()),
+    }
+  }
+}
+
 /// An entry with mutably borrowed inner structures.
 pub type MutBorrowed<'a> = Entry<&'a mut File, &'a mut Directory>;
 
+impl MutBorrowed<'_> {
+  /// Returns this entry immutably reborrowed.
+  #[must_use]
+  pub fn as_borrowed(&self) -> Borrowed<'_> {
+    match self {
+      Self::File(file) => Borrowed::File(file),
+      Self::Directory(directory) => Borrowed::Directory(directory),
+    }
+  }
+}
+
 impl<'a> From<&'a mut Entry> for MutBorrowed<'a> {
   fn from(entry: &'a mut Entry) -> Self {
     match entry {
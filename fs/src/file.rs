@@ -1,12 +1,17 @@
 use std::ffi::OsString;
 
-use crate::metadata::Metadata;
+use crate::{
+  content::{Content, TooLarge},
+  metadata::Metadata,
+  storage::BlobStore,
+};
 
 /// A file.
+#[derive(Clone)]
 pub struct File {
   pub metadata: Metadata,
 
-  pub content: Vec<u8>,
+  pub content: Content,
 }
 
 impl File {
@@ -15,7 +20,59 @@ impl File {
   pub fn new<S: Into<OsString>>(name: S) -> Self {
     Self {
       metadata: Metadata::new(name),
-      content: Vec::new(),
+      content: Content::default(),
     }
   }
+
+  /// Appends `content` to the file, updating its length. New blocks are stored in `store` if one
+  /// is given.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error, leaving the file unchanged, if its length would overflow.
+  pub fn append(&mut self, content: &[u8], store: Option<&BlobStore>) -> Result<(), TooLarge> {
+    self.content.append(content, store)?;
+    self.update_metadata();
+
+    Ok(())
+  }
+
+  /// Writes `content` at `offset`, updating the file's length. New blocks are stored in `store` if
+  /// one is given.
+  ///
+  /// See [`Content::write_at`].
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error, leaving the file unchanged, if the written range would
+  /// end past the largest offset a `u64` can hold.
+  pub fn write_at(&mut self, offset: u64, content: &[u8], store: Option<&BlobStore>) -> Result<(), TooLarge> {
+    self.content.write_at(offset, content, store)?;
+    self.update_metadata();
+
+    Ok(())
+  }
+
+  /// Sets the length of the file, updating its metadata.
+  ///
+  /// See [`Content::truncate`].
+  pub fn truncate(&mut self, len: u64, store: Option<&BlobStore>) {
+    self.content.truncate(len, store);
+    self.update_metadata();
+  }
+
+  /// Deallocates a range of the file, updating its metadata.
+  ///
+  /// See [`Content::punch_hole`].
+  pub fn punch_hole(&mut self, offset: u64, len: u64, store: Option<&BlobStore>) {
+    self.content.punch_hole(offset, len, store);
+    self.update_metadata();
+  }
+
+  /// Updates the length and allocated bytes in the file's metadata from its content, after the
+  /// content was changed directly.
+  pub fn update_metadata(&mut self) {
+    self.metadata.len = self.content.len();
+    self.metadata.allocated = self.content.allocated();
+  }
 }
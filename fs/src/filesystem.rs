@@ -1,15 +1,42 @@
-use crate::directory::Directory;
+use crate::{content::Compression, directory::Directory, limits::Limits, storage::Storage};
 
 /// A filesystem.
 pub struct Filesystem {
   pub root: Directory,
+
+  pub limits: Limits,
+
+  pub storage: Storage,
+
+  /// How the contents of newly created files are compressed.
+  pub compression: Compression,
 }
 
 impl Filesystem {
   /// Creates a new filesystem.
   #[must_use]
   pub fn new() -> Self {
-    Self { root: Directory::root() }
+    Self::with_limits(Limits::unlimited())
+  }
+
+  /// Creates a new filesystem with capacity limits.
+  #[must_use]
+  pub fn with_limits(limits: Limits) -> Self {
+    Self {
+      root: Directory::root(),
+      limits,
+      storage: Storage::Inline,
+      compression: Compression::None,
+    }
+  }
+
+  /// Creates a new filesystem whose file contents are deduplicated.
+  #[must_use]
+  pub fn deduplicated() -> Self {
+    Self {
+      storage: Storage::deduplicated(),
+      ..Self::new()
+    }
   }
 }
 
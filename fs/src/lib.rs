@@ -1,7 +1,18 @@
+pub mod content;
 pub mod directory;
 pub mod entry;
 pub mod file;
 pub mod filesystem;
+pub mod limits;
 pub mod metadata;
+pub mod storage;
 
-pub use self::{directory::Directory, entry::Entry, file::File, filesystem::Filesystem};
+pub use self::{
+  content::{Compression, Content, OutOfMemory, TooLarge},
+  directory::Directory,
+  entry::Entry,
+  file::File,
+  filesystem::Filesystem,
+  limits::Limits,
+  storage::Storage,
+};
@@ -0,0 +1,29 @@
+/// Capacity limits of a [`Filesystem`]. A limit of `None` means unlimited.
+///
+/// [`Filesystem`]: crate::filesystem::Filesystem
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Limits {
+  /// The maximum total length in bytes of all files.
+  pub max_bytes: Option<u64>,
+
+  /// The maximum number of entries, including the root directory.
+  pub max_entries: Option<u64>,
+
+  /// The maximum length in bytes of a single file.
+  pub max_file_size: Option<u64>,
+
+  /// The maximum length in bytes of an entry's name.
+  pub max_name_len: Option<usize>,
+
+  /// The maximum number of components in a path, not including the root.
+  pub max_depth: Option<usize>,
+}
+
+impl Limits {
+  /// Creates limits where everything is unlimited.
+  #[must_use]
+  pub fn unlimited() -> Self {
+    Self::default()
+  }
+}
@@ -1,4 +1,4 @@
-use std::ffi::OsString;
+use std::{collections::BTreeMap, ffi::OsString};
 
 use chrono::{DateTime, Utc};
 
@@ -6,18 +6,40 @@ use chrono::{DateTime, Utc};
 ///
 /// [`Directory`]: crate::directory::Directory
 /// [`File`]: crate::file::File
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Metadata {
   pub name: OsString,
 
   pub created_at: DateTime<Utc>,
+
+  /// When a file's content last changed, or when entries were last added to or removed from a
+  /// directory.
+  pub modified_at: DateTime<Utc>,
+
+  /// The length in bytes of a file's content. This is always `0` for directories.
+  pub len: u64,
+
+  /// The number of bytes of a file's content backed by stored blocks. This is less than `len` for
+  /// sparse files with holes, and always `0` for directories.
+  pub allocated: u64,
+
+  /// Extended attributes, mapping namespaced names like `user.tag` to arbitrary bytes.
+  pub xattrs: BTreeMap<String, Vec<u8>>,
 }
 
 impl Metadata {
   /// Creates a new metadata.
   pub fn new<S: Into<OsString>>(name: S) -> Self {
+    let now = Utc::now();
+
     Self {
       name: name.into(),
-      created_at: Utc::now(),
+      created_at: now,
+      modified_at: now,
+      len: 0,
+      allocated: 0,
+      xattrs: BTreeMap::new(),
     }
   }
 }
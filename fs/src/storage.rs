@@ -0,0 +1,152 @@
+use std::{
+  collections::{hash_map::DefaultHasher, HashMap},
+  hash::{Hash, Hasher},
+  ops::Deref,
+  sync::{Arc, Mutex, MutexGuard, PoisonError, Weak},
+};
+
+/// How the contents of files in a [`Filesystem`] are stored.
+///
+/// [`Filesystem`]: crate::filesystem::Filesystem
+#[derive(Clone, Default)]
+pub enum Storage {
+  /// Each file owns its content.
+  #[default]
+  Inline,
+
+  /// File contents are split into blocks that are shared between all files through a
+  /// [`BlobStore`], so identical blocks are only stored once.
+  Deduplicated(BlobStore),
+}
+
+impl Storage {
+  /// Creates a deduplicated storage with an empty [`BlobStore`].
+  #[must_use]
+  pub fn deduplicated() -> Self {
+    Self::Deduplicated(BlobStore::default())
+  }
+
+  /// Returns the blob store if this storage is deduplicated.
+  #[must_use]
+  pub fn blob_store(&self) -> Option<&BlobStore> {
+    match self {
+      Self::Inline => None,
+      Self::Deduplicated(store) => Some(store),
+    }
+  }
+}
+
+/// Statistics about the blobs in a [`BlobStore`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlobStats {
+  /// The number of distinct blobs.
+  pub blobs: u64,
+
+  /// The total length in bytes of all distinct blobs.
+  pub bytes: u64,
+}
+
+/// A content-addressed store of reference-counted [`Blob`]s.
+///
+/// The store only weakly references its blobs. A blob is removed from the store once every file
+/// referencing it is dropped.
+#[derive(Clone, Default)]
+pub struct BlobStore {
+  inner: Arc<Mutex<Blobs>>,
+}
+
+impl BlobStore {
+  /// Returns a blob containing `data`, reusing an existing blob with identical content if one exists.
+  #[must_use]
+  pub fn intern(&self, data: &[u8]) -> Arc<Blob> {
+    let hash = hash(data);
+
+    // blobs that don't match must be dropped after the lock is released, as dropping the last
+    // reference to a blob removes it from the store
+    let mut mismatched = Vec::new();
+
+    let mut blobs = lock(&self.inner);
+    let bucket = blobs.buckets.entry(hash).or_default();
+
+    for blob in bucket.iter().filter_map(Weak::upgrade) {
+      if *blob.data == *data {
+        return blob;
+      }
+
+      mismatched.push(blob);
+    }
+
+    let blob = Arc::new(Blob {
+      hash,
+      data: data.into(),
+      store: Arc::downgrade(&self.inner),
+    });
+
+    bucket.push(Arc::downgrade(&blob));
+    blobs.stats.blobs += 1;
+    blobs.stats.bytes += data.len() as u64;
+
+    blob
+  }
+
+  /// Returns statistics about the blobs currently in the store.
+  #[must_use]
+  pub fn stats(&self) -> BlobStats {
+    lock(&self.inner).stats
+  }
+}
+
+/// The blobs of a [`BlobStore`], bucketed by the hash of their content.
+#[derive(Default)]
+struct Blobs {
+  buckets: HashMap<u64, Vec<Weak<Blob>>>,
+
+  stats: BlobStats,
+}
+
+/// An immutable block of bytes in a [`BlobStore`].
+pub struct Blob {
+  hash: u64,
+
+  data: Box<[u8]>,
+
+  store: Weak<Mutex<Blobs>>,
+}
+
+impl Deref for Blob {
+  type Target = [u8];
+
+  fn deref(&self) -> &[u8] {
+    &self.data
+  }
+}
+
+impl Drop for Blob {
+  fn drop(&mut self) {
+    let Some(store) = self.store.upgrade() else {
+      return;
+    };
+
+    let mut blobs = lock(&store);
+    blobs.stats.blobs -= 1;
+    blobs.stats.bytes -= self.data.len() as u64;
+
+    if let Some(bucket) = blobs.buckets.get_mut(&self.hash) {
+      bucket.retain(|blob| blob.strong_count() > 0);
+
+      if bucket.is_empty() {
+        blobs.buckets.remove(&self.hash);
+      }
+    }
+  }
+}
+
+fn hash(data: &[u8]) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  data.hash(&mut hasher);
+  hasher.finish()
+}
+
+fn lock(blobs: &Mutex<Blobs>) -> MutexGuard<'_, Blobs> {
+  blobs.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,332 @@
+use std::mem;
+
+use regex::Regex;
+
+/// What the caller of [`Ed::feed`] should do after a line.
+pub enum Status {
+  /// Read the next line.
+  Continue,
+
+  /// Write the buffer to a file, the edited file if no path is given, then quit if `quit`.
+  Write { path: Option<String>, quit: bool },
+
+  /// Stop editing.
+  Quit,
+}
+
+/// Whether lines are commands or text being added to the buffer.
+enum Mode {
+  Command,
+
+  /// Text lines are inserted at this index, until a line with only `.`.
+  Insert(usize),
+}
+
+/// A minimal `ed`-style line editor over an in-memory buffer.
+///
+/// Supports the addresses `N`, `.`, `$`, `+N`, `-N`, ranges like `1,3` and `,` for every line,
+/// and the commands `a`, `i`, `c`, `d`, `p`, `n`, `=`, `s/regex/replacement/[g]`, `w [path]`,
+/// `wq`, `q` and `Q`. An address without a command prints that line. Reading and writing files is
+/// left to the caller.
+pub struct Ed {
+  lines: Vec<String>,
+
+  /// The current line, starting at 1, or 0 if the buffer is empty.
+  current: usize,
+
+  mode: Mode,
+
+  modified: bool,
+
+  /// Whether `q` was refused because of unsaved changes, so that a second `q` quits anyway.
+  warned: bool,
+}
+
+impl Ed {
+  /// Creates an editor over `content`, with the last line current.
+  pub fn new(content: &[u8]) -> Self {
+    let lines: Vec<_> = String::from_utf8_lossy(content).lines().map(str::to_owned).collect();
+
+    Self {
+      current: lines.len(),
+      lines,
+      mode: Mode::Command,
+      modified: false,
+      warned: false,
+    }
+  }
+
+  /// Returns the buffer with a newline after every line.
+  pub fn content(&self) -> Vec<u8> {
+    let mut content = Vec::new();
+
+    for line in &self.lines {
+      content.extend_from_slice(line.as_bytes());
+      content.push(b'\n');
+    }
+
+    content
+  }
+
+  /// Marks the buffer as saved after it was written.
+  pub fn saved(&mut self) {
+    self.modified = false;
+  }
+
+  /// Handles a command or text line, appending the lines it prints to `printed`. Errors are
+  /// returned for the caller to report and don't end editing.
+  pub fn feed(&mut self, line: &str, printed: &mut Vec<String>) -> Result<Status, String> {
+    if let Mode::Insert(at) = self.mode {
+      if line == "." {
+        self.mode = Mode::Command;
+      } else {
+        self.lines.insert(at, line.to_owned());
+        self.current = at + 1;
+        self.mode = Mode::Insert(at + 1);
+        self.modified = true;
+      }
+
+      return Ok(Status::Continue);
+    }
+
+    let warned = mem::take(&mut self.warned);
+
+    self.command(line, warned, printed)
+  }
+
+  fn command(&mut self, line: &str, warned: bool, printed: &mut Vec<String>) -> Result<Status, String> {
+    let mut rest = line.trim_start();
+    let range = self.range(&mut rest)?;
+    let (command, args) = rest.split_at(rest.chars().next().map_or(0, char::len_utf8));
+
+    let (start, end) = match range {
+      Some(range) => range,
+      None => match command {
+        // without an address, print the next line
+        "" => (self.current + 1, self.current + 1),
+        "=" => (self.lines.len(), self.lines.len()),
+        _ => (self.current, self.current),
+      },
+    };
+
+    if !matches!(command, "s" | "w") && !args.trim().is_empty() {
+      return Err(format!("unexpected `{args}`"));
+    }
+
+    let check = |start: usize, end: usize| {
+      if start == 0 || end > self.lines.len() {
+        Err("invalid address".to_string())
+      } else {
+        Ok(())
+      }
+    };
+
+    match command {
+      "" => {
+        check(end, end)?;
+        self.current = end;
+        printed.push(self.lines[end - 1].clone());
+      }
+      "a" => self.mode = Mode::Insert(end),
+      "i" => self.mode = Mode::Insert(end.saturating_sub(1)),
+      "c" => {
+        check(start, end)?;
+        self.lines.drain(start - 1..end);
+        self.modified = true;
+        self.mode = Mode::Insert(start - 1);
+      }
+      "d" => {
+        check(start, end)?;
+        self.lines.drain(start - 1..end);
+        self.current = start.min(self.lines.len());
+        self.modified = true;
+      }
+      "p" | "n" => {
+        check(start, end)?;
+
+        for number in start..=end {
+          let line = &self.lines[number - 1];
+          printed.push(if command == "n" {
+            format!("{number}\t{line}")
+          } else {
+            line.clone()
+          });
+        }
+
+        self.current = end;
+      }
+      "=" => printed.push(end.to_string()),
+      "s" => {
+        check(start, end)?;
+        self.substitute(start, end, args)?;
+      }
+      "w" => {
+        let (quit, path) = match args.strip_prefix('q') {
+          Some(path) => (true, path.trim()),
+          None => (false, args.trim()),
+        };
+
+        return Ok(Status::Write {
+          path: (!path.is_empty()).then(|| path.to_owned()),
+          quit,
+        });
+      }
+      "q" if self.modified && !warned => {
+        self.warned = true;
+        return Err("warning: buffer modified".to_string());
+      }
+      "q" | "Q" => return Ok(Status::Quit),
+      _ => return Err(format!("unknown command `{rest}`")),
+    }
+
+    Ok(Status::Continue)
+  }
+
+  /// Parses the address range at the start of `rest`, advancing past it.
+  fn range(&self, rest: &mut &str) -> Result<Option<(usize, usize)>, String> {
+    if let Some(after) = rest.strip_prefix(',') {
+      *rest = after;
+
+      return Ok(Some((1.min(self.lines.len()), self.lines.len())));
+    }
+
+    let Some(start) = self.address(rest)? else {
+      return Ok(None);
+    };
+
+    let end = match rest.strip_prefix(',') {
+      Some(after) => {
+        *rest = after;
+        self.address(rest)?.ok_or("invalid address")?
+      }
+      None => start,
+    };
+
+    if start > end {
+      return Err("invalid address".to_string());
+    }
+
+    Ok(Some((start, end)))
+  }
+
+  /// Parses a single address at the start of `rest`, advancing past it.
+  fn address(&self, rest: &mut &str) -> Result<Option<usize>, String> {
+    let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+
+    let address = match rest.chars().next() {
+      Some('.') => {
+        *rest = &rest[1..];
+        self.current
+      }
+      Some('$') => {
+        *rest = &rest[1..];
+        self.lines.len()
+      }
+      Some(sign @ ('+' | '-')) => {
+        let len = digits(&rest[1..]);
+        let offset = if len == 0 {
+          1
+        } else {
+          rest[1..=len].parse().map_err(|_| "invalid address")?
+        };
+        *rest = &rest[1 + len..];
+
+        let address = if sign == '+' {
+          self.current.checked_add(offset)
+        } else {
+          self.current.checked_sub(offset)
+        };
+
+        address.ok_or("invalid address")?
+      }
+      Some(c) if c.is_ascii_digit() => {
+        let len = digits(rest);
+        let address = rest[..len].parse().map_err(|_| "invalid address")?;
+        *rest = &rest[len..];
+
+        address
+      }
+      _ => return Ok(None),
+    };
+
+    if address > self.lines.len() {
+      return Err("invalid address".to_string());
+    }
+
+    Ok(Some(address))
+  }
+
+  /// Runs `s/regex/replacement/[g]` on the lines `start..=end`, where `&` in the replacement is
+  /// the match and `\N` is the Nth group.
+  fn substitute(&mut self, start: usize, end: usize, args: &str) -> Result<(), String> {
+    let mut chars = args.chars();
+    let delimiter = chars
+      .next()
+      .filter(|c| !c.is_alphanumeric() && !c.is_whitespace())
+      .ok_or("invalid substitution")?;
+
+    let parts: Vec<_> = chars.as_str().splitn(3, delimiter).collect();
+    let [pattern, replacement, flags] = parts[..] else {
+      return Err("invalid substitution".to_string());
+    };
+
+    let global = match flags {
+      "" => false,
+      "g" => true,
+      _ => return Err(format!("unknown flags `{flags}`")),
+    };
+
+    let regex = Regex::new(pattern).map_err(|_| format!("invalid pattern `{pattern}`"))?;
+    let replacement = replacement_template(replacement);
+
+    let mut found = false;
+
+    for number in start..=end {
+      let line = &mut self.lines[number - 1];
+      if !regex.is_match(line) {
+        continue;
+      }
+
+      *line = if global {
+        regex.replace_all(line, replacement.as_str())
+      } else {
+        regex.replace(line, replacement.as_str())
+      }
+      .into_owned();
+
+      found = true;
+      self.current = number;
+    }
+
+    if !found {
+      return Err("no match".to_string());
+    }
+
+    self.modified = true;
+
+    Ok(())
+  }
+}
+
+/// Converts an `ed` replacement, where `&` is the match and `\N` is the Nth group, to a
+/// replacement for [`Regex::replace`].
+fn replacement_template(replacement: &str) -> String {
+  let mut template = String::new();
+  let mut chars = replacement.chars();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '&' => template.push_str("${0}"),
+      '$' => template.push_str("$$"),
+      '\\' => match chars.next() {
+        Some(digit @ '0'..='9') => template.push_str(&format!("${{{digit}}}")),
+        Some('$') => template.push_str("$$"),
+        Some(c) => template.push(c),
+        None => template.push('\\'),
+      },
+      c => template.push(c),
+    }
+  }
+
+  template
+}
@@ -0,0 +1,133 @@
+use std::path::Path;
+
+use rustyline::{
+  completion::{Completer, Pair},
+  highlight::Highlighter,
+  hint::Hinter,
+  validate::Validator,
+  Context,
+};
+use session::SessionApi;
+
+/// Characters that end a word on a command line, besides whitespace.
+const OPERATORS: [char; 5] = [';', '&', '|', '<', '>'];
+
+/// A line editor helper completing command names and paths in the session.
+pub struct Helper<'a> {
+  pub session: &'a dyn SessionApi,
+
+  pub commands: &'a [String],
+}
+
+impl Helper<'_> {
+  /// Returns the entries of the directory part of `word` whose names start with its last
+  /// component, with a `/` after directories.
+  fn complete_path(&self, word: &str) -> Vec<Pair> {
+    let (directory, prefix) = match word.rfind('/') {
+      Some(i) => (&word[..=i], &word[i + 1..]),
+      None => ("", word),
+    };
+
+    let Ok(entries) = self
+      .session
+      .read_dir_with_metadata(Path::new(if directory.is_empty() { "." } else { directory }))
+    else {
+      return Vec::new();
+    };
+
+    entries
+      .into_iter()
+      .filter_map(|entry| {
+        let name = entry.name.to_str()?;
+        if !name.starts_with(prefix) {
+          return None;
+        }
+
+        let suffix = if entry.is_directory { "/" } else { "" };
+
+        Some(Pair {
+          display: format!("{name}{suffix}"),
+          replacement: format!("{}{}{suffix}", escape(directory), escape(name)),
+        })
+      })
+      .collect()
+  }
+}
+
+impl Completer for Helper<'_> {
+  type Candidate = Pair;
+
+  fn complete(&self, line: &str, pos: usize, _: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+    let line = &line[..pos];
+    let (start, word) = current_word(line);
+
+    // the first word of each command is a command name
+    let before = line[..start].trim_end();
+    if before.is_empty() || before.ends_with(OPERATORS) {
+      let commands = self
+        .commands
+        .iter()
+        .filter(|command| command.starts_with(&word))
+        .map(|command| Pair {
+          display: command.clone(),
+          replacement: format!("{command} "),
+        })
+        .collect();
+
+      return Ok((start, commands));
+    }
+
+    let mut paths = self.complete_path(&word);
+    paths.sort_by(|a, b| a.display.cmp(&b.display));
+
+    Ok((start, paths))
+  }
+}
+
+impl Hinter for Helper<'_> {
+  type Hint = String;
+}
+
+impl Highlighter for Helper<'_> {}
+
+impl Validator for Helper<'_> {}
+
+impl rustyline::Helper for Helper<'_> {}
+
+/// Returns the start of the last word of `line` and the word with quotes and escapes removed.
+fn current_word(line: &str) -> (usize, String) {
+  let mut start = 0;
+  let mut word = String::new();
+  let mut quote = None;
+  let mut chars = line.char_indices();
+
+  while let Some((i, c)) = chars.next() {
+    match (quote, c) {
+      (None, '\\') => word.extend(chars.next().map(|(_, c)| c)),
+      (None, '\'' | '"') => quote = Some(c),
+      (Some(q), c) if c == q => quote = None,
+      (None, c) if c.is_whitespace() || OPERATORS.contains(&c) => {
+        start = i + c.len_utf8();
+        word.clear();
+      }
+      (_, c) => word.push(c),
+    }
+  }
+
+  (start, word)
+}
+
+/// Escapes the characters of `name` that the shell would otherwise interpret.
+fn escape(name: &str) -> String {
+  let mut escaped = String::with_capacity(name.len());
+
+  for c in name.chars() {
+    if c.is_whitespace() || OPERATORS.contains(&c) || matches!(c, '\'' | '"' | '\\' | '$' | '#') {
+      escaped.push('\\');
+    }
+
+    escaped.push(c);
+  }
+
+  escaped
+}
@@ -0,0 +1,54 @@
+use std::{
+  io,
+  path::{Path, PathBuf},
+};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+  #[error(transparent)]
+  Session(#[from] session::Error),
+
+  #[error(transparent)]
+  Io(#[from] io::Error),
+
+  /// A command was used incorrectly, e.g. with invalid arguments.
+  #[error("{0}")]
+  Usage(String),
+
+  /// A file is too large to be read whole into memory.
+  #[error("{0:?} is too large to hold in memory")]
+  TooLarge(PathBuf),
+
+  #[error("script {0:?} failed")]
+  Script(PathBuf),
+
+  /// Redirected output was only partly written to a file.
+  #[error("short write: wrote {written} of {len} bytes to {path:?}")]
+  ShortWrite { path: PathBuf, written: usize, len: usize },
+}
+
+impl Error {
+  /// Returns a stable code identifying the kind of this error, using [`session::Error::code`] for
+  /// errors of the session.
+  pub fn code(&self) -> &'static str {
+    match self {
+      Self::Session(err) => err.code(),
+      Self::Io(_) => "io",
+      Self::Usage(_) => "usage",
+      Self::TooLarge(_) => "too_large",
+      Self::Script(_) => "script_failed",
+      Self::ShortWrite { .. } => "short_write",
+    }
+  }
+
+  /// Returns the path this error is about, if any.
+  pub fn path(&self) -> Option<&Path> {
+    match self {
+      Self::Session(err) => err.path(),
+      Self::TooLarge(path) | Self::Script(path) | Self::ShortWrite { path, .. } => Some(path),
+      Self::Io(_) | Self::Usage(_) => None,
+    }
+  }
+}
@@ -0,0 +1,151 @@
+use std::cmp::Ordering;
+
+/// Returns the lines of `input`, each with its trailing newline if it has one.
+fn lines(input: &[u8]) -> impl DoubleEndedIterator<Item = &[u8]> {
+  input.split_inclusive(|byte| *byte == b'\n')
+}
+
+/// Returns `line` without its trailing newline.
+fn trim_newline(line: &[u8]) -> &[u8] {
+  line.strip_suffix(b"\n").unwrap_or(line)
+}
+
+/// The number of lines, words and bytes of input counted a chunk at a time, like `wc`. Only
+/// newlines are counted as lines, so a last line without one isn't counted.
+#[derive(Default)]
+pub struct Count {
+  pub lines: usize,
+  pub words: usize,
+  pub bytes: usize,
+
+  /// Whether the input counted so far ends in the middle of a word.
+  in_word: bool,
+}
+
+impl Count {
+  /// Counts `chunk`, the input following what was counted so far.
+  pub fn update(&mut self, chunk: &[u8]) {
+    for byte in chunk {
+      let space = byte.is_ascii_whitespace();
+
+      if !space && !self.in_word {
+        self.words += 1;
+      }
+
+      self.in_word = !space;
+    }
+
+    self.lines += chunk.iter().filter(|byte| **byte == b'\n').count();
+    self.bytes += chunk.len();
+  }
+}
+
+/// Returns the first `n` lines of `input`.
+pub fn head(input: &[u8], mut n: usize) -> &[u8] {
+  &input[..head_len(input, &mut n)]
+}
+
+/// Returns the length of the start of `chunk` holding at most `n` more lines, and subtracts the
+/// lines it ends from `n`, so that the first lines of input can be taken a chunk at a time.
+pub fn head_len(chunk: &[u8], n: &mut usize) -> usize {
+  let mut len = 0;
+
+  for line in lines(chunk) {
+    if *n == 0 {
+      break;
+    }
+
+    len += line.len();
+
+    if line.ends_with(b"\n") {
+      *n -= 1;
+    }
+  }
+
+  len
+}
+
+/// Returns the last `n` lines of `input`.
+pub fn tail(input: &[u8], n: usize) -> &[u8] {
+  let len: usize = lines(input).rev().take(n).map(<[u8]>::len).sum();
+
+  &input[input.len() - len..]
+}
+
+/// Returns the lines of `input` sorted bytewise, or by the number at their start if `numeric`,
+/// with lines without a number first. With `unique`, only the first of equal lines is kept.
+/// Every line of the output ends with a newline.
+pub fn sort(input: &[u8], reverse: bool, numeric: bool, unique: bool) -> Vec<u8> {
+  let compare = |a: &&[u8], b: &&[u8]| {
+    if numeric {
+      leading_number(a).total_cmp(&leading_number(b))
+    } else {
+      a.cmp(b)
+    }
+  };
+
+  let mut sorted: Vec<_> = lines(input).map(trim_newline).collect();
+  sorted.sort_by(|a, b| compare(a, b).then_with(|| a.cmp(b)));
+
+  if unique {
+    sorted.dedup_by(|a, b| compare(&&**a, &&**b) == Ordering::Equal);
+  }
+
+  if reverse {
+    sorted.reverse();
+  }
+
+  join(sorted)
+}
+
+/// Returns `input` with runs of equal adjacent lines replaced by one line, prefixed with the
+/// length of the run if `count`. Every line of the output ends with a newline.
+pub fn uniq(input: &[u8], count: bool) -> Vec<u8> {
+  let mut runs: Vec<(&[u8], usize)> = Vec::new();
+
+  for line in lines(input).map(trim_newline) {
+    match runs.last_mut() {
+      Some((last, n)) if *last == line => *n += 1,
+      _ => runs.push((line, 1)),
+    }
+  }
+
+  if !count {
+    return join(runs.into_iter().map(|(line, _)| line));
+  }
+
+  let mut output = Vec::new();
+
+  for (line, n) in runs {
+    output.extend_from_slice(format!("{n:>7} ").as_bytes());
+    output.extend_from_slice(line);
+    output.push(b'\n');
+  }
+
+  output
+}
+
+/// Returns the number at the start of `line`, after any whitespace, or `-inf` if there is none.
+fn leading_number(line: &[u8]) -> f64 {
+  let line = String::from_utf8_lossy(line);
+  let line = line.trim_start();
+
+  let end = line
+    .char_indices()
+    .find(|(i, c)| !(c.is_ascii_digit() || *c == '.' || (*i == 0 && *c == '-')))
+    .map_or(line.len(), |(i, _)| i);
+
+  line[..end].parse().unwrap_or(f64::NEG_INFINITY)
+}
+
+/// Joins `lines`, ending each with a newline.
+fn join<'a>(lines: impl IntoIterator<Item = &'a [u8]>) -> Vec<u8> {
+  let mut output = Vec::new();
+
+  for line in lines {
+    output.extend_from_slice(line);
+    output.push(b'\n');
+  }
+
+  output
+}
@@ -0,0 +1,242 @@
+use std::{cmp::Ordering, iter::Peekable, path::PathBuf, vec::IntoIter};
+
+use chrono::{DateTime, Duration, Utc};
+use session::{
+  find::{EntryType, Predicate},
+  walk::WalkOptions,
+  SessionApi,
+};
+
+/// What to do with each entry matched by `find`.
+pub enum Action {
+  Print,
+  Delete,
+
+  /// Run a repl command, with each `{}` in its arguments replaced by the entry's path.
+  Exec(Vec<String>),
+}
+
+/// A parsed `find` command line: `find [path] [expression]`.
+///
+/// Actions in the expression always match, and are applied to every entry matching the
+/// expression as a whole.
+pub struct Find {
+  pub path: PathBuf,
+
+  pub predicate: Predicate,
+
+  pub options: WalkOptions,
+
+  pub actions: Vec<Action>,
+}
+
+impl Find {
+  /// Parses the arguments of `find`, resolving paths referenced by tests like `-newer` in
+  /// `session`.
+  pub fn parse(args: Vec<String>, session: &dyn SessionApi) -> Result<Self, String> {
+    let mut args = args.into_iter().peekable();
+
+    let path = match args.peek() {
+      Some(arg) if !arg.starts_with('-') && arg != "!" && arg != "(" => PathBuf::from(args.next().unwrap()),
+      _ => PathBuf::from("."),
+    };
+
+    let mut parser = Parser {
+      args,
+      session,
+      now: Utc::now(),
+      options: WalkOptions::new(),
+      actions: Vec::new(),
+      delete: false,
+    };
+
+    let predicate = if parser.args.peek().is_some() {
+      parser.or()?
+    } else {
+      Predicate::True
+    };
+
+    if let Some(arg) = parser.args.next() {
+      return Err(format!("find: unexpected argument {arg:?}"));
+    }
+
+    if parser.actions.is_empty() {
+      parser.actions.push(Action::Print);
+    }
+
+    Ok(Self {
+      path,
+      predicate,
+      // entries are deleted before their parent directories
+      options: parser.options.contents_first(parser.delete),
+      actions: parser.actions,
+    })
+  }
+}
+
+/// A recursive descent parser of `find` expressions, where `-not` binds tighter than `-and`,
+/// which binds tighter than `-or`.
+struct Parser<'a> {
+  args: Peekable<IntoIter<String>>,
+
+  session: &'a dyn SessionApi,
+
+  now: DateTime<Utc>,
+
+  options: WalkOptions,
+
+  actions: Vec<Action>,
+
+  delete: bool,
+}
+
+impl Parser<'_> {
+  fn or(&mut self) -> Result<Predicate, String> {
+    let mut predicate = self.and()?;
+
+    while self.args.next_if(|arg| arg == "-o" || arg == "-or").is_some() {
+      predicate = predicate.or(self.and()?);
+    }
+
+    Ok(predicate)
+  }
+
+  fn and(&mut self) -> Result<Predicate, String> {
+    let mut predicate = self.not()?;
+
+    loop {
+      match self.args.peek().map(String::as_str) {
+        None | Some("-o" | "-or" | ")") => return Ok(predicate),
+        Some("-a" | "-and") => {
+          self.args.next();
+        }
+        Some(_) => (),
+      }
+
+      predicate = predicate.and(self.not()?);
+    }
+  }
+
+  fn not(&mut self) -> Result<Predicate, String> {
+    if self.args.next_if(|arg| arg == "!" || arg == "-not").is_some() {
+      return Ok(!self.not()?);
+    }
+
+    if self.args.next_if(|arg| arg == "(").is_some() {
+      let predicate = self.or()?;
+
+      return match self.args.next() {
+        Some(arg) if arg == ")" => Ok(predicate),
+        _ => Err("find: expected `)`".to_string()),
+      };
+    }
+
+    self.primary()
+  }
+
+  fn primary(&mut self) -> Result<Predicate, String> {
+    let arg = self.args.next().ok_or("find: expected an expression")?;
+
+    let predicate = match arg.as_str() {
+      "-name" => Predicate::name(&self.value(&arg)?).map_err(|err| err.to_string())?,
+      "-type" => match self.value(&arg)?.as_str() {
+        "f" => Predicate::Type(EntryType::File),
+        "d" => Predicate::Type(EntryType::Directory),
+        kind => return Err(format!("find: unknown type {kind:?}")),
+      },
+      "-size" => {
+        let size = self.value(&arg)?;
+        let (ordering, size) = parse_comparison(&size)?;
+        let size = crate::util::parse_size(size.trim_end_matches('c'))?;
+
+        Predicate::Size(ordering, size)
+      }
+      "-newer" => {
+        let entry = self.session.stat(self.value(&arg)?.as_ref()).map_err(|err| err.to_string())?;
+
+        Predicate::Modified(Ordering::Greater, entry.modified_at)
+      }
+      "-mtime" => {
+        let days = self.value(&arg)?;
+        let (ordering, days) = parse_comparison(&days)?;
+        let days: i64 = days.parse().map_err(|_| format!("find: invalid number of days {days:?}"))?;
+        let ago = |days| Predicate::Modified(Ordering::Greater, self.now - Duration::days(days));
+
+        // like find, ages are rounded down to whole days
+        match ordering {
+          Ordering::Less => ago(days),
+          Ordering::Equal => ago(days + 1).and(!ago(days)),
+          Ordering::Greater => !ago(days + 1),
+        }
+      }
+      "-empty" => Predicate::Empty,
+      "-true" => Predicate::True,
+      "-false" => !Predicate::True,
+      "-maxdepth" | "-mindepth" => {
+        let depth = self.value(&arg)?;
+        let depth = depth.parse().map_err(|_| format!("find: invalid depth {depth:?}"))?;
+        let options = std::mem::take(&mut self.options);
+
+        self.options = if arg == "-maxdepth" {
+          options.max_depth(depth)
+        } else {
+          options.min_depth(depth)
+        };
+
+        Predicate::True
+      }
+      "-print" => {
+        self.actions.push(Action::Print);
+        Predicate::True
+      }
+      "-delete" => {
+        self.actions.push(Action::Delete);
+        self.delete = true;
+        Predicate::True
+      }
+      "-exec" => {
+        let mut command = Vec::new();
+
+        loop {
+          match self.args.next() {
+            Some(arg) if arg == ";" => break,
+            Some(arg) => command.push(arg),
+            None => return Err("find: missing `;` after -exec".to_string()),
+          }
+        }
+
+        if command.is_empty() {
+          return Err("find: -exec requires a command".to_string());
+        }
+
+        self.actions.push(Action::Exec(command));
+        Predicate::True
+      }
+      _ => return Err(format!("find: unknown predicate {arg:?}")),
+    };
+
+    Ok(predicate)
+  }
+
+  /// Returns the value of the test `arg`.
+  fn value(&mut self, arg: &str) -> Result<String, String> {
+    self.args.next().ok_or_else(|| format!("find: missing argument to {arg}"))
+  }
+}
+
+/// Splits a `+` or `-` prefix from a numeric argument, meaning greater or less than the number.
+fn parse_comparison(arg: &str) -> Result<(Ordering, &str), String> {
+  let comparison = match arg.strip_prefix('+') {
+    Some(number) => (Ordering::Greater, number),
+    None => match arg.strip_prefix('-') {
+      Some(number) => (Ordering::Less, number),
+      None => (Ordering::Equal, arg),
+    },
+  };
+
+  if comparison.1.is_empty() {
+    return Err(format!("find: invalid argument {arg:?}"));
+  }
+
+  Ok(comparison)
+}
@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use serde_json::{json, Map, Value};
+use session::listing::ListEntry;
+
+use crate::error::Result;
+
+/// Returns the document reporting a command in JSON output.
+///
+/// Successful commands have `"ok": true` and their structured `result`, if any. Failed commands
+/// have `"ok": false` and an `error` with a `code`, a `message` and the `path` it is about, if
+/// any. Text the command wrote is included as `output`, unless there was none.
+pub fn document(command: Option<&str>, res: &Result<Option<Value>>, output: &[u8]) -> Value {
+  let mut document = Map::new();
+  document.insert("ok".to_string(), res.is_ok().into());
+
+  if let Some(command) = command {
+    document.insert("command".to_string(), command.into());
+  }
+
+  match res {
+    Ok(Some(result)) => {
+      document.insert("result".to_string(), result.clone());
+    }
+    Ok(None) => (),
+    Err(err) => {
+      document.insert("error".to_string(), error(err.code(), &err.to_string(), err.path()));
+    }
+  }
+
+  if !output.is_empty() {
+    document.insert("output".to_string(), String::from_utf8_lossy(output).into());
+  }
+
+  document.into()
+}
+
+/// Returns the `error` object of a document.
+pub fn error(code: &str, message: &str, path: Option<&Path>) -> Value {
+  let mut error = json!({ "code": code, "message": message });
+
+  if let Some(path) = path {
+    error["path"] = path_string(path).into();
+  }
+
+  error
+}
+
+/// Returns `path` as a string, replacing invalid UTF-8.
+pub fn path_string(path: &Path) -> String {
+  path.to_string_lossy().into_owned()
+}
+
+/// Returns an entry of a listing with all of its metadata.
+pub fn list_entry(name: &str, entry: &ListEntry) -> Value {
+  json!({
+    "name": name,
+    "type": if entry.is_directory { "directory" } else { "file" },
+    "mode": entry.mode_string(),
+    "links": entry.links,
+    "owner": entry.owner,
+    "size": entry.len,
+    "allocated": entry.allocated,
+    "created_at": entry.created_at.to_rfc3339(),
+    "modified_at": entry.modified_at.to_rfc3339(),
+  })
+}
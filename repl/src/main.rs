@@ -1,14 +1,97 @@
+mod ed;
+mod editor;
+mod error;
+mod filter;
+mod find;
+mod json;
+mod shell;
+mod text;
 mod util;
 
-use std::{ffi::OsString, io::Write, path::PathBuf};
+use std::{
+  borrow::Cow,
+  cmp::Reverse,
+  collections::BTreeMap,
+  io::{self, BufRead, IsTerminal, Write},
+  mem,
+  num::ParseIntError,
+  path::{Path, PathBuf},
+  process::ExitCode,
+  str::FromStr,
+};
 
-use clap::Parser;
-use fs::Filesystem;
-use session::{Result, Session};
+use clap::{Args as ClapArgs, CommandFactory, Parser, Subcommand, ValueEnum};
+use ed::{Ed, Status as EdStatus};
+use editor::Helper;
+use error::{Error, Result};
+use find::{Action, Find};
+use fs::{Compression, Filesystem};
+use rustyline::{
+  error::ReadlineError,
+  history::{FileHistory, History},
+  CompletionType, Config, Editor,
+};
+use serde_json::{json, Value};
+use server::Client;
+use session::{
+  fault::{Fault, Operation, Rule, Trigger},
+  generate::{ContentGenerator, FileSize, Pattern, TreeGenerator},
+  grep::{GrepOptions, Line},
+  listing::ListEntry,
+  tree::{TreeNode, TreeOptions},
+  Session, SessionApi,
+};
+use shell::{Connector, Pipeline, Redirect, SimpleCommand, Word};
+use util::{Buffer, Stdout, MAX_BUFFER_LEN};
+
+/// An in-memory filesystem repl.
+///
+/// Commands are read interactively, or, without a prompt, from `-c`, a script file on the host or
+/// piped stdin. Scripts stop at the first failing command with a non-zero exit code. The
+/// filesystem is private to the repl, unless `--connect` uses one shared by a server.
+#[derive(Parser)]
+struct Args {
+  /// A script file on the host to run.
+  script: Option<PathBuf>,
+
+  /// Commands to run.
+  #[clap(short, conflicts_with = "script")]
+  command: Option<String>,
+
+  /// Keep running a script after a command fails. The exit code is still non-zero.
+  #[clap(long)]
+  keep_going: bool,
+
+  /// Deduplicate file contents by storing identical blocks once.
+  #[clap(long)]
+  dedup: bool,
+
+  /// Compress the contents of new files in memory.
+  #[clap(long)]
+  compress: bool,
+
+  /// Use the filesystem shared by the server listening on this Unix domain socket instead of a
+  /// new one.
+  #[clap(long, conflicts_with_all = ["dedup", "compress"])]
+  connect: Option<PathBuf>,
+
+  /// The file on the host to load history from and append it to. Defaults to
+  /// `~/.memfs_history`.
+  #[clap(long)]
+  history: Option<PathBuf>,
+
+  /// Also append history to this file in the in-memory filesystem.
+  #[clap(long)]
+  memfs_history: Option<PathBuf>,
+
+  /// Print one JSON document per command instead of text, like after `set output json`.
+  #[clap(long)]
+  json: bool,
+}
 
 #[derive(Parser)]
 #[command(
-  override_usage = "A in-memory filesystem repl. Use ctrl-c to exit.",
+  override_usage = "A in-memory filesystem repl. Use ctrl-d to exit.",
   no_binary_name = true,
   disable_help_flag = true
 )]
@@ -19,23 +102,218 @@ enum Command {
   /// Change directory.
   Cd { path: PathBuf },
 
-  /// List directory entries.
-  Ls {
-    #[clap(default_value = ".")]
+  /// Print the current directory.
+  Pwd,
+
+  /// Print the canonical path of an existing file or directory.
+  Realpath {
     path: PathBuf,
+
+    /// Print the path relative to this directory instead.
+    #[clap(long)]
+    relative_to: Option<PathBuf>,
   },
 
+  /// List directory entries, or files. Paths can be globs like `/logs/*.log`.
+  #[command(disable_help_flag = true)]
+  Ls(LsArgs),
+
   /// Create a new directory.
   Mkdir { path: PathBuf },
 
   /// Creates an empty file.
   Touch { path: PathBuf },
 
-  /// Fills a file with random data.
-  Fill { path: PathBuf },
+  /// Appends generated content to a file, creating it if it doesn't exist.
+  Fill {
+    path: PathBuf,
+
+    /// The number of bytes to append, with an optional K, M, G or T suffix.
+    #[clap(long, default_value = "100", value_parser = crate::util::parse_size)]
+    size: u64,
+
+    /// The content to generate.
+    #[clap(long, value_enum, default_value_t = FillPattern::Ascii)]
+    pattern: FillPattern,
+
+    /// The seed of random content. A random seed is used if omitted.
+    #[clap(long)]
+    seed: Option<u64>,
+  },
+
+  /// Generate a tree of directories and files for testing.
+  ///
+  /// Every directory gets `--files` files named f0, f1, ... and, above `--depth`, `--fanout`
+  /// subdirectories named d0, d1, ....
+  GenTree {
+    path: PathBuf,
+
+    /// How many levels of subdirectories are below the root.
+    #[clap(long, default_value_t = 2)]
+    depth: usize,
+
+    /// How many subdirectories each directory has.
+    #[clap(long, default_value_t = 3)]
+    fanout: usize,
+
+    /// How many files each directory has.
+    #[clap(long, default_value_t = 3)]
+    files: usize,
+
+    /// The size of files: fixed like `4K`, uniform in a range like `1K-1M`, or exponentially
+    /// distributed with a mean like `exp:64K`.
+    #[clap(long, default_value = "1K", value_parser = crate::util::parse_file_size)]
+    file_size: FileSize,
+
+    /// The content of files.
+    #[clap(long, value_enum, default_value_t = FillPattern::Ascii)]
+    pattern: FillPattern,
+
+    /// The seed of file sizes and content.
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+  },
+
+  /// Set a file's length, discarding its end or extending it with a hole.
+  Truncate {
+    path: PathBuf,
+
+    /// The new length, with an optional K, M, G or T suffix.
+    #[clap(value_parser = crate::util::parse_size)]
+    size: u64,
+  },
+
+  /// Deallocate a range of a file, turning it into a hole.
+  Punch {
+    path: PathBuf,
+
+    #[clap(value_parser = crate::util::parse_size)]
+    offset: u64,
+
+    #[clap(value_parser = crate::util::parse_size)]
+    len: u64,
+  },
+
+  /// Print the next offset in a file containing data, or in a hole.
+  Seek {
+    path: PathBuf,
+
+    #[clap(value_parser = crate::util::parse_size)]
+    offset: u64,
+
+    /// Seek to the next hole instead of the next data.
+    #[clap(long)]
+    hole: bool,
+  },
+
+  /// Prints a file's content, or the input if no path is given.
+  Cat { path: Option<PathBuf> },
+
+  /// Count the lines, words and bytes of a file or the input.
+  Wc {
+    path: Option<PathBuf>,
+
+    /// Only count lines.
+    #[clap(short)]
+    lines: bool,
+
+    /// Only count words.
+    #[clap(short)]
+    words: bool,
+
+    /// Only count bytes.
+    #[clap(short = 'c')]
+    bytes: bool,
+  },
+
+  /// Print the first lines of a file or the input.
+  Head {
+    path: Option<PathBuf>,
+
+    /// The number of lines to print.
+    #[clap(short = 'n', default_value_t = 10)]
+    lines: usize,
+  },
+
+  /// Print the last lines of a file or the input.
+  Tail {
+    path: Option<PathBuf>,
+
+    /// The number of lines to print.
+    #[clap(short = 'n', default_value_t = 10)]
+    lines: usize,
+  },
+
+  /// Sort the lines of a file or the input.
+  Sort {
+    path: Option<PathBuf>,
+
+    /// Sort in reverse order.
+    #[clap(short)]
+    reverse: bool,
+
+    /// Compare the numbers at the start of lines.
+    #[clap(short)]
+    numeric: bool,
+
+    /// Only print the first of equal lines.
+    #[clap(short)]
+    unique: bool,
+  },
+
+  /// Remove adjacent duplicate lines of a file or the input.
+  Uniq {
+    path: Option<PathBuf>,
+
+    /// Prefix lines with how many times they occurred.
+    #[clap(short)]
+    count: bool,
+  },
+
+  /// Print arguments separated by spaces.
+  Echo {
+    /// Don't print a trailing newline.
+    #[clap(short = 'n')]
+    no_newline: bool,
 
-  /// Prints a file's content.
-  Cat { path: PathBuf },
+    /// Interpret backslash escapes like `\n`.
+    #[clap(short = 'e')]
+    escapes: bool,
+
+    #[clap(allow_hyphen_values = true, trailing_var_arg = true)]
+    args: Vec<String>,
+  },
+
+  /// Print arguments according to a format, e.g. `printf "%-8s %5d\n" name 42`.
+  Printf {
+    #[clap(allow_hyphen_values = true)]
+    format: String,
+
+    #[clap(allow_hyphen_values = true, trailing_var_arg = true)]
+    args: Vec<String>,
+  },
+
+  /// Copy the input to files and the output.
+  Tee {
+    paths: Vec<PathBuf>,
+
+    /// Append to the files instead of replacing their content.
+    #[clap(short)]
+    append: bool,
+  },
+
+  /// Edit a file with `ed`-style commands, read from the input or, if there is none, from the
+  /// terminal.
+  ///
+  /// Addresses: N, ., $, +N, -N, N,M and , for every line. Commands: a, i and c to add text up to
+  /// a line with only `.`, d, p, n, =, s/regex/replacement/[g], w [path], wq, q and Q.
+  Ed {
+    path: PathBuf,
+
+    /// Don't print the number of bytes read and written.
+    #[clap(short)]
+    silent: bool,
+  },
 
   /// Remove a directory or file.
   Rm { path: PathBuf },
@@ -44,112 +322,1785 @@ enum Command {
   /// as opposed to the source's new parent. This will overwrite the destination if one exists.
   Mv { src: PathBuf, dst: PathBuf },
 
+  /// Copy a file or directory. The destination will be the copy's name, as opposed to its
+  /// parent. This will overwrite the destination if one exists.
+  Cp { src: PathBuf, dst: PathBuf },
+
   /// List contents of directories in a tree-like format.
   Tree {
     #[clap(default_value = ".")]
     path: PathBuf,
+
+    /// Only descend this many levels below the path.
+    #[clap(short = 'L')]
+    level: Option<usize>,
+
+    /// Only list directories.
+    #[clap(short)]
+    directories: bool,
+
+    /// List entries whose names start with a `.`.
+    #[clap(short)]
+    all: bool,
+
+    /// Print the size of each file, and the total size of the files under each directory.
+    #[clap(long)]
+    du: bool,
+
+    /// Print the tree as JSON.
+    #[clap(short = 'J')]
+    json: bool,
+  },
+
+  /// Search for entries under a path, e.g. `find /logs -name *.log -size +1M -delete`.
+  ///
+  /// Tests: -name <glob>, -type f|d, -size [+-]N[kMG], -newer <path>, -mtime [+-]days, -empty.
+  /// Options: -maxdepth N, -mindepth N. Operators: -and, -or, -not, ( ).
+  /// Actions: -print, -delete, -exec <command with {}> ;
+  Find {
+    #[clap(allow_hyphen_values = true, trailing_var_arg = true)]
+    args: Vec<String>,
+  },
+
+  /// Search file contents for lines matching a regex.
+  Grep(GrepArgs),
+
+  /// Display the space used by each directory under a path.
+  #[command(disable_help_flag = true)]
+  Du {
+    #[clap(default_value = ".")]
+    path: PathBuf,
+
+    /// Only display the total for the path itself.
+    #[clap(short)]
+    summarize: bool,
+
+    /// Print sizes in human readable format.
+    #[clap(short)]
+    human_readable: bool,
+  },
+
+  /// Display the space used by the entire filesystem and its limits.
+  Df,
+
+  /// Set the filesystem's capacity limits. Use `none` to remove a limit.
+  Limit {
+    /// The maximum total length in bytes of all files.
+    #[clap(long)]
+    bytes: Option<Limit>,
+
+    /// The maximum number of entries.
+    #[clap(long)]
+    entries: Option<Limit>,
+
+    /// The maximum length in bytes of a single file.
+    #[clap(long)]
+    file_size: Option<Limit>,
+
+    /// The maximum length in bytes of an entry's name.
+    #[clap(long)]
+    name_len: Option<Limit>,
+
+    /// The maximum number of components in a path.
+    #[clap(long)]
+    depth: Option<Limit>,
+  },
+
+  /// Display the extended attributes of a file or directory.
+  Getfattr {
+    path: PathBuf,
+
+    /// Only display the attribute with this name.
+    #[clap(short)]
+    name: Option<String>,
+  },
+
+  /// Set or remove an extended attribute of a file or directory.
+  Setfattr {
+    path: PathBuf,
+
+    /// The name of the attribute to set.
+    #[clap(short, conflicts_with = "remove", requires = "value")]
+    name: Option<String>,
+
+    /// The value of the attribute to set.
+    #[clap(short)]
+    value: Option<String>,
+
+    /// The name of the attribute to remove.
+    #[clap(short = 'x', required_unless_present = "name")]
+    remove: Option<String>,
+  },
+
+  /// Compress or decompress a file's content in memory.
+  Compress {
+    path: PathBuf,
+
+    /// Decompress the file's content instead.
+    #[clap(short)]
+    decompress: bool,
+  },
+
+  /// Manage fault injection rules.
+  Fault {
+    #[command(subcommand)]
+    command: FaultCommand,
+  },
+
+  /// Run the commands in a file in the in-memory filesystem.
+  Source { path: PathBuf },
+
+  /// Change a setting of the repl.
+  Set {
+    #[command(subcommand)]
+    setting: Setting,
+  },
+}
+
+#[derive(Subcommand)]
+enum Setting {
+  /// Print command output as text, or as one JSON document per command.
+  ///
+  /// Each document has `"ok"` and the `"command"`, then either its structured `"result"` or an
+  /// `"error"` with a `"code"`, a `"message"` and maybe a `"path"`, and any text `"output"`.
+  Output {
+    #[clap(value_enum)]
+    format: OutputFormat,
+  },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+  Text,
+  Json,
+}
+
+#[derive(Subcommand)]
+enum FaultCommand {
+  /// Add a rule injecting a fault into operations under a path.
+  Add {
+    path: PathBuf,
+
+    /// The operation to inject faults into. All operations are matched if omitted.
+    #[clap(long)]
+    op: Option<FaultOperation>,
+
+    /// Only trigger on the nth matching operation.
+    #[clap(long, conflicts_with = "probability")]
+    nth: Option<u64>,
+
+    /// Trigger on each matching operation with this probability, between 0 and 1.
+    #[clap(long, value_parser = crate::util::parse_probability)]
+    probability: Option<f64>,
+
+    /// The seed used for random triggers.
+    #[clap(long, default_value_t = 0, requires = "probability")]
+    seed: u64,
+
+    /// Write at most this many bytes instead of failing.
+    #[clap(long, conflicts_with = "error")]
+    short: Option<usize>,
+
+    /// The error to fail with.
+    #[clap(long, value_enum, default_value_t = FaultError::Io)]
+    error: FaultError,
   },
 
-  /// List all file paths under the current directory with the given name.
-  Find { target_name: String },
+  /// List all rules.
+  Ls,
+
+  /// Remove a rule.
+  Rm { id: usize },
+
+  /// Enable a rule.
+  Enable { id: usize },
+
+  /// Disable a rule.
+  Disable { id: usize },
+
+  /// Remove all rules.
+  Clear,
+}
+
+/// Entries of a listing with their names.
+type Entries = Vec<(String, ListEntry)>;
+
+#[derive(ClapArgs)]
+struct LsArgs {
+  #[clap(default_value = ".")]
+  paths: Vec<PathBuf>,
+
+  /// Show the mode, link count, owner, size and modification time of entries.
+  #[clap(short)]
+  long: bool,
+
+  /// Show entries whose names start with a `.`.
+  #[clap(short)]
+  all: bool,
+
+  /// List subdirectories recursively.
+  #[clap(short = 'R')]
+  recursive: bool,
+
+  /// Sort by modification time, newest first.
+  #[clap(short = 't', conflicts_with = "size")]
+  time: bool,
+
+  /// Sort by size, largest first.
+  #[clap(short = 'S')]
+  size: bool,
+
+  /// Reverse the order of entries.
+  #[clap(short)]
+  reverse: bool,
+
+  /// Print sizes in human readable format.
+  #[clap(short)]
+  human_readable: bool,
+}
+
+impl LsArgs {
+  /// Sorts `entries` by name, or by time or size if requested, and reverses them if requested.
+  fn sort(&self, entries: &mut [(String, ListEntry)]) {
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    if self.time {
+      entries.sort_by_key(|(_, entry)| Reverse(entry.modified_at));
+    } else if self.size {
+      entries.sort_by_key(|(_, entry)| Reverse(entry.len));
+    }
+
+    if self.reverse {
+      entries.reverse();
+    }
+  }
+
+  /// Writes `entries` one per line, with aligned columns in the long format.
+  fn write(&self, entries: &[(String, ListEntry)], out: &mut dyn Write) -> Result<()> {
+    let size = |entry: &ListEntry| {
+      if self.human_readable {
+        crate::util::human_size(entry.len)
+      } else {
+        entry.len.to_string()
+      }
+    };
+
+    let links_width = entries.iter().map(|(_, entry)| entry.links.to_string().len()).max().unwrap_or(0);
+    let size_width = entries.iter().map(|(_, entry)| size(entry).len()).max().unwrap_or(0);
+
+    for (name, entry) in entries {
+      if self.long {
+        writeln!(
+          out,
+          "{mode} {links:>links_width$} {owner} {size:>size_width$} {modified} {name}",
+          mode = entry.mode_string(),
+          links = entry.links,
+          owner = entry.owner,
+          size = size(entry),
+          modified = entry.modified_at.format("%Y-%m-%d %H:%M"),
+        )?;
+      } else {
+        writeln!(out, "{name}")?;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[derive(ClapArgs)]
+struct GrepArgs {
+  pattern: String,
+
+  #[clap(default_value = ".")]
+  paths: Vec<PathBuf>,
+
+  /// Search directories recursively.
+  #[clap(short)]
+  recursive: bool,
+
+  /// Print line numbers.
+  #[clap(short = 'n')]
+  line_number: bool,
+
+  /// Match case-insensitively.
+  #[clap(short)]
+  ignore_case: bool,
+
+  /// Only print the paths of files with matches.
+  #[clap(short = 'l')]
+  files_with_matches: bool,
+
+  /// Search binary files too.
+  #[clap(short = 'a')]
+  text: bool,
+
+  /// Print lines of context after each match.
+  #[clap(short = 'A', default_value_t = 0)]
+  after_context: usize,
+
+  /// Print lines of context before each match.
+  #[clap(short = 'B', default_value_t = 0)]
+  before_context: usize,
+
+  /// Print lines of context before and after each match.
+  #[clap(short = 'C')]
+  context: Option<usize>,
+
+  /// Only search files whose name matches a glob.
+  #[clap(long)]
+  include: Vec<String>,
+
+  /// Skip files and directories whose name matches a glob.
+  #[clap(long)]
+  exclude: Vec<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FaultOperation {
+  Mkdir,
+  Touch,
+  Rm,
+  Mv,
+  Cp,
+  Write,
+  Truncate,
+  Punch,
+  Read,
+}
+
+impl From<FaultOperation> for Operation {
+  fn from(operation: FaultOperation) -> Self {
+    match operation {
+      FaultOperation::Mkdir => Self::CreateDirectory,
+      FaultOperation::Touch => Self::CreateFile,
+      FaultOperation::Rm => Self::Remove,
+      FaultOperation::Mv => Self::Move,
+      FaultOperation::Cp => Self::Copy,
+      FaultOperation::Write => Self::Write,
+      FaultOperation::Truncate => Self::Truncate,
+      FaultOperation::Punch => Self::PunchHole,
+      FaultOperation::Read => Self::Read,
+    }
+  }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FillPattern {
+  Random,
+  Zeros,
+  Ascii,
+  Lorem,
+  Sequence,
+}
+
+impl From<FillPattern> for Pattern {
+  fn from(pattern: FillPattern) -> Self {
+    match pattern {
+      FillPattern::Random => Self::Random,
+      FillPattern::Zeros => Self::Zeros,
+      FillPattern::Ascii => Self::Ascii,
+      FillPattern::Lorem => Self::Lorem,
+      FillPattern::Sequence => Self::Sequence,
+    }
+  }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FaultError {
+  Io,
+  Nospace,
+}
+
+/// How deeply `source` commands can be nested.
+const MAX_SOURCE_DEPTH: usize = 64;
+
+/// The most bytes read from a file at a time by commands streaming their input.
+const READ_LEN: usize = 1 << 20;
+
+/// A capacity limit given on the command line, where `none` means unlimited.
+#[derive(Clone)]
+struct Limit(Option<u64>);
+
+impl FromStr for Limit {
+  type Err = ParseIntError;
+
+  fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+    if s == "none" {
+      Ok(Self(None))
+    } else {
+      s.parse().map(|limit| Self(Some(limit)))
+    }
+  }
 }
 
 struct Repl {
-  session: Session,
+  session: Box<dyn SessionApi>,
+
+  /// User-defined shell variables, set with `NAME=value`.
+  variables: BTreeMap<String, String>,
+
+  /// The names of all commands, for completion.
+  commands: Vec<String>,
+
+  history: FileHistory,
+
+  /// The file on the host that history is appended to.
+  history_path: Option<PathBuf>,
+
+  /// The file in the session that history is appended to.
+  memfs_history_path: Option<PathBuf>,
+
+  /// Whether scripts keep running after a command fails.
+  keep_going: bool,
+
+  /// How many `source` commands are running, to catch scripts sourcing themselves.
+  source_depth: usize,
+
+  /// Whether each command prints a JSON document instead of text.
+  json: bool,
+
+  /// Whether the running command returns a structured result instead of writing text, which is
+  /// only the case in JSON output for commands whose output isn't piped or redirected.
+  structured: bool,
 }
 
 impl Repl {
-  pub fn new(filesystem: Filesystem) -> Self {
+  pub fn new(session: Box<dyn SessionApi>) -> Self {
     Self {
-      session: Session::new(filesystem),
+      session,
+      variables: BTreeMap::new(),
+      commands: Command::command()
+        .get_subcommands()
+        .map(|command| command.get_name().to_owned())
+        .collect(),
+      history: FileHistory::new(),
+      history_path: None,
+      memfs_history_path: None,
+      keep_going: false,
+      source_depth: 0,
+      json: false,
+      structured: false,
     }
   }
 
-  fn get_line(&self) -> String {
-    // TODO: don't unwrap
-    print!("{current_directory:?} >>> ", current_directory = self.session.current_directory());
-    std::io::stdout().flush().unwrap();
-
-    let mut line = String::new();
-    std::io::stdin().read_line(&mut line).unwrap();
+  /// Loads history from `path` on the host, which it is also appended to as lines are read. A
+  /// missing file is created later.
+  fn load_history(&mut self, path: PathBuf) {
+    if path.exists() {
+      if let Err(err) = self.history.load(&path) {
+        self.report("history", &format!("failed to load history from {path:?}: {err}"), Some(&path));
+      }
+    }
 
-    line
+    self.history_path = Some(path);
   }
 
-  fn handle_command(&mut self, command: Command) -> Result<()> {
-    match command {
-      Command::Cd { path } => self.session.change_directory(path)?,
-      Command::Mkdir { path } => self.session.create_directory(path)?,
-      Command::Touch { path } => self.session.create_file(path)?,
-      Command::Fill { path } => self.session.write_file(path, crate::util::random_ascii(100))?,
-      Command::Cat { path } => println!("{}", self.session.read_file(path)?),
-      Command::Rm { path } => self.session.remove(path)?,
-      Command::Mv { src, dst } => self.session.move_entry(src, dst)?,
-
-      Command::Find { target_name } => {
-        let target_name = OsString::from(target_name);
-
-        self.session.walk(self.session.current_directory(), |path, entry| {
-          if entry.name() == target_name {
-            println!("{path:?}");
-          }
-        })?;
+  /// Reads a line with editing, history and completion, returning `None` at the end of input.
+  /// Interrupting the line with ctrl-c returns an empty line.
+  fn read_line(&mut self, prompt: &str) -> Option<String> {
+    let config = Config::builder()
+      .auto_add_history(true)
+      .completion_type(CompletionType::List)
+      .build();
+    let history = mem::replace(&mut self.history, FileHistory::new());
+
+    let mut editor = match Editor::with_history(config, history) {
+      Ok(editor) => editor,
+      Err(err) => {
+        self.report("io", &err.to_string(), None);
+        return None;
       }
+    };
 
-      Command::Stat { path } => {
-        let (_, entry) = self.session.resolve(path)?;
-        let metadata = entry.metadata();
+    editor.set_helper(Some(Helper {
+      session: &*self.session,
+      commands: &self.commands,
+    }));
 
-        println!(
-          "{kind}: {name:?}",
-          kind = if entry.is_directory() { "Directory" } else { "File" },
-          name = entry.name(),
-        );
+    let line = editor.readline(prompt);
+
+    self.history = mem::replace(editor.history_mut(), FileHistory::new());
+    drop(editor);
 
-        println!("Created At: {}", metadata.created_at);
+    match line {
+      Ok(line) => {
+        self.append_history(&line);
+        Some(line)
+      }
+      Err(ReadlineError::Interrupted) => Some(String::new()),
+      Err(ReadlineError::Eof) => None,
+      Err(err) => {
+        self.report("io", &err.to_string(), None);
+        None
       }
+    }
+  }
 
-      Command::Tree { path } => {
-        let root_depth = path.components().count();
+  /// Appends `line` to the history files.
+  fn append_history(&mut self, line: &str) {
+    if let Some(path) = &self.history_path {
+      if let Err(err) = self.history.append(path) {
+        self.report("history", &format!("failed to save history to {path:?}: {err}"), Some(path));
+      }
+    }
 
-        self.session.walk(path, |path, entry| {
-          let depth = "  ".repeat(path.components().count() - root_depth);
+    if let Some(path) = &self.memfs_history_path {
+      let res = match self.session.stat(path) {
+        Ok(_) => Ok(()),
+        Err(_) => self.session.create_file(path),
+      };
 
-          println!("{depth}· {name:?}", name = entry.name());
-        })?;
+      if let Err(err) = res.and_then(|()| self.session.write_file(path, format!("{line}\n").into_bytes())) {
+        self.report("history", &format!("failed to save history to {path:?}: {err}"), Some(path));
       }
+    }
+  }
+
+  /// Runs each line of a script, returning whether every line succeeded. Unless `keep_going` is
+  /// set, the script stops at the first failing line.
+  fn run_script(&mut self, mut lines: impl Iterator<Item = String>, out: &mut dyn Write) -> bool {
+    let mut success = true;
+
+    while let Some(line) = lines.next() {
+      if !self.run_line(&line, &mut |_| lines.next(), out) {
+        success = false;
 
-      Command::Ls { path } => {
-        for entry in self.session.list_directory(path)? {
-          println!(
-            "{kind} {name:?}",
-            kind = if entry.is_directory() { "d" } else { "f" },
-            name = entry.name()
-          );
+        if !self.keep_going {
+          break;
         }
       }
     }
 
-    Ok(())
+    success
   }
-}
-
-fn main() {
-  let mut repl = Repl::new(Filesystem::new());
-
-  loop {
-    let line = repl.get_line();
 
-    let command = match Command::try_parse_from(line.trim().split(' ')) {
-      Ok(command) => command,
+  /// Runs a command line, returning whether the last pipeline that ran succeeded. The bodies of
+  /// heredocs are read from the following lines with `more`.
+  fn run_line(&mut self, line: &str, more: &mut dyn FnMut(&mut Self) -> Option<String>, out: &mut dyn Write) -> bool {
+    let mut pipelines = match shell::parse(line) {
+      Ok(pipelines) => pipelines,
       Err(err) => {
-        println!("{err}");
-        continue;
+        if self.json {
+          self.write_document(&json!({ "ok": false, "error": json::error("syntax", &err, None) }), out);
+        } else {
+          eprintln!("{err}");
+        }
+
+        return false;
       }
     };
 
-    if let Err(err) = repl.handle_command(command) {
-      println!("{err}");
+    if let Err(err) = self.read_heredocs(&mut pipelines, more) {
+      if self.json {
+        self.write_document(&json::document(None, &Err(err), &[]), out);
+      } else {
+        eprintln!("{err}");
+      }
+
+      return false;
+    }
+
+    let mut success = true;
+
+    for (connector, pipeline) in pipelines {
+      let run = match connector {
+        Connector::Always => true,
+        Connector::And => success,
+        Connector::Or => !success,
+      };
+
+      if run {
+        success = self.run_pipeline(&pipeline, out);
+      }
     }
+
+    success
   }
+
+  /// Reads the bodies of the heredocs of a command line in order, each up to a line with only its
+  /// delimiter, and replaces the delimiters with the bodies. A body without a delimiter ends at
+  /// the end of input.
+  fn read_heredocs(&mut self, pipelines: &mut [(Connector, Pipeline)], more: &mut dyn FnMut(&mut Self) -> Option<String>) -> Result<()> {
+    let redirects = pipelines
+      .iter_mut()
+      .flat_map(|(_, pipeline)| pipeline.iter_mut())
+      .flat_map(|command| command.redirects.iter_mut());
+
+    for (redirect, word) in redirects {
+      if *redirect != Redirect::Heredoc {
+        continue;
+      }
+
+      let delimiter = word.literal();
+      let mut body = String::new();
+
+      loop {
+        match more(self) {
+          Some(line) if line == delimiter => break,
+          Some(line) => {
+            body.push_str(&line);
+            body.push('\n');
+          }
+          None => {
+            eprintln!("warning: heredoc ended by end of input instead of `{delimiter}`");
+            break;
+          }
+        }
+      }
+
+      *word = Word::heredoc(&body, word.is_quoted()).map_err(Error::Usage)?;
+    }
+
+    Ok(())
+  }
+
+  /// Runs the commands of a pipeline one after the other, passing the output of each as the input
+  /// of the next, and returns whether the last command succeeded.
+  fn run_pipeline(&mut self, pipeline: &[SimpleCommand], out: &mut dyn Write) -> bool {
+    let mut input = Vec::new();
+    let mut success = true;
+
+    for (i, command) in pipeline.iter().enumerate() {
+      if i == pipeline.len() - 1 {
+        success = self.run(command, &input, None, out);
+      } else {
+        let mut output = Buffer::default();
+        success = self.run(command, &input, Some(&mut output), out);
+        input = output.into_inner();
+      }
+    }
+
+    success
+  }
+
+  /// Runs a command, returning whether it succeeded. Its output is written to `pipe` if it is
+  /// piped into another command, or else to `out`. Errors are printed to stderr, except in JSON
+  /// output, where every command writes a document with its result or error to `out` instead.
+  fn run(&mut self, command: &SimpleCommand, input: &[u8], pipe: Option<&mut Buffer>, out: &mut dyn Write) -> bool {
+    if !self.json {
+      let res = match pipe {
+        Some(pipe) => self.run_redirected(command, input, pipe),
+        None => self.run_redirected(command, input, out),
+      };
+
+      if let Err(err) = &res {
+        eprintln!("{err}");
+      }
+
+      return res.is_ok();
+    }
+
+    let redirected = command
+      .redirects
+      .iter()
+      .any(|(redirect, _)| matches!(redirect, Redirect::Output | Redirect::Append));
+    let structured = mem::replace(&mut self.structured, pipe.is_none() && !redirected);
+
+    let mut output = Buffer::default();
+    let res = match pipe {
+      Some(pipe) => self.run_redirected(command, input, pipe),
+      None => self.run_redirected(command, input, &mut output),
+    };
+    self.structured = structured;
+
+    let name = command
+      .words
+      .first()
+      .filter(|word| word.assignment().is_none())
+      .and_then(|word| word.expand(|name| self.variable(name)));
+
+    self.write_document(&json::document(name.as_deref(), &res, output.as_bytes()), out);
+
+    res.is_ok()
+  }
+
+  /// Reports an error outside of any command, like failing to save history, to stderr, or as a
+  /// document on stdout in JSON output.
+  fn report(&self, code: &str, message: &str, path: Option<&Path>) {
+    if self.json {
+      self.write_document(
+        &json!({ "ok": false, "error": json::error(code, message, path) }),
+        &mut io::stdout(),
+      );
+    } else {
+      eprintln!("{message}");
+    }
+  }
+
+  /// Writes a JSON document on its own line.
+  fn write_document(&self, document: &Value, out: &mut dyn Write) {
+    if let Err(err) = writeln!(out, "{document}") {
+      eprintln!("{err}");
+    }
+  }
+
+  /// Runs a command with its input read from the file of a `<` redirection and its output
+  /// appended to the file of a `>` or `>>` redirection, if any. Output files are created, and
+  /// truncated for `>`, before the command runs. The last redirection of each kind wins.
+  fn run_redirected(&mut self, command: &SimpleCommand, input: &[u8], out: &mut dyn Write) -> Result<Option<Value>> {
+    let mut input = Cow::Borrowed(input);
+    let mut output = None;
+
+    for (redirect, word) in &command.redirects {
+      if *redirect == Redirect::Heredoc {
+        input = Cow::Owned(word.expand(|name| self.variable(name)).unwrap_or_default().into_bytes());
+        continue;
+      }
+
+      let path = word
+        .expand(|name| self.variable(name))
+        .ok_or_else(|| Error::Usage("ambiguous redirect".to_string()))?;
+
+      match redirect {
+        Redirect::Input => input = Cow::Owned(self.read_whole(Path::new(&path))?),
+        Redirect::Output | Redirect::Append => {
+          let path = PathBuf::from(path);
+          self.open_output(&path, *redirect == Redirect::Append)?;
+
+          output = Some(path);
+        }
+        Redirect::Heredoc => unreachable!("heredocs are handled above"),
+      }
+    }
+
+    let Some(path) = output else {
+      return self.execute(&command.words, &input, out);
+    };
+
+    let mut buffer = Buffer::default();
+    let res = self.execute(&command.words, &input, &mut buffer);
+    self.write_output(&path, buffer.into_inner())?;
+
+    res
+  }
+
+  /// Creates the file at `path` if it doesn't exist, or empties it unless `append`.
+  fn open_output(&mut self, path: &Path, append: bool) -> Result<()> {
+    match self.session.stat(path) {
+      Ok(_) if !append => self.session.truncate(path, 0)?,
+      Ok(_) => (),
+      Err(_) => self.session.create_file(path)?,
+    }
+
+    Ok(())
+  }
+
+  /// Appends `content` to the file at `path`, failing if only part of it is written.
+  fn write_output(&mut self, path: &Path, content: Vec<u8>) -> Result<()> {
+    let len = content.len();
+    let written = self.session.write_file(path, content)?;
+
+    if written < len {
+      return Err(Error::ShortWrite {
+        path: path.to_owned(),
+        written,
+        len,
+      });
+    }
+
+    Ok(())
+  }
+
+  /// Expands the variables of a command and runs it. A command made only of `NAME=value` words
+  /// sets those variables instead.
+  fn execute(&mut self, words: &[Word], input: &[u8], out: &mut dyn Write) -> Result<Option<Value>> {
+    if let Some(assignments) = words.iter().map(Word::assignment).collect::<Option<Vec<_>>>() {
+      for (name, value) in assignments {
+        let value = value.expand(|name| self.variable(name)).unwrap_or_default();
+        self.variables.insert(name.to_owned(), value);
+      }
+
+      return Ok(None);
+    }
+
+    let args: Vec<_> = words.iter().filter_map(|word| word.expand(|name| self.variable(name))).collect();
+
+    // every word expanded to nothing, e.g. `$UNSET`
+    if args.is_empty() {
+      return Ok(None);
+    }
+
+    let command = match Command::try_parse_from(args) {
+      Ok(command) => command,
+
+      // `help` is reported as an error too
+      Err(err) if !err.use_stderr() => {
+        write!(out, "{err}")?;
+        return Ok(None);
+      }
+      Err(err) => return Err(Error::Usage(err.to_string().trim_end().to_owned())),
+    };
+
+    self.handle_command(command, input, out)
+  }
+
+  /// Returns the content of the file at `path`, or `input` if there is no path.
+  fn read_input<'a>(&self, path: Option<&Path>, input: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+    match path {
+      Some(path) => Ok(Cow::Owned(self.read_whole(path)?)),
+      None => Ok(Cow::Borrowed(input)),
+    }
+  }
+
+  /// Returns the whole content of the file at `path`, failing if it is larger than
+  /// [`MAX_BUFFER_LEN`].
+  fn read_whole(&self, path: &Path) -> Result<Vec<u8>> {
+    let len = self.session.stat(path)?.len;
+
+    match usize::try_from(len) {
+      Ok(len) if len <= MAX_BUFFER_LEN => Ok(self.session.read_file_at(path, 0, len)?),
+      _ => Err(Error::TooLarge(path.to_owned())),
+    }
+  }
+
+  /// Reads the file at `path` a chunk at a time, passing each chunk to `f` until the end of the
+  /// file or until `f` returns false.
+  fn read_chunks(&self, path: &Path, mut f: impl FnMut(&[u8]) -> Result<bool>) -> Result<()> {
+    let mut offset = 0;
+
+    loop {
+      let chunk = self.session.read_file_at(path, offset, READ_LEN)?;
+
+      if chunk.is_empty() || !f(&chunk)? {
+        return Ok(());
+      }
+
+      offset += chunk.len() as u64;
+    }
+  }
+
+  /// Returns the value of a shell variable. `$PWD` is always the current directory.
+  fn variable(&self, name: &str) -> Option<String> {
+    match name {
+      "PWD" => Some(self.session.current_directory().display().to_string()),
+      _ => self.variables.get(name).cloned(),
+    }
+  }
+
+  /// Runs a command, reading piped input from `input` and writing output to `out`. In JSON
+  /// output, commands with structured results return them instead of writing text.
+  fn handle_command(&mut self, command: Command, input: &[u8], out: &mut dyn Write) -> Result<Option<Value>> {
+    match command {
+      Command::Cd { path } => self.session.change_directory(&path)?,
+      Command::Pwd => {
+        let path = self.session.current_directory();
+
+        if self.structured {
+          return Ok(Some(json!({ "path": json::path_string(&path) })));
+        }
+
+        writeln!(out, "{}", path.display())?;
+      }
+      Command::Realpath { path, relative_to } => {
+        let mut path = self.session.canonicalize(&path)?;
+
+        if let Some(relative_to) = relative_to {
+          path = session::util::relative_path(self.session.canonicalize(&relative_to)?, path)?;
+        }
+
+        if self.structured {
+          return Ok(Some(json!({ "path": json::path_string(&path) })));
+        }
+
+        writeln!(out, "{}", path.display())?;
+      }
+      Command::Mkdir { path } => self.session.create_directory(&path)?,
+      Command::Touch { path } => self.session.create_file(&path)?,
+      Command::Fill { path, size, pattern, seed } => {
+        self.open_output(&path, true)?;
+
+        let seed = seed.unwrap_or_else(rand::random);
+        let written = ContentGenerator::new(pattern.into(), seed).fill(&mut *self.session, &path, size)?;
+
+        if self.structured {
+          return Ok(Some(json!({ "size": size, "written": written })));
+        }
+
+        if written < size {
+          writeln!(out, "short write: wrote {written} of {size} bytes")?;
+        }
+      }
+      Command::GenTree {
+        path,
+        depth,
+        fanout,
+        files,
+        file_size,
+        pattern,
+        seed,
+      } => {
+        let usage = TreeGenerator::new()
+          .depth(depth)
+          .fanout(fanout)
+          .files(files)
+          .file_size(file_size)
+          .pattern(pattern.into())
+          .seed(seed)
+          .generate(&mut *self.session, path)?;
+
+        if self.structured {
+          return Ok(Some(
+            json!({ "directories": usage.directories, "files": usage.files, "bytes": usage.bytes }),
+          ));
+        }
+
+        writeln!(
+          out,
+          "generated {} directories and {} files with {} bytes",
+          usage.directories, usage.files, usage.bytes
+        )?;
+      }
+      Command::Truncate { path, size } => self.session.truncate(&path, size)?,
+      Command::Punch { path, offset, len } => self.session.punch_hole(&path, offset, len)?,
+      Command::Seek { path, offset, hole } => {
+        let next = if hole {
+          self.session.seek_hole(&path, offset)?
+        } else {
+          self.session.seek_data(&path, offset)?
+        };
+
+        if self.structured {
+          return Ok(Some(json!({ "offset": next })));
+        }
+
+        match next {
+          Some(next) => writeln!(out, "{next}")?,
+          None => writeln!(out, "no {} past {offset}", if hole { "hole" } else { "data" })?,
+        }
+      }
+      Command::Cat { path: Some(path) } => self.read_chunks(&path, |chunk| {
+        out.write_all(chunk)?;
+        Ok(true)
+      })?,
+      Command::Cat { path: None } => out.write_all(input)?,
+      Command::Wc { path, lines, words, bytes } => {
+        let mut count = filter::Count::default();
+
+        match &path {
+          Some(path) => self.read_chunks(path, |chunk| {
+            count.update(chunk);
+            Ok(true)
+          })?,
+          None => count.update(input),
+        }
+
+        let filter::Count {
+          lines: line_count,
+          words: word_count,
+          bytes: byte_count,
+          ..
+        } = count;
+        let all = !(lines || words || bytes);
+
+        if self.structured {
+          return Ok(Some(json!({ "lines": line_count, "words": word_count, "bytes": byte_count })));
+        }
+
+        let mut counts = Vec::new();
+        if lines || all {
+          counts.push(line_count.to_string());
+        }
+        if words || all {
+          counts.push(word_count.to_string());
+        }
+        if bytes || all {
+          counts.push(byte_count.to_string());
+        }
+        if let Some(path) = path {
+          counts.push(path.display().to_string());
+        }
+
+        writeln!(out, "{}", counts.join(" "))?;
+      }
+      Command::Head {
+        path: Some(path),
+        mut lines,
+      } => self.read_chunks(&path, |chunk| {
+        out.write_all(&chunk[..filter::head_len(chunk, &mut lines)])?;
+        Ok(lines > 0)
+      })?,
+      Command::Head { path: None, lines } => out.write_all(filter::head(input, lines))?,
+      Command::Tail { path, lines } => out.write_all(filter::tail(&self.read_input(path.as_deref(), input)?, lines))?,
+      Command::Sort {
+        path,
+        reverse,
+        numeric,
+        unique,
+      } => out.write_all(&filter::sort(&self.read_input(path.as_deref(), input)?, reverse, numeric, unique))?,
+      Command::Uniq { path, count } => out.write_all(&filter::uniq(&self.read_input(path.as_deref(), input)?, count))?,
+      Command::Echo { no_newline, escapes, args } => {
+        let mut text = args.join(" ");
+        let mut newline = !no_newline;
+
+        if escapes {
+          let (unescaped, more) = text::unescape(&text);
+          text = unescaped;
+          newline &= more;
+        }
+
+        if newline {
+          text.push('\n');
+        }
+
+        out.write_all(text.as_bytes())?;
+      }
+      Command::Printf { format, args } => out.write_all(text::printf(&format, &args).map_err(Error::Usage)?.as_bytes())?,
+      Command::Tee { paths, append } => {
+        for path in &paths {
+          self.open_output(path, append)?;
+          self.write_output(path, input.to_vec())?;
+        }
+
+        out.write_all(input)?;
+      }
+      Command::Ed { path, silent } => self.handle_ed(path, silent, input, out)?,
+      Command::Rm { path } => self.session.remove(&path)?,
+      Command::Mv { src, dst } => self.session.move_entry(&src, &dst)?,
+      Command::Cp { src, dst } => self.session.copy_entry(&src, &dst)?,
+
+      Command::Find { args } => {
+        let find = Find::parse(args, &*self.session).map_err(Error::Usage)?;
+
+        let paths = self.session.find(&find.path, &find.predicate, find.options)?;
+
+        let mut printed = Vec::new();
+
+        for path in paths {
+          for action in &find.actions {
+            match action {
+              Action::Print if self.structured => printed.push(json::path_string(&path)),
+              Action::Print => writeln!(out, "{path:?}")?,
+              Action::Delete => self.session.remove(&path)?,
+              Action::Exec(args) => {
+                let path = path.to_string_lossy();
+
+                let command =
+                  Command::try_parse_from(args.iter().map(|arg| arg.replace("{}", &path))).map_err(|err| Error::Usage(err.to_string()))?;
+
+                // the output of executed commands is text even in JSON output
+                let structured = mem::replace(&mut self.structured, false);
+                let res = self.handle_command(command, &[], out);
+                self.structured = structured;
+                res?;
+              }
+            }
+          }
+        }
+
+        if self.structured {
+          return Ok(Some(json!({ "paths": printed })));
+        }
+      }
+
+      Command::Stat { path } => {
+        let entry = self.session.stat(&path)?;
+
+        if self.structured {
+          let mut stat = json!({
+            "type": if entry.is_directory { "directory" } else { "file" },
+            "name": entry.name.to_string_lossy(),
+            "size": entry.len,
+            "created_at": entry.created_at.to_rfc3339(),
+            "modified_at": entry.modified_at.to_rfc3339(),
+            "xattrs": entry
+              .xattrs
+              .iter()
+              .map(|(name, value)| (name.clone(), String::from_utf8_lossy(value).into()))
+              .collect::<serde_json::Map<_, _>>(),
+          });
+
+          if !entry.is_directory {
+            stat["allocated"] = entry.allocated.into();
+            stat["stored_size"] = entry.stored.into();
+            stat["compression"] = format!("{:?}", entry.compression).into();
+          }
+
+          return Ok(Some(stat));
+        }
+
+        writeln!(
+          out,
+          "{kind}: {name:?}",
+          kind = if entry.is_directory { "Directory" } else { "File" },
+          name = entry.name,
+        )?;
+
+        writeln!(out, "Size: {}", entry.len)?;
+
+        if !entry.is_directory {
+          writeln!(out, "Allocated: {}", entry.allocated)?;
+          writeln!(out, "Stored Size: {}", entry.stored)?;
+          writeln!(out, "Compression: {:?}", entry.compression)?;
+        }
+
+        writeln!(out, "Created At: {}", entry.created_at)?;
+        writeln!(out, "Modified At: {}", entry.modified_at)?;
+
+        for (name, value) in &entry.xattrs {
+          writeln!(out, "Xattr: {name}={:?}", String::from_utf8_lossy(value))?;
+        }
+      }
+
+      Command::Tree {
+        path,
+        level,
+        directories,
+        all,
+        du,
+        json,
+      } => {
+        let mut options = TreeOptions::new().directories_only(directories).all(all);
+        if let Some(level) = level {
+          options = options.max_depth(level);
+        }
+
+        let tree = self.session.tree(&path, &options)?;
+
+        if self.structured {
+          return Ok(Some(serde_json::to_value(tree.to_json(du)).map_err(io::Error::from)?));
+        }
+
+        if json {
+          serde_json::to_writer(&mut *out, &tree.to_json(du)).map_err(io::Error::from)?;
+          writeln!(out)?;
+        } else {
+          out.write_all(tree.render(du).as_bytes())?;
+        }
+      }
+
+      Command::Ls(args) => return self.handle_ls(&args, out),
+
+      Command::Grep(args) => return self.handle_grep(args, out),
+
+      Command::Du {
+        path,
+        summarize,
+        human_readable,
+      } => {
+        let format = |bytes| {
+          if human_readable {
+            crate::util::human_size(bytes)
+          } else {
+            bytes.to_string()
+          }
+        };
+
+        let mut totals = Vec::new();
+
+        let path = self.session.canonicalize(&path)?;
+
+        if summarize {
+          let usage = self.session.disk_usage(&path)?;
+
+          totals.push((path, usage.bytes));
+        } else {
+          let tree = self.session.tree(&path, &TreeOptions::new().all(true))?;
+
+          // directories before their subdirectories, in name order
+          let mut stack: Vec<(PathBuf, &TreeNode)> = vec![(path, &tree.root)];
+
+          while let Some((path, node)) = stack.pop() {
+            if !node.is_directory {
+              continue;
+            }
+
+            stack.extend(node.children.iter().rev().map(|child| (path.join(&child.name), child)));
+            totals.push((path, node.size));
+          }
+        }
+
+        if self.structured {
+          let totals: Vec<_> = totals
+            .iter()
+            .map(|(path, bytes)| json!({ "path": json::path_string(path), "bytes": bytes }))
+            .collect();
+
+          return Ok(Some(json!({ "directories": totals })));
+        }
+
+        for (path, bytes) in totals {
+          writeln!(out, "{}\t{path:?}", format(bytes))?;
+        }
+      }
+
+      Command::Df => {
+        let usage = self.session.filesystem_usage()?;
+        let limits = self.session.limits()?;
+        let stats = self.session.storage_stats()?;
+
+        if self.structured {
+          return Ok(Some(json!({
+            "bytes": usage.bytes,
+            "entries": usage.entries(),
+            "files": usage.files,
+            "directories": usage.directories,
+            "allocated_bytes": usage.allocated,
+            "stored_bytes": stats.stored_bytes,
+            "stored_blocks": stats.blobs,
+            "dedup_ratio": stats.dedup_ratio(),
+            "limits": {
+              "bytes": limits.max_bytes,
+              "entries": limits.max_entries,
+              "file_size": limits.max_file_size,
+              "name_len": limits.max_name_len,
+              "depth": limits.max_depth,
+            },
+          })));
+        }
+
+        let limit = |limit: Option<u64>| limit.map_or_else(|| "unlimited".to_string(), |limit| limit.to_string());
+
+        writeln!(out, "Bytes: {} / {}", usage.bytes, limit(limits.max_bytes))?;
+        writeln!(out, "Entries: {} / {}", usage.entries(), limit(limits.max_entries))?;
+        writeln!(out, "  Files: {}", usage.files)?;
+        writeln!(out, "  Directories: {}", usage.directories)?;
+        writeln!(out, "Max File Size: {}", limit(limits.max_file_size))?;
+        writeln!(out, "Max Name Length: {}", limit(limits.max_name_len.map(|max| max as u64)))?;
+        writeln!(out, "Max Depth: {}", limit(limits.max_depth.map(|max| max as u64)))?;
+
+        writeln!(out, "Allocated Bytes: {}", usage.allocated)?;
+        writeln!(out, "Stored Bytes: {}", stats.stored_bytes)?;
+        if let Some(blobs) = stats.blobs {
+          writeln!(out, "Stored Blocks: {blobs}")?;
+        }
+        writeln!(out, "Dedup Ratio: {:.2}", stats.dedup_ratio())?;
+      }
+
+      Command::Limit {
+        bytes,
+        entries,
+        file_size,
+        name_len,
+        depth,
+      } => {
+        let mut limits = self.session.limits()?;
+
+        if let Some(Limit(bytes)) = bytes {
+          limits.max_bytes = bytes;
+        }
+        if let Some(Limit(entries)) = entries {
+          limits.max_entries = entries;
+        }
+        if let Some(Limit(file_size)) = file_size {
+          limits.max_file_size = file_size;
+        }
+        if let Some(Limit(name_len)) = name_len {
+          limits.max_name_len = name_len.map(|max| max as usize);
+        }
+        if let Some(Limit(depth)) = depth {
+          limits.max_depth = depth.map(|max| max as usize);
+        }
+
+        self.session.set_limits(limits)?;
+      }
+
+      Command::Getfattr { path, name } => {
+        let names = match name {
+          Some(name) => vec![name],
+          None => self.session.list_xattr(&path)?,
+        };
+
+        let mut attributes = serde_json::Map::new();
+
+        for name in names {
+          let value = self.session.get_xattr(&path, &name)?;
+          let value = String::from_utf8_lossy(&value);
+
+          if self.structured {
+            attributes.insert(name, value.into());
+          } else {
+            writeln!(out, "{name}={value:?}")?;
+          }
+        }
+
+        if self.structured {
+          return Ok(Some(json!({ "attributes": attributes })));
+        }
+      }
+
+      Command::Setfattr { path, name, value, remove } => match (name, value, remove) {
+        (Some(name), Some(value), _) => self.session.set_xattr(&path, &name, value.into_bytes())?,
+        (_, _, Some(name)) => self.session.remove_xattr(&path, &name)?,
+        _ => unreachable!("clap requires either a name and value or an attribute to remove"),
+      },
+
+      Command::Compress { path, decompress } => {
+        let compression = if decompress { Compression::None } else { Compression::Lz4 };
+
+        self.session.set_compression(&path, compression)?;
+      }
+
+      Command::Fault { command } => return self.handle_fault_command(command, out),
+
+      Command::Source { path } => {
+        if self.source_depth == MAX_SOURCE_DEPTH {
+          return Err(Error::Usage(format!(
+            "source: scripts are nested more than {MAX_SOURCE_DEPTH} deep"
+          )));
+        }
+
+        let script = self.session.read_file(&path)?;
+
+        // in JSON output, the documents of the script's commands are the result
+        let mut documents = Vec::new();
+        let structured = self.structured;
+
+        self.source_depth += 1;
+        let success = self.run_script(script.lines().map(str::to_owned), if structured { &mut documents } else { out });
+        self.source_depth -= 1;
+
+        if !success {
+          out.write_all(&documents)?;
+          return Err(Error::Script(path));
+        }
+
+        if structured {
+          let documents = serde_json::Deserializer::from_slice(&documents)
+            .into_iter::<Value>()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(io::Error::from)?;
+
+          return Ok(Some(json!({ "commands": documents })));
+        }
+      }
+
+      Command::Set {
+        setting: Setting::Output { format },
+      } => self.json = format == OutputFormat::Json,
+    }
+
+    Ok(None)
+  }
+
+  /// Edits the file at `path` with `ed` commands read from `input`, or from the terminal if there
+  /// is no input. Errors in commands are printed with a `?` and don't stop editing.
+  fn handle_ed(&mut self, path: PathBuf, silent: bool, input: &[u8], out: &mut dyn Write) -> Result<()> {
+    let content = match self.read_whole(&path) {
+      Ok(content) => content,
+      Err(Error::Session(session::Error::NotExist(_))) => Vec::new(),
+      Err(err) => return Err(err),
+    };
+
+    if !silent {
+      writeln!(out, "{}", content.len())?;
+    }
+
+    let mut ed = Ed::new(&content);
+    let interactive = input.is_empty() && std::io::stdin().is_terminal();
+    let input = String::from_utf8_lossy(input);
+    let mut commands = input.lines();
+    let mut printed = Vec::new();
+
+    loop {
+      out.flush()?;
+
+      let line = if interactive {
+        self.read_line("*")
+      } else {
+        commands.next().map(str::to_owned)
+      };
+
+      let Some(line) = line else {
+        break;
+      };
+
+      let status = ed.feed(&line, &mut printed);
+      for line in printed.drain(..) {
+        writeln!(out, "{line}")?;
+      }
+
+      match status {
+        Ok(EdStatus::Continue) => (),
+        Ok(EdStatus::Write { path: target, quit }) => {
+          let target = target.map_or_else(|| path.clone(), PathBuf::from);
+          let content = ed.content();
+          let len = content.len();
+
+          match self.open_output(&target, false).and_then(|()| self.write_output(&target, content)) {
+            Ok(()) => {
+              ed.saved();
+
+              if !silent {
+                writeln!(out, "{len}")?;
+              }
+              if quit {
+                break;
+              }
+            }
+            Err(err) => writeln!(out, "? {err}")?,
+          }
+        }
+        Ok(EdStatus::Quit) => break,
+        Err(err) => writeln!(out, "? {err}")?,
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Lists the files among the paths of `args`, then the entries of each directory, preceded by a
+  /// header if there are several, and recursively of their subdirectories with `-R`.
+  fn handle_ls(&self, args: &LsArgs, out: &mut dyn Write) -> Result<Option<Value>> {
+    let (files, directories) = self.list(args)?;
+
+    if self.structured {
+      let entries = |entries: &Entries| -> Vec<_> { entries.iter().map(|(name, entry)| json::list_entry(name, entry)).collect() };
+      let directories: Vec<_> = directories
+        .iter()
+        .map(|(path, list)| json!({ "path": json::path_string(path), "entries": entries(list) }))
+        .collect();
+
+      return Ok(Some(json!({ "files": entries(&files), "directories": directories })));
+    }
+
+    let headers = args.recursive || files.len() + directories.len() > 1;
+    let mut separate = false;
+
+    if !files.is_empty() {
+      args.write(&files, out)?;
+      separate = true;
+    }
+
+    for (directory, entries) in &directories {
+      if separate {
+        writeln!(out)?;
+      }
+      if headers {
+        writeln!(out, "{}:", directory.display())?;
+      }
+
+      args.write(entries, out)?;
+      separate = true;
+    }
+
+    Ok(None)
+  }
+
+  /// Returns the sorted files among the paths of `args`, then each directory among them with its
+  /// sorted entries, followed by its subdirectories with `-R`. Globs are expanded.
+  fn list(&self, args: &LsArgs) -> Result<(Entries, Vec<(PathBuf, Entries)>)> {
+    let mut files = Vec::new();
+    let mut directories = Vec::new();
+
+    for path in &args.paths {
+      let paths = if path.to_str().is_some_and(|path| path.contains(['*', '?', '['])) {
+        let paths = self.session.glob(path)?;
+        if paths.is_empty() {
+          return Err(session::Error::NotExist(path.clone()).into());
+        }
+
+        paths
+      } else {
+        vec![path.clone()]
+      };
+
+      for path in paths {
+        let entry = self.session.stat(&path)?;
+
+        if entry.is_directory {
+          directories.push(path);
+        } else {
+          files.push((path.display().to_string(), entry));
+        }
+      }
+    }
+
+    args.sort(&mut files);
+    directories.reverse();
+
+    let mut listed = Vec::new();
+
+    while let Some(directory) = directories.pop() {
+      let mut entries: Entries = self
+        .session
+        .read_dir_with_metadata(&directory)?
+        .into_iter()
+        .filter(|entry| args.all || !entry.is_hidden())
+        .map(|entry| (entry.name.to_string_lossy().into_owned(), entry))
+        .collect();
+      args.sort(&mut entries);
+
+      if args.recursive {
+        let subdirectories = entries.iter().filter(|(_, entry)| entry.is_directory);
+        directories.extend(subdirectories.rev().map(|(name, _)| directory.join(name)));
+      }
+
+      listed.push((directory, entries));
+    }
+
+    Ok((files, listed))
+  }
+
+  fn handle_grep(&self, args: GrepArgs, out: &mut dyn Write) -> Result<Option<Value>> {
+    let before = args.context.unwrap_or(args.before_context);
+    let after = args.context.unwrap_or(args.after_context);
+    let separate = before > 0 || after > 0;
+
+    let mut options = GrepOptions::new()
+      .ignore_case(args.ignore_case)
+      .binary(args.text)
+      .before_context(before)
+      .after_context(after);
+
+    for pattern in args.include {
+      options = options.include(pattern);
+    }
+    for pattern in args.exclude {
+      options = options.exclude(pattern);
+    }
+
+    let show_path = args.recursive || args.paths.len() > 1;
+
+    // the last line printed, so that overlapping context is only printed once
+    let mut last: Option<(PathBuf, usize)> = None;
+
+    // the paths with matches or the matches, in JSON output
+    let mut results = Vec::new();
+
+    for path in &args.paths {
+      if self.session.stat(path)?.is_directory && !args.recursive {
+        writeln!(out, "{path:?} is a directory")?;
+        continue;
+      }
+
+      for found in self.session.grep(path, &args.pattern, &options)? {
+        if args.files_with_matches {
+          if last.as_ref().is_none_or(|(path, _)| *path != found.path) {
+            if self.structured {
+              results.push(json::path_string(&found.path).into());
+            } else {
+              writeln!(out, "{}", found.path.display())?;
+            }
+
+            last = Some((found.path, 0));
+          }
+
+          continue;
+        }
+
+        if self.structured {
+          let line = |line: &Line| json!({ "number": line.number, "text": line.text });
+
+          results.push(json!({
+            "path": json::path_string(&found.path),
+            "line": line(&found.line),
+            "before": found.before.iter().map(line).collect::<Vec<_>>(),
+            "after": found.after.iter().map(line).collect::<Vec<_>>(),
+          }));
+
+          continue;
+        }
+
+        let lines = found.before.iter().map(|line| (line, '-'));
+        let lines = lines.chain([(&found.line, ':')]);
+        let lines = lines.chain(found.after.iter().map(|line| (line, '-')));
+
+        for (line, separator) in lines {
+          if let Some((path, number)) = &last {
+            if *path == found.path && line.number <= *number {
+              continue;
+            }
+
+            if separate && (*path != found.path || line.number > number + 1) {
+              writeln!(out, "--")?;
+            }
+          }
+
+          let mut prefix = String::new();
+          if show_path {
+            prefix.push_str(&format!("{}{separator}", found.path.display()));
+          }
+          if args.line_number {
+            prefix.push_str(&format!("{}{separator}", line.number));
+          }
+
+          writeln!(out, "{prefix}{}", line.text)?;
+          last = Some((found.path.clone(), line.number));
+        }
+      }
+    }
+
+    if self.structured {
+      let key = if args.files_with_matches { "files" } else { "matches" };
+
+      return Ok(Some(json!({ key: results })));
+    }
+
+    Ok(None)
+  }
+
+  fn handle_fault_command(&mut self, command: FaultCommand, out: &mut dyn Write) -> Result<Option<Value>> {
+    match command {
+      FaultCommand::Add {
+        path,
+        op,
+        nth,
+        probability,
+        seed,
+        short,
+        error,
+      } => {
+        let path = session::util::clean_path(self.session.current_directory().join(path))?;
+
+        let fault = match (short, error) {
+          (Some(max), _) => Fault::ShortWrite(max),
+          (None, FaultError::Io) => Fault::Io,
+          (None, FaultError::Nospace) => Fault::NoSpace,
+        };
+
+        let trigger = match (nth, probability) {
+          (Some(n), _) => Trigger::Nth(n),
+          (None, Some(probability)) => Trigger::random(probability, seed)?,
+          (None, None) => Trigger::Always,
+        };
+
+        let id = self
+          .session
+          .add_fault(Rule::new(op.map(Operation::from), path, fault).trigger(trigger))?;
+
+        if self.structured {
+          return Ok(Some(json!({ "id": id })));
+        }
+
+        writeln!(out, "added fault {id}")?;
+      }
+
+      FaultCommand::Ls => {
+        if self.structured {
+          let rules: Vec<_> = self
+            .session
+            .fault_rules()?
+            .into_iter()
+            .map(|(id, rule, hits)| {
+              json!({
+                "id": id,
+                "enabled": rule.enabled,
+                "operation": rule.operation.map(|operation| format!("{operation:?}")),
+                "path": json::path_string(&rule.path),
+                "trigger": format!("{:?}", rule.trigger),
+                "fault": format!("{:?}", rule.fault),
+                "hits": hits,
+              })
+            })
+            .collect();
+
+          return Ok(Some(json!({ "rules": rules })));
+        }
+
+        for (id, rule, hits) in self.session.fault_rules()? {
+          writeln!(
+            out,
+            "{id}: {enabled} {operation} {path:?} {trigger:?} {fault:?} ({hits} hits)",
+            enabled = if rule.enabled { "enabled" } else { "disabled" },
+            operation = rule
+              .operation
+              .map_or_else(|| "Any".to_string(), |operation| format!("{operation:?}")),
+            path = rule.path,
+            trigger = rule.trigger,
+            fault = rule.fault,
+          )?;
+        }
+      }
+
+      FaultCommand::Rm { id } => {
+        if !self.session.remove_fault(id)? {
+          writeln!(out, "no fault {id}")?;
+        }
+      }
+
+      FaultCommand::Enable { id } => {
+        if !self.session.set_fault_enabled(id, true)? {
+          writeln!(out, "no fault {id}")?;
+        }
+      }
+
+      FaultCommand::Disable { id } => {
+        if !self.session.set_fault_enabled(id, false)? {
+          writeln!(out, "no fault {id}")?;
+        }
+      }
+
+      FaultCommand::Clear => self.session.clear_faults()?,
+    }
+
+    Ok(None)
+  }
+}
+
+fn main() -> ExitCode {
+  let args = Args::parse();
+
+  let session: Box<dyn SessionApi> = if let Some(socket) = &args.connect {
+    match Client::connect(socket) {
+      Ok(client) => Box::new(client),
+      Err(err) => {
+        eprintln!("failed to connect to {socket:?}: {err}");
+        return ExitCode::FAILURE;
+      }
+    }
+  } else {
+    let mut filesystem = if args.dedup {
+      Filesystem::deduplicated()
+    } else {
+      Filesystem::new()
+    };
+
+    if args.compress {
+      filesystem.compression = Compression::Lz4;
+    }
+
+    Box::new(Session::new(filesystem))
+  };
+
+  let mut repl = Repl::new(session);
+  repl.memfs_history_path = args.memfs_history;
+  repl.keep_going = args.keep_going;
+  repl.json = args.json;
+
+  let mut stdout = Stdout::new();
+
+  let script = if let Some(command) = args.command {
+    Some(repl.run_script(command.lines().map(str::to_owned), &mut stdout))
+  } else if let Some(path) = args.script {
+    match std::fs::read_to_string(&path) {
+      Ok(script) => Some(repl.run_script(script.lines().map(str::to_owned), &mut stdout)),
+      Err(err) => {
+        eprintln!("failed to read {path:?}: {err}");
+        Some(false)
+      }
+    }
+  } else if !std::io::stdin().is_terminal() {
+    Some(repl.run_script(std::io::stdin().lock().lines().map_while(std::result::Result::ok), &mut stdout))
+  } else {
+    None
+  };
+
+  if let Some(success) = script {
+    return if success { ExitCode::SUCCESS } else { ExitCode::FAILURE };
+  }
+
+  if let Some(path) = args
+    .history
+    .or_else(|| std::env::home_dir().map(|home| home.join(".memfs_history")))
+  {
+    repl.load_history(path);
+  }
+
+  loop {
+    let prompt = format!("{current_directory:?} >>> ", current_directory = repl.session.current_directory());
+    let Some(line) = repl.read_line(&prompt) else {
+      break;
+    };
+
+    repl.run_line(&line, &mut |repl| repl.read_line("> "), &mut stdout);
+
+    // keep the prompt at the start of a line after output without a trailing newline
+    if !stdout.at_line_start() {
+      println!();
+      stdout.reset();
+    }
+  }
+
+  ExitCode::SUCCESS
 }
@@ -0,0 +1,375 @@
+use std::{iter::Peekable, mem, str::Chars};
+
+/// How a command is chained to the one before it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Connector {
+  /// The command always runs, e.g. the first command or one after `;`.
+  Always,
+
+  /// The command runs if the previous command succeeded, after `&&`.
+  And,
+
+  /// The command runs if the previous command failed, after `||`.
+  Or,
+}
+
+/// A piece of a word, before variable expansion.
+#[derive(Clone)]
+enum Part {
+  /// Text taken as is. `quoted` is whether it came from quotes or a backslash escape.
+  Literal { text: String, quoted: bool },
+
+  /// A `$NAME` or `${NAME}` reference.
+  Variable(String),
+}
+
+/// A shell word, e.g. `"$PWD/a b"`, whose variables are expanded right before its command runs.
+pub struct Word {
+  parts: Vec<Part>,
+
+  /// Whether any part of the word was quoted, in which case it is kept even if it expands to an
+  /// empty string.
+  quoted: bool,
+}
+
+impl Word {
+  /// Expands the variables of this word with `lookup`, where unset variables expand to an empty
+  /// string. Returns `None` if the word is unquoted and expands to nothing, like in a shell.
+  pub fn expand(&self, lookup: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let mut expanded = String::new();
+
+    for part in &self.parts {
+      match part {
+        Part::Literal { text, .. } => expanded.push_str(text),
+        Part::Variable(name) => expanded.push_str(&lookup(name).unwrap_or_default()),
+      }
+    }
+
+    (self.quoted || !expanded.is_empty()).then_some(expanded)
+  }
+
+  /// Returns the name and value of this word if it is a variable assignment like `NAME=value`,
+  /// where the name is unquoted.
+  pub fn assignment(&self) -> Option<(&str, Word)> {
+    let Some(Part::Literal { text, quoted: false }) = self.parts.first() else {
+      return None;
+    };
+
+    let (name, value) = text.split_once('=')?;
+    if !is_name(name) {
+      return None;
+    }
+
+    let mut parts = vec![Part::Literal {
+      text: value.to_owned(),
+      quoted: false,
+    }];
+    parts.extend_from_slice(&self.parts[1..]);
+
+    Some((name, Word { parts, quoted: true }))
+  }
+
+  /// Returns the body of a heredoc as a word. Unless `literal`, `$` variables are expanded and
+  /// `\` escapes `$` and `\`, like in double quotes.
+  pub fn heredoc(body: &str, literal: bool) -> Result<Word, String> {
+    let mut word = Word {
+      parts: Vec::new(),
+      quoted: true,
+    };
+
+    if literal {
+      word.parts.push(Part::Literal {
+        text: body.to_owned(),
+        quoted: true,
+      });
+
+      return Ok(word);
+    }
+
+    let mut lexer = Lexer {
+      chars: body.chars().peekable(),
+    };
+
+    while let Some(c) = lexer.chars.next() {
+      match c {
+        '\\' => match lexer.chars.next_if(|c| matches!(c, '$' | '\\')) {
+          Some(c) => word.push(c, true),
+          None => word.push('\\', true),
+        },
+        '$' => lexer.variable(&mut word, true)?,
+        c => word.push(c, true),
+      }
+    }
+
+    Ok(word)
+  }
+
+  /// Returns the text of this word without expanding variables, e.g. for a heredoc delimiter.
+  pub fn literal(&self) -> String {
+    let mut literal = String::new();
+
+    for part in &self.parts {
+      match part {
+        Part::Literal { text, .. } => literal.push_str(text),
+        Part::Variable(name) => {
+          literal.push('$');
+          literal.push_str(name);
+        }
+      }
+    }
+
+    literal
+  }
+
+  /// Returns whether any part of this word was quoted.
+  pub fn is_quoted(&self) -> bool {
+    self.quoted
+  }
+
+  fn push(&mut self, c: char, quoted: bool) {
+    self.quoted |= quoted;
+
+    match self.parts.last_mut() {
+      Some(Part::Literal { text, quoted: last }) if *last == quoted => text.push(c),
+      _ => self.parts.push(Part::Literal {
+        text: c.to_string(),
+        quoted,
+      }),
+    }
+  }
+}
+
+/// Where a command's input comes from or its output goes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Redirect {
+  /// `< path` reads input from a file.
+  Input,
+
+  /// `> path` writes output to a file, replacing its content.
+  Output,
+
+  /// `>> path` appends output to a file.
+  Append,
+
+  /// `<< DELIMITER` reads input from the following lines, up to a line with only the delimiter.
+  /// Once the lines are read, the delimiter word is replaced by the body, see [`Word::heredoc`].
+  Heredoc,
+}
+
+/// A command with its arguments and redirections, before variable expansion.
+#[derive(Default)]
+pub struct SimpleCommand {
+  pub words: Vec<Word>,
+
+  pub redirects: Vec<(Redirect, Word)>,
+}
+
+impl SimpleCommand {
+  fn is_empty(&self) -> bool {
+    self.words.is_empty() && self.redirects.is_empty()
+  }
+}
+
+/// Commands connected with `|`, where the output of each is the input of the next.
+pub type Pipeline = Vec<SimpleCommand>;
+
+/// A token of a command line.
+enum Token {
+  Word(Word),
+  Operator(Connector),
+  Pipe,
+  Redirect(Redirect),
+}
+
+/// Parses a command line into pipelines and how each is chained to the one before it.
+///
+/// Supports single quotes, which keep everything literally, double quotes, in which `\` only
+/// escapes `$`, `"` and `\`, backslash escapes, `$NAME` and `${NAME}` variables, `#` comments,
+/// chaining with `;`, `&&` and `||`, pipes with `|`, and redirections with `<`, `>`, `>>` and
+/// `<<`.
+pub fn parse(line: &str) -> Result<Vec<(Connector, Pipeline)>, String> {
+  let mut lexer = Lexer {
+    chars: line.chars().peekable(),
+  };
+
+  let mut pipelines = Vec::new();
+  let mut connector = Connector::Always;
+  let mut pipeline = Vec::new();
+  let mut command = SimpleCommand::default();
+
+  while let Some(token) = lexer.next_token()? {
+    match token {
+      Token::Word(word) => command.words.push(word),
+      Token::Redirect(redirect) => match lexer.next_token()? {
+        Some(Token::Word(path)) => command.redirects.push((redirect, path)),
+        _ => return Err("syntax error: expected a path after a redirection".to_string()),
+      },
+      Token::Pipe | Token::Operator(_) if command.is_empty() => {
+        return Err("syntax error: expected a command before an operator".to_string());
+      }
+      Token::Pipe => pipeline.push(mem::take(&mut command)),
+      Token::Operator(next) => {
+        pipeline.push(mem::take(&mut command));
+        pipelines.push((connector, mem::take(&mut pipeline)));
+        connector = next;
+      }
+    }
+  }
+
+  if !command.is_empty() {
+    pipeline.push(command);
+    pipelines.push((connector, pipeline));
+  } else if !pipeline.is_empty() || connector != Connector::Always {
+    return Err("syntax error: expected a command after an operator".to_string());
+  }
+
+  Ok(pipelines)
+}
+
+/// Returns whether `name` is a valid variable name.
+fn is_name(name: &str) -> bool {
+  let mut chars = name.chars();
+
+  chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+struct Lexer<'a> {
+  chars: Peekable<Chars<'a>>,
+}
+
+impl Lexer<'_> {
+  fn next_token(&mut self) -> Result<Option<Token>, String> {
+    while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+    let token = match self.chars.peek() {
+      None | Some('#') => return Ok(None),
+      Some(';') => {
+        self.chars.next();
+        Token::Operator(Connector::Always)
+      }
+      Some('&') => {
+        self.chars.next();
+        self.double('&')?;
+        Token::Operator(Connector::And)
+      }
+      Some('|') => {
+        self.chars.next();
+
+        match self.chars.next_if_eq(&'|') {
+          Some(_) => Token::Operator(Connector::Or),
+          None => Token::Pipe,
+        }
+      }
+      Some('>') => {
+        self.chars.next();
+
+        match self.chars.next_if_eq(&'>') {
+          Some(_) => Token::Redirect(Redirect::Append),
+          None => Token::Redirect(Redirect::Output),
+        }
+      }
+      Some('<') => {
+        self.chars.next();
+
+        match self.chars.next_if_eq(&'<') {
+          Some(_) => Token::Redirect(Redirect::Heredoc),
+          None => Token::Redirect(Redirect::Input),
+        }
+      }
+      Some(_) => Token::Word(self.word()?),
+    };
+
+    Ok(Some(token))
+  }
+
+  /// Consumes the second character of a two character operator like `&&`.
+  fn double(&mut self, c: char) -> Result<(), String> {
+    match self.chars.next_if_eq(&c) {
+      Some(_) => Ok(()),
+      None => Err(format!("unsupported operator `{c}`")),
+    }
+  }
+
+  fn word(&mut self) -> Result<Word, String> {
+    let mut word = Word {
+      parts: Vec::new(),
+      quoted: false,
+    };
+
+    while let Some(c) = self
+      .chars
+      .next_if(|c| !c.is_whitespace() && !matches!(c, ';' | '&' | '|' | '<' | '>'))
+    {
+      match c {
+        '\'' => {
+          word.quoted = true;
+
+          loop {
+            match self.chars.next() {
+              Some('\'') => break,
+              Some(c) => word.push(c, true),
+              None => return Err("unterminated single quote".to_string()),
+            }
+          }
+        }
+        '"' => {
+          word.quoted = true;
+
+          loop {
+            match self.chars.next() {
+              Some('"') => break,
+              Some('\\') => match self.chars.next_if(|c| matches!(c, '$' | '"' | '\\')) {
+                Some(c) => word.push(c, true),
+                None => word.push('\\', true),
+              },
+              Some('$') => self.variable(&mut word, true)?,
+              Some(c) => word.push(c, true),
+              None => return Err("unterminated double quote".to_string()),
+            }
+          }
+        }
+        '\\' => match self.chars.next() {
+          Some(c) => word.push(c, true),
+          None => return Err("unexpected end of line after `\\`".to_string()),
+        },
+        '$' => self.variable(&mut word, false)?,
+        c => word.push(c, false),
+      }
+    }
+
+    Ok(word)
+  }
+
+  /// Reads a variable reference after a `$` into `word`. A `$` not followed by a name is kept
+  /// literally.
+  fn variable(&mut self, word: &mut Word, quoted: bool) -> Result<(), String> {
+    let mut name = String::new();
+
+    if self.chars.next_if_eq(&'{').is_some() {
+      loop {
+        match self.chars.next() {
+          Some('}') => break,
+          Some(c) => name.push(c),
+          None => return Err("unterminated `${`".to_string()),
+        }
+      }
+
+      if !is_name(&name) {
+        return Err(format!("bad substitution `${{{name}}}`"));
+      }
+    } else {
+      if !self.chars.peek().is_some_and(|c| c.is_ascii_alphabetic() || *c == '_') {
+        word.push('$', quoted);
+        return Ok(());
+      }
+
+      while let Some(c) = self.chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+        name.push(c);
+      }
+    }
+
+    word.parts.push(Part::Variable(name));
+
+    Ok(())
+  }
+}
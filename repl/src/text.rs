@@ -0,0 +1,219 @@
+use std::{iter::Peekable, str::Chars};
+
+/// Replaces backslash escapes in `s` like `echo -e`: `\n`, `\t`, `\r`, `\0`, `\\`, `\a`, `\b`,
+/// `\e`, `\f`, `\v` and `\xHH`. Also returns whether output continues, which is `false` if a `\c`
+/// ended it.
+pub fn unescape(s: &str) -> (String, bool) {
+  let mut unescaped = String::with_capacity(s.len());
+  let mut chars = s.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    if c != '\\' {
+      unescaped.push(c);
+      continue;
+    }
+
+    match chars.next() {
+      Some('c') => return (unescaped, false),
+      Some(c) => unescaped.push_str(&escape(c, &mut chars)),
+      None => unescaped.push('\\'),
+    }
+  }
+
+  (unescaped, true)
+}
+
+/// Returns the character of the escape `\c`, reading any digits of `\xHH` from `chars`. Unknown
+/// escapes are kept as is.
+fn escape(c: char, chars: &mut Peekable<Chars>) -> String {
+  let c = match c {
+    'n' => '\n',
+    't' => '\t',
+    'r' => '\r',
+    '0' => '\0',
+    '\\' => '\\',
+    'a' => '\x07',
+    'b' => '\x08',
+    'e' => '\x1b',
+    'f' => '\x0c',
+    'v' => '\x0b',
+    'x' => {
+      let mut digits = String::new();
+      while let Some(digit) = chars.next_if(|c| c.is_ascii_hexdigit() && digits.len() < 2) {
+        digits.push(digit);
+      }
+
+      match u8::from_str_radix(&digits, 16) {
+        Ok(byte) => char::from(byte),
+        Err(_) => return "\\x".to_string(),
+      }
+    }
+    c => return format!("\\{c}"),
+  };
+
+  c.to_string()
+}
+
+/// Formats `args` with `format` like the shell's `printf`.
+///
+/// Supports the conversions `%s`, `%d`, `%i`, `%u`, `%x`, `%X`, `%o`, `%f`, `%c` and `%%`, with
+/// the `-` and `0` flags, a width and a precision, and the escapes of [`unescape`]. The format is
+/// repeated until every argument is used, and missing arguments are empty or zero.
+pub fn printf(format: &str, args: &[String]) -> Result<String, String> {
+  let mut output = String::new();
+  let mut args = args.iter().map(String::as_str);
+  let mut remaining = args.len();
+
+  loop {
+    let consumed = format_once(format, &mut args, &mut output)?;
+    remaining = remaining.saturating_sub(consumed);
+
+    if remaining == 0 || consumed == 0 {
+      return Ok(output);
+    }
+  }
+}
+
+/// Formats `args` with `format` once, returning how many arguments were used.
+fn format_once<'a>(format: &str, args: &mut impl Iterator<Item = &'a str>, output: &mut String) -> Result<usize, String> {
+  let mut consumed = 0;
+  let mut chars = format.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => match chars.next() {
+        Some('c') => break,
+        Some(c) => output.push_str(&escape(c, &mut chars)),
+        None => output.push('\\'),
+      },
+      '%' => {
+        if chars.next_if_eq(&'%').is_some() {
+          output.push('%');
+          continue;
+        }
+
+        let spec = Spec::parse(&mut chars)?;
+        let arg = args.next();
+        consumed += usize::from(arg.is_some());
+
+        output.push_str(&spec.format(arg.unwrap_or_default())?);
+      }
+      c => output.push(c),
+    }
+  }
+
+  Ok(consumed)
+}
+
+/// A conversion specification of a `printf` format, e.g. `%-8.3s`.
+struct Spec {
+  left: bool,
+
+  zero: bool,
+
+  width: usize,
+
+  precision: Option<usize>,
+
+  conversion: char,
+}
+
+impl Spec {
+  /// Parses a specification after its `%`.
+  fn parse(chars: &mut Peekable<Chars>) -> Result<Self, String> {
+    let mut spec = Spec {
+      left: false,
+      zero: false,
+      width: 0,
+      precision: None,
+      conversion: 's',
+    };
+
+    while let Some(flag) = chars.next_if(|c| matches!(c, '-' | '0')) {
+      match flag {
+        '-' => spec.left = true,
+        _ => spec.zero = true,
+      }
+    }
+
+    spec.width = number(chars);
+    if chars.next_if_eq(&'.').is_some() {
+      spec.precision = Some(number(chars));
+    }
+
+    spec.conversion = match chars.next() {
+      Some(c @ ('s' | 'd' | 'i' | 'u' | 'x' | 'X' | 'o' | 'f' | 'c')) => c,
+      Some(c) => return Err(format!("printf: invalid conversion `%{c}`")),
+      None => return Err("printf: missing conversion after `%`".to_string()),
+    };
+
+    Ok(spec)
+  }
+
+  fn format(&self, arg: &str) -> Result<String, String> {
+    let integer = || -> Result<i64, String> {
+      if arg.is_empty() {
+        Ok(0)
+      } else {
+        arg.parse().map_err(|_| format!("printf: invalid number `{arg}`"))
+      }
+    };
+
+    let formatted = match self.conversion {
+      's' => match self.precision {
+        Some(precision) => arg.chars().take(precision).collect(),
+        None => arg.to_owned(),
+      },
+      'c' => arg.chars().next().map(String::from).unwrap_or_default(),
+      'd' | 'i' | 'u' => integer()?.to_string(),
+      'x' => format!("{:x}", integer()?),
+      'X' => format!("{:X}", integer()?),
+      'o' => format!("{:o}", integer()?),
+      _ => {
+        let float: f64 = if arg.is_empty() {
+          0.0
+        } else {
+          arg.parse().map_err(|_| format!("printf: invalid number `{arg}`"))?
+        };
+
+        format!("{float:.*}", self.precision.unwrap_or(6))
+      }
+    };
+
+    Ok(self.pad(formatted))
+  }
+
+  /// Pads `formatted` to the width, with zeros after any sign for numbers with the `0` flag.
+  fn pad(&self, formatted: String) -> String {
+    let len = formatted.chars().count();
+    if len >= self.width {
+      return formatted;
+    }
+
+    let padding = self.width - len;
+
+    if self.left {
+      format!("{formatted}{}", " ".repeat(padding))
+    } else if self.zero && !matches!(self.conversion, 's' | 'c') {
+      let (sign, digits) = match formatted.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", formatted.as_str()),
+      };
+
+      format!("{sign}{}{digits}", "0".repeat(padding))
+    } else {
+      format!("{}{formatted}", " ".repeat(padding))
+    }
+  }
+}
+
+/// Reads a decimal number from `chars`, or 0 if there are no digits.
+fn number(chars: &mut Peekable<Chars>) -> usize {
+  let mut number = 0usize;
+
+  while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+    number = number.saturating_mul(10).saturating_add(digit as usize - '0' as usize);
+  }
+
+  number
+}
@@ -1,6 +1,145 @@
-use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use std::io::{self, Write};
 
-/// Returns a vector of random ascii characters of a specified size
-pub fn random_ascii(n: usize) -> Vec<u8> {
-  thread_rng().sample_iter(&Alphanumeric).take(n).collect()
+use session::generate::FileSize;
+
+/// Returns `bytes` formatted with a binary unit suffix, e.g. `1.5K`.
+pub fn human_size(bytes: u64) -> String {
+  const UNITS: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+
+  if bytes < 1024 {
+    return bytes.to_string();
+  }
+
+  let mut size = bytes as f64 / 1024.0;
+  let mut unit = 0;
+
+  while size >= 1024.0 && unit < UNITS.len() - 1 {
+    size /= 1024.0;
+    unit += 1;
+  }
+
+  format!("{size:.1}{}", UNITS[unit])
+}
+
+/// Parses a size in bytes with an optional binary unit suffix, e.g. `10M`.
+pub fn parse_size(s: &str) -> Result<u64, String> {
+  let (digits, shift) = match s.char_indices().last() {
+    Some((i, 'K' | 'k')) => (&s[..i], 10),
+    Some((i, 'M' | 'm')) => (&s[..i], 20),
+    Some((i, 'G' | 'g')) => (&s[..i], 30),
+    Some((i, 'T' | 't')) => (&s[..i], 40),
+    _ => (s, 0),
+  };
+
+  let size: u64 = digits.parse().map_err(|_| format!("invalid size: {s}"))?;
+
+  size.checked_mul(1 << shift).ok_or_else(|| format!("size too large: {s}"))
+}
+
+/// Parses a probability between 0 and 1, e.g. `0.25`.
+pub fn parse_probability(s: &str) -> Result<f64, String> {
+  let probability: f64 = s.parse().map_err(|_| format!("invalid probability: {s}"))?;
+
+  if !(0.0..=1.0).contains(&probability) {
+    return Err(format!("probability must be between 0 and 1: {s}"));
+  }
+
+  Ok(probability)
+}
+
+/// Parses a distribution of file sizes: a fixed size like `4K`, a uniform range like `1K-1M`, or
+/// an exponential distribution with a mean like `exp:64K`.
+pub fn parse_file_size(s: &str) -> Result<FileSize, String> {
+  if let Some(mean) = s.strip_prefix("exp:") {
+    return Ok(FileSize::Exponential { mean: parse_size(mean)? });
+  }
+
+  match s.split_once('-') {
+    Some((min, max)) => {
+      let (min, max) = (parse_size(min)?, parse_size(max)?);
+      if min > max {
+        return Err(format!("invalid range: {s}"));
+      }
+
+      Ok(FileSize::Uniform { min, max })
+    }
+    None => Ok(FileSize::Fixed(parse_size(s)?)),
+  }
+}
+
+/// The most bytes held in memory as the input or output of a command, like a file read whole to
+/// be sorted or the output piped into the next command.
+pub const MAX_BUFFER_LEN: usize = 1 << 30;
+
+/// Output held in memory, like the output piped into the next command, failing instead of
+/// growing past [`MAX_BUFFER_LEN`].
+#[derive(Default)]
+pub struct Buffer {
+  content: Vec<u8>,
+}
+
+impl Buffer {
+  pub fn as_bytes(&self) -> &[u8] {
+    &self.content
+  }
+
+  pub fn into_inner(self) -> Vec<u8> {
+    self.content
+  }
+}
+
+impl Write for Buffer {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    let too_large = || io::Error::new(io::ErrorKind::OutOfMemory, "output is too large to hold in memory");
+
+    if buf.len() > MAX_BUFFER_LEN - self.content.len() {
+      return Err(too_large());
+    }
+
+    self.content.try_reserve(buf.len()).map_err(|_| too_large())?;
+    self.content.extend_from_slice(buf);
+
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+/// Standard output, tracking whether the output so far ends a line.
+pub struct Stdout {
+  line_start: bool,
+}
+
+impl Stdout {
+  pub fn new() -> Self {
+    Self { line_start: true }
+  }
+
+  /// Returns whether nothing was written since the last newline or [`Self::reset`].
+  pub fn at_line_start(&self) -> bool {
+    self.line_start
+  }
+
+  /// Marks the output as being at the start of a line, after a newline was printed elsewhere.
+  pub fn reset(&mut self) {
+    self.line_start = true;
+  }
+}
+
+impl Write for Stdout {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    let written = io::stdout().write(buf)?;
+
+    if let Some(last) = buf[..written].last() {
+      self.line_start = *last == b'\n';
+    }
+
+    Ok(written)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    io::stdout().flush()
+  }
 }
@@ -0,0 +1,299 @@
+use std::{
+  io,
+  os::unix::net::UnixStream,
+  path::{Path, PathBuf},
+};
+
+use fs::{Compression, Limits};
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use session::{
+  fault::Rule,
+  find::Predicate,
+  grep::{GrepMatch, GrepOptions},
+  listing::ListEntry,
+  tree::{Tree, TreeOptions},
+  usage::{StorageStats, Usage},
+  walk::WalkOptions,
+  Error, Result, SessionApi,
+};
+
+use crate::protocol::{self, Request, Response};
+
+/// A client of a session hosted by a [`Server`], implementing [`SessionApi`] by sending each
+/// operation as a request over a Unix domain socket.
+///
+/// Failures of the connection itself are returned as [`Error::Connection`].
+///
+/// [`Server`]: crate::Server
+pub struct Client {
+  stream: Mutex<UnixStream>,
+
+  /// The current directory of this connection on the server as of the last response with it,
+  /// returned if asking for it fails.
+  current_directory: Mutex<PathBuf>,
+}
+
+impl Client {
+  /// Connects to the server listening on the socket at `path`.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if connecting fails.
+  pub fn connect<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+    Ok(Self {
+      stream: Mutex::new(UnixStream::connect(path)?),
+      current_directory: Mutex::new(PathBuf::from("/")),
+    })
+  }
+
+  /// Sends `request` with `data`, returning the result and data of the response.
+  fn call<T: DeserializeOwned>(&self, request: &Request, data: &[u8]) -> Result<(T, Vec<u8>)> {
+    let connection = |err: io::Error| Error::Connection(err.to_string());
+    let mut stream = self.stream.lock();
+
+    protocol::write_frame(&mut *stream, request, data).map_err(connection)?;
+    let (response, data) = protocol::read_frame(&mut *stream)
+      .map_err(connection)?
+      .ok_or_else(|| Error::Connection("the server closed the connection".to_string()))?;
+
+    match response {
+      Response::Ok(value) => Ok((serde_json::from_value(value).map_err(|err| connection(err.into()))?, data)),
+      Response::Err(err) => Err(err),
+    }
+  }
+
+  /// Sends `request`, returning the result of the response.
+  fn request<T: DeserializeOwned>(&self, request: &Request) -> Result<T> {
+    self.call(request, &[]).map(|(value, _)| value)
+  }
+}
+
+impl SessionApi for Client {
+  fn current_directory(&self) -> PathBuf {
+    let mut current_directory = self.current_directory.lock();
+
+    // another connection may have moved it, so it is asked for each time
+    if let Ok(asked) = self.request(&Request::CurrentDirectory) {
+      *current_directory = asked;
+    }
+
+    current_directory.clone()
+  }
+
+  fn change_directory(&mut self, path: &Path) -> Result<()> {
+    *self.current_directory.get_mut() = self.request(&Request::ChangeDirectory { path: path.to_owned() })?;
+
+    Ok(())
+  }
+
+  fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
+    self.request(&Request::Canonicalize { path: path.to_owned() })
+  }
+
+  fn create_directory(&mut self, path: &Path) -> Result<()> {
+    self.request(&Request::CreateDirectory { path: path.to_owned() })
+  }
+
+  fn create_file(&mut self, path: &Path) -> Result<()> {
+    self.request(&Request::CreateFile { path: path.to_owned() })
+  }
+
+  fn remove(&mut self, path: &Path) -> Result<()> {
+    *self.current_directory.get_mut() = self.request(&Request::Remove { path: path.to_owned() })?;
+
+    Ok(())
+  }
+
+  fn move_entry(&mut self, src: &Path, dst: &Path) -> Result<()> {
+    *self.current_directory.get_mut() = self.request(&Request::MoveEntry {
+      src: src.to_owned(),
+      dst: dst.to_owned(),
+    })?;
+
+    Ok(())
+  }
+
+  fn copy_entry(&mut self, src: &Path, dst: &Path) -> Result<()> {
+    self.request(&Request::CopyEntry {
+      src: src.to_owned(),
+      dst: dst.to_owned(),
+    })
+  }
+
+  fn stat(&self, path: &Path) -> Result<ListEntry> {
+    self.request(&Request::Stat { path: path.to_owned() })
+  }
+
+  fn read_dir_with_metadata(&self, path: &Path) -> Result<Vec<ListEntry>> {
+    self.request(&Request::ReadDirWithMetadata { path: path.to_owned() })
+  }
+
+  fn glob(&self, pattern: &Path) -> Result<Vec<PathBuf>> {
+    self.request(&Request::Glob {
+      pattern: pattern.to_owned(),
+    })
+  }
+
+  fn find(&self, root: &Path, predicate: &Predicate, options: WalkOptions) -> Result<Vec<PathBuf>> {
+    self.request(&Request::Find {
+      root: root.to_owned(),
+      predicate: predicate.clone(),
+      options,
+    })
+  }
+
+  fn tree(&self, root: &Path, options: &TreeOptions) -> Result<Tree> {
+    self.request(&Request::Tree {
+      root: root.to_owned(),
+      options: options.clone(),
+    })
+  }
+
+  fn grep(&self, root: &Path, pattern: &str, options: &GrepOptions) -> Result<Vec<GrepMatch>> {
+    self.request(&Request::Grep {
+      root: root.to_owned(),
+      pattern: pattern.to_owned(),
+      options: options.clone(),
+    })
+  }
+
+  fn disk_usage(&self, path: &Path) -> Result<Usage> {
+    self.request(&Request::DiskUsage { path: path.to_owned() })
+  }
+
+  fn filesystem_usage(&self) -> Result<Usage> {
+    self.request(&Request::FilesystemUsage)
+  }
+
+  fn storage_stats(&self) -> Result<StorageStats> {
+    self.request(&Request::StorageStats)
+  }
+
+  fn limits(&self) -> Result<Limits> {
+    self.request(&Request::Limits)
+  }
+
+  fn set_limits(&mut self, limits: Limits) -> Result<()> {
+    self.request(&Request::SetLimits { limits })
+  }
+
+  fn set_compression(&mut self, path: &Path, compression: Compression) -> Result<()> {
+    self.request(&Request::SetCompression {
+      path: path.to_owned(),
+      compression,
+    })
+  }
+
+  fn write_file(&mut self, path: &Path, content: Vec<u8>) -> Result<usize> {
+    self
+      .call(&Request::WriteFile { path: path.to_owned() }, &content)
+      .map(|(written, _)| written)
+  }
+
+  fn write_file_at(&mut self, path: &Path, offset: u64, content: Vec<u8>) -> Result<usize> {
+    self
+      .call(
+        &Request::WriteFileAt {
+          path: path.to_owned(),
+          offset,
+        },
+        &content,
+      )
+      .map(|(written, _)| written)
+  }
+
+  fn truncate(&mut self, path: &Path, len: u64) -> Result<()> {
+    self.request(&Request::Truncate {
+      path: path.to_owned(),
+      len,
+    })
+  }
+
+  fn punch_hole(&mut self, path: &Path, offset: u64, len: u64) -> Result<()> {
+    self.request(&Request::PunchHole {
+      path: path.to_owned(),
+      offset,
+      len,
+    })
+  }
+
+  fn seek_data(&self, path: &Path, offset: u64) -> Result<Option<u64>> {
+    self.request(&Request::SeekData {
+      path: path.to_owned(),
+      offset,
+    })
+  }
+
+  fn seek_hole(&self, path: &Path, offset: u64) -> Result<Option<u64>> {
+    self.request(&Request::SeekHole {
+      path: path.to_owned(),
+      offset,
+    })
+  }
+
+  fn read_file(&self, path: &Path) -> Result<String> {
+    let (_, content) = self.call::<()>(&Request::ReadFile { path: path.to_owned() }, &[])?;
+
+    String::from_utf8(content).map_err(|err| Error::Connection(err.to_string()))
+  }
+
+  fn read_file_at(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>> {
+    let request = Request::ReadFileAt {
+      path: path.to_owned(),
+      offset,
+      len,
+    };
+
+    self.call::<()>(&request, &[]).map(|((), content)| content)
+  }
+
+  fn set_xattr(&mut self, path: &Path, name: &str, value: Vec<u8>) -> Result<()> {
+    let request = Request::SetXattr {
+      path: path.to_owned(),
+      name: name.to_owned(),
+    };
+
+    self.call(&request, &value).map(|((), _)| ())
+  }
+
+  fn get_xattr(&self, path: &Path, name: &str) -> Result<Vec<u8>> {
+    let request = Request::GetXattr {
+      path: path.to_owned(),
+      name: name.to_owned(),
+    };
+
+    self.call::<()>(&request, &[]).map(|((), value)| value)
+  }
+
+  fn list_xattr(&self, path: &Path) -> Result<Vec<String>> {
+    self.request(&Request::ListXattr { path: path.to_owned() })
+  }
+
+  fn remove_xattr(&mut self, path: &Path, name: &str) -> Result<()> {
+    self.request(&Request::RemoveXattr {
+      path: path.to_owned(),
+      name: name.to_owned(),
+    })
+  }
+
+  fn add_fault(&mut self, rule: Rule) -> Result<usize> {
+    self.request(&Request::AddFault { rule })
+  }
+
+  fn fault_rules(&self) -> Result<Vec<(usize, Rule, u64)>> {
+    self.request(&Request::FaultRules)
+  }
+
+  fn remove_fault(&mut self, id: usize) -> Result<bool> {
+    self.request(&Request::RemoveFault { id })
+  }
+
+  fn set_fault_enabled(&mut self, id: usize, enabled: bool) -> Result<bool> {
+    self.request(&Request::SetFaultEnabled { id, enabled })
+  }
+
+  fn clear_faults(&mut self) -> Result<()> {
+    self.request(&Request::ClearFaults)
+  }
+}
@@ -0,0 +1,219 @@
+pub mod client;
+pub mod protocol;
+
+use std::{
+  collections::HashMap,
+  io::{self, BufReader, BufWriter, Write},
+  os::unix::net::{UnixListener, UnixStream},
+  path::{Path, PathBuf},
+  sync::Arc,
+  thread,
+};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::Value;
+use session::{Error, Result, Session, SessionApi};
+
+pub use self::client::Client;
+use self::protocol::{Request, Response};
+
+/// A server sharing one [`Session`] between the clients connected to a Unix domain socket.
+///
+/// Each connection has its own current directory, starting at `/` and following it being moved
+/// by any connection, and its requests are handled one at a time. Requests of different connections are serialized on the session.
+pub struct Server {
+  shared: Arc<Mutex<Shared>>,
+}
+
+/// The session shared by the connections of a server, with the current directory of each.
+struct Shared {
+  session: Session,
+
+  /// The current directories of the open connections, by connection id.
+  current_directories: HashMap<u64, PathBuf>,
+
+  next_id: u64,
+}
+
+impl Server {
+  /// Creates a server sharing `session`.
+  #[must_use]
+  pub fn new(session: Session) -> Self {
+    Self {
+      shared: Arc::new(Mutex::new(Shared {
+        session,
+        current_directories: HashMap::new(),
+        next_id: 0,
+      })),
+    }
+  }
+
+  /// Accepts connections on `listener` forever, serving each on its own thread. An error reading
+  /// or writing a connection closes it and is passed to `on_error`.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if accepting a connection fails.
+  pub fn serve<F>(&self, listener: &UnixListener, on_error: F) -> io::Result<()>
+  where
+    F: Fn(io::Error) + Clone + Send + 'static,
+  {
+    for stream in listener.incoming() {
+      let stream = stream?;
+      let shared = Arc::clone(&self.shared);
+      let on_error = on_error.clone();
+
+      thread::spawn(move || {
+        let id = shared.lock().connect();
+        let res = serve_connection(&shared, id, &stream);
+        shared.lock().current_directories.remove(&id);
+
+        if let Err(err) = res {
+          on_error(err);
+        }
+      });
+    }
+
+    Ok(())
+  }
+}
+
+impl Shared {
+  /// Registers a new connection with `/` as its current directory, returning its id.
+  fn connect(&mut self) -> u64 {
+    let id = self.next_id;
+    self.next_id += 1;
+    self.current_directories.insert(id, PathBuf::from("/"));
+
+    id
+  }
+
+  /// Runs `request` of the connection `id` in its current directory. When a directory is moved,
+  /// the current directories of the other connections follow it like the connection's own.
+  fn handle(&mut self, id: u64, request: Request, data: Vec<u8>) -> Result<(Value, Vec<u8>)> {
+    let current_directory = self
+      .current_directories
+      .get_mut(&id)
+      .expect("connections are registered until they are closed");
+
+    let (res, moved) = self.session.with_current_directory(current_directory, |session| {
+      let moved = match &request {
+        Request::MoveEntry { src, dst } => session.absolute(src).and_then(|src| Ok((src, session.absolute(dst)?))).ok(),
+        _ => None,
+      };
+
+      (handle(session, request, data), moved)
+    })?;
+
+    if let (Ok(_), Some((src, dst))) = (&res, moved) {
+      for (_, current_directory) in self.current_directories.iter_mut().filter(|(other, _)| **other != id) {
+        follow_move(current_directory, &src, &dst);
+      }
+    }
+
+    res
+  }
+}
+
+/// Handles the requests of the connection `id` until it is closed.
+fn serve_connection(shared: &Mutex<Shared>, id: u64, stream: &UnixStream) -> io::Result<()> {
+  let mut reader = BufReader::new(stream);
+  let mut writer = BufWriter::new(stream);
+
+  while let Some((request, data)) = protocol::read_frame(&mut reader)? {
+    let res = shared.lock().handle(id, request, data);
+    respond(&mut writer, res)?;
+  }
+
+  Ok(())
+}
+
+/// Updates `current_directory` if it is in the entry moved from `src` to `dst`.
+fn follow_move(current_directory: &mut PathBuf, src: &Path, dst: &Path) {
+  if let Ok(suffix) = current_directory.strip_prefix(src) {
+    let mut moved = dst.to_owned();
+    if !suffix.as_os_str().is_empty() {
+      moved.push(suffix);
+    }
+
+    *current_directory = moved;
+  }
+}
+
+/// Writes the response to a request. A response too large to be sent is replaced with an error,
+/// so that the connection can still be used.
+fn respond<W: Write>(writer: &mut W, res: Result<(Value, Vec<u8>)>) -> io::Result<()> {
+  let written = match res {
+    Ok((value, data)) => protocol::write_frame(writer, &Response::Ok(value), &data),
+    Err(err) => protocol::write_frame(writer, &Response::Err(err), &[]),
+  };
+
+  match written {
+    // nothing was written, so the error can still be sent in its place
+    Err(err) if err.kind() == io::ErrorKind::InvalidInput => {
+      protocol::write_frame(writer, &Response::Err(Error::Connection(err.to_string())), &[])
+    }
+    written => written,
+  }
+}
+
+/// Runs `request` on `session`, with `data` as the content of writes, returning the JSON result
+/// and any bytes read.
+fn handle(session: &mut dyn SessionApi, request: Request, data: Vec<u8>) -> Result<(Value, Vec<u8>)> {
+  let value = match request {
+    Request::CurrentDirectory => json(session.current_directory())?,
+    Request::ChangeDirectory { path } => {
+      session.change_directory(&path)?;
+      json(session.current_directory())?
+    }
+    Request::Canonicalize { path } => json(session.canonicalize(&path)?)?,
+    Request::CreateDirectory { path } => session.create_directory(&path).map(|()| Value::Null)?,
+    Request::CreateFile { path } => session.create_file(&path).map(|()| Value::Null)?,
+    Request::Remove { path } => {
+      session.remove(&path)?;
+      json(session.current_directory())?
+    }
+    Request::MoveEntry { src, dst } => {
+      session.move_entry(&src, &dst)?;
+      json(session.current_directory())?
+    }
+    Request::CopyEntry { src, dst } => session.copy_entry(&src, &dst).map(|()| Value::Null)?,
+    Request::Stat { path } => json(session.stat(&path)?)?,
+    Request::ReadDirWithMetadata { path } => json(session.read_dir_with_metadata(&path)?)?,
+    Request::Glob { pattern } => json(session.glob(&pattern)?)?,
+    Request::Find { root, predicate, options } => json(session.find(&root, &predicate, options)?)?,
+    Request::Tree { root, options } => json(session.tree(&root, &options)?)?,
+    Request::Grep { root, pattern, options } => json(session.grep(&root, &pattern, &options)?)?,
+    Request::DiskUsage { path } => json(session.disk_usage(&path)?)?,
+    Request::FilesystemUsage => json(session.filesystem_usage()?)?,
+    Request::StorageStats => json(session.storage_stats()?)?,
+    Request::Limits => json(session.limits()?)?,
+    Request::SetLimits { limits } => session.set_limits(limits).map(|()| Value::Null)?,
+    Request::SetCompression { path, compression } => session.set_compression(&path, compression).map(|()| Value::Null)?,
+    Request::WriteFile { path } => json(session.write_file(&path, data)?)?,
+    Request::WriteFileAt { path, offset } => json(session.write_file_at(&path, offset, data)?)?,
+    Request::Truncate { path, len } => session.truncate(&path, len).map(|()| Value::Null)?,
+    Request::PunchHole { path, offset, len } => session.punch_hole(&path, offset, len).map(|()| Value::Null)?,
+    Request::SeekData { path, offset } => json(session.seek_data(&path, offset)?)?,
+    Request::SeekHole { path, offset } => json(session.seek_hole(&path, offset)?)?,
+    Request::ReadFile { path } => return Ok((Value::Null, session.read_file(&path)?.into_bytes())),
+    Request::ReadFileAt { path, offset, len } => return Ok((Value::Null, session.read_file_at(&path, offset, len)?)),
+    Request::SetXattr { path, name } => session.set_xattr(&path, &name, data).map(|()| Value::Null)?,
+    Request::GetXattr { path, name } => return Ok((Value::Null, session.get_xattr(&path, &name)?)),
+    Request::ListXattr { path } => json(session.list_xattr(&path)?)?,
+    Request::RemoveXattr { path, name } => session.remove_xattr(&path, &name).map(|()| Value::Null)?,
+    Request::AddFault { rule } => json(session.add_fault(rule)?)?,
+    Request::FaultRules => json(session.fault_rules()?)?,
+    Request::RemoveFault { id } => json(session.remove_fault(id)?)?,
+    Request::SetFaultEnabled { id, enabled } => json(session.set_fault_enabled(id, enabled)?)?,
+    Request::ClearFaults => session.clear_faults().map(|()| Value::Null)?,
+  };
+
+  Ok((value, Vec::new()))
+}
+
+/// Returns the JSON form of a result, which fails for paths that aren't valid UTF-8.
+fn json<T: Serialize>(value: T) -> Result<Value> {
+  serde_json::to_value(value).map_err(|err| Error::Connection(err.to_string()))
+}
@@ -0,0 +1,64 @@
+use std::{
+  os::unix::net::{UnixListener, UnixStream},
+  path::PathBuf,
+  process::ExitCode,
+};
+
+use clap::Parser;
+use fs::{Compression, Filesystem};
+use server::Server;
+use session::Session;
+
+/// Serves a shared in-memory filesystem over a Unix domain socket.
+///
+/// Clients like `repl --connect <socket>` each get their own current directory, but see the same
+/// files.
+#[derive(Parser)]
+struct Args {
+  /// The path of the socket to listen on.
+  socket: PathBuf,
+
+  /// Deduplicate file contents by storing identical blocks once.
+  #[clap(long)]
+  dedup: bool,
+
+  /// Compress the contents of new files in memory.
+  #[clap(long)]
+  compress: bool,
+}
+
+fn main() -> ExitCode {
+  let args = Args::parse();
+
+  let mut filesystem = if args.dedup {
+    Filesystem::deduplicated()
+  } else {
+    Filesystem::new()
+  };
+
+  if args.compress {
+    filesystem.compression = Compression::Lz4;
+  }
+
+  // a socket left behind by a server that exited can't be bound again
+  if args.socket.exists() && UnixStream::connect(&args.socket).is_err() {
+    let _ = std::fs::remove_file(&args.socket);
+  }
+
+  let listener = match UnixListener::bind(&args.socket) {
+    Ok(listener) => listener,
+    Err(err) => {
+      eprintln!("failed to listen on {:?}: {err}", args.socket);
+      return ExitCode::FAILURE;
+    }
+  };
+
+  println!("listening on {:?}", args.socket);
+
+  if let Err(err) = Server::new(Session::new(filesystem)).serve(&listener, |err| eprintln!("connection failed: {err}")) {
+    eprintln!("{err}");
+    return ExitCode::FAILURE;
+  }
+
+  ExitCode::SUCCESS
+}
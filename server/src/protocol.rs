@@ -0,0 +1,242 @@
+use std::{
+  io::{self, Read, Write},
+  path::PathBuf,
+};
+
+use fs::{Compression, Limits};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use session::{fault::Rule, find::Predicate, grep::GrepOptions, tree::TreeOptions, walk::WalkOptions, Error};
+
+/// The largest header accepted in a frame, to fail fast on corrupt streams.
+pub const MAX_HEADER_LEN: u32 = 64 << 20;
+
+/// The largest data accepted in a frame, so a corrupt length can't make the reader buffer
+/// without bound.
+pub const MAX_DATA_LEN: u64 = 1 << 30;
+
+/// A request to run an operation of [`SessionApi`] on the server's session.
+///
+/// Requests are JSON objects with the operation's name in `op` and its arguments as the other
+/// fields, e.g. `{"op":"create_file","path":"/a"}`. The content written by `write_file`,
+/// `write_file_at` and the value set by `set_xattr` are sent as the frame's data instead.
+///
+/// [`SessionApi`]: session::SessionApi
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Request {
+  /// Responds with the connection's current directory.
+  CurrentDirectory,
+
+  /// Changes the connection's current directory, responding with the new one.
+  ChangeDirectory {
+    path: PathBuf,
+  },
+  Canonicalize {
+    path: PathBuf,
+  },
+  CreateDirectory {
+    path: PathBuf,
+  },
+  CreateFile {
+    path: PathBuf,
+  },
+  /// Removes an entry, responding with the connection's current directory.
+  Remove {
+    path: PathBuf,
+  },
+
+  /// Moves an entry, responding with the connection's current directory. The current directories
+  /// of all connections follow it being moved.
+  MoveEntry {
+    src: PathBuf,
+    dst: PathBuf,
+  },
+  CopyEntry {
+    src: PathBuf,
+    dst: PathBuf,
+  },
+  Stat {
+    path: PathBuf,
+  },
+  ReadDirWithMetadata {
+    path: PathBuf,
+  },
+  Glob {
+    pattern: PathBuf,
+  },
+  Find {
+    root: PathBuf,
+    predicate: Predicate,
+    options: WalkOptions,
+  },
+  Tree {
+    root: PathBuf,
+    options: TreeOptions,
+  },
+  Grep {
+    root: PathBuf,
+    pattern: String,
+    options: GrepOptions,
+  },
+  DiskUsage {
+    path: PathBuf,
+  },
+  FilesystemUsage,
+  StorageStats,
+  Limits,
+  SetLimits {
+    limits: Limits,
+  },
+  SetCompression {
+    path: PathBuf,
+    compression: Compression,
+  },
+  WriteFile {
+    path: PathBuf,
+  },
+  WriteFileAt {
+    path: PathBuf,
+    offset: u64,
+  },
+  Truncate {
+    path: PathBuf,
+    len: u64,
+  },
+  PunchHole {
+    path: PathBuf,
+    offset: u64,
+    len: u64,
+  },
+  SeekData {
+    path: PathBuf,
+    offset: u64,
+  },
+  SeekHole {
+    path: PathBuf,
+    offset: u64,
+  },
+
+  /// Reads a file, responding with its content as the frame's data.
+  ReadFile {
+    path: PathBuf,
+  },
+
+  /// Reads part of a file, responding with the bytes as the frame's data.
+  ReadFileAt {
+    path: PathBuf,
+    offset: u64,
+    len: usize,
+  },
+  SetXattr {
+    path: PathBuf,
+    name: String,
+  },
+
+  /// Reads an extended attribute, responding with its value as the frame's data.
+  GetXattr {
+    path: PathBuf,
+    name: String,
+  },
+  ListXattr {
+    path: PathBuf,
+  },
+  RemoveXattr {
+    path: PathBuf,
+    name: String,
+  },
+  AddFault {
+    rule: Rule,
+  },
+  FaultRules,
+  RemoveFault {
+    id: usize,
+  },
+  SetFaultEnabled {
+    id: usize,
+    enabled: bool,
+  },
+  ClearFaults,
+}
+
+/// The response to a [`Request`]: `{"ok":result}` with the JSON form of the operation's result,
+/// which is `null` for operations without one, or `{"err":error}` with the [`Error`] it failed
+/// with, e.g. `{"err":{"NotExist":"a"}}`.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Response {
+  Ok(Value),
+  Err(Error),
+}
+
+/// Writes a frame with a JSON `header` and binary `data`.
+///
+/// A frame is the length in bytes of the header as a big-endian `u32`, the length of the data as
+/// a big-endian `u64`, then the header and the data. Requests and responses are each sent as one
+/// frame, and a connection handles one request at a time.
+///
+/// # Errors
+///
+/// This function will return an error if writing fails, or with [`io::ErrorKind::InvalidInput`]
+/// before writing anything if the header or data is too long.
+pub fn write_frame<W: Write, T: Serialize>(writer: &mut W, header: &T, data: &[u8]) -> io::Result<()> {
+  let header = serde_json::to_vec(header)?;
+  let header_len = u32::try_from(header.len())
+    .ok()
+    .filter(|len| *len <= MAX_HEADER_LEN)
+    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "frame header is too long"))?;
+  let data_len = u64::try_from(data.len())
+    .ok()
+    .filter(|len| *len <= MAX_DATA_LEN)
+    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "frame data is too long"))?;
+
+  let mut frame = Vec::with_capacity(12 + header.len());
+  frame.extend_from_slice(&header_len.to_be_bytes());
+  frame.extend_from_slice(&data_len.to_be_bytes());
+  frame.extend_from_slice(&header);
+
+  writer.write_all(&frame)?;
+  writer.write_all(data)?;
+  writer.flush()
+}
+
+/// Reads a frame written by [`write_frame`], returning its header and data, or `None` if the
+/// stream ended before the frame started.
+///
+/// # Errors
+///
+/// This function will return an error if reading fails, the stream ends within the frame, the
+/// header is invalid or the header or data is too long.
+pub fn read_frame<R: Read, T: DeserializeOwned>(reader: &mut R) -> io::Result<Option<(T, Vec<u8>)>> {
+  let mut header_len = [0; 4];
+
+  match reader.read(&mut header_len[..1])? {
+    0 => return Ok(None),
+    _ => reader.read_exact(&mut header_len[1..])?,
+  }
+
+  let mut data_len = [0; 8];
+  reader.read_exact(&mut data_len)?;
+
+  let header_len = u32::from_be_bytes(header_len);
+  if header_len > MAX_HEADER_LEN {
+    return Err(io::Error::new(io::ErrorKind::InvalidData, "frame header is too long"));
+  }
+
+  let mut header = vec![0; header_len as usize];
+  reader.read_exact(&mut header)?;
+
+  let data_len = u64::from_be_bytes(data_len);
+  if data_len > MAX_DATA_LEN {
+    return Err(io::Error::new(io::ErrorKind::InvalidData, "frame data is too long"));
+  }
+
+  let mut data = Vec::new();
+  reader.take(data_len).read_to_end(&mut data)?;
+
+  if (data.len() as u64) < data_len {
+    return Err(io::ErrorKind::UnexpectedEof.into());
+  }
+
+  Ok(Some((serde_json::from_slice(&header)?, data)))
+}
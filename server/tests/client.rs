@@ -0,0 +1,141 @@
+use std::{
+  os::unix::net::UnixListener,
+  path::{Path, PathBuf},
+  thread,
+};
+
+use fs::Filesystem;
+use server::{protocol::MAX_DATA_LEN, Client, Server};
+use session::{find::Predicate, tree::TreeOptions, walk::WalkOptions, Error, Session, SessionApi};
+
+/// Starts a server on a new socket, returning its path.
+fn serve(name: &str) -> PathBuf {
+  let path = std::env::temp_dir().join(format!("memfs-{}-{name}.sock", std::process::id()));
+  let _ = std::fs::remove_file(&path);
+
+  let listener = UnixListener::bind(&path).unwrap();
+  thread::spawn(move || Server::new(Session::new(Filesystem::new())).serve(&listener, |err| panic!("connection failed: {err}")));
+
+  path
+}
+
+#[test]
+fn client_shared() {
+  let socket = serve("shared");
+  let mut a = Client::connect(&socket).unwrap();
+  let mut b = Client::connect(&socket).unwrap();
+
+  a.create_directory(Path::new("/a")).unwrap();
+  a.create_file(Path::new("/a/f")).unwrap();
+  assert_eq!(a.write_file(Path::new("/a/f"), b"hello".to_vec()).unwrap(), 5);
+
+  assert_eq!(b.read_file_at(Path::new("/a/f"), 1, 3).unwrap(), b"ell");
+  assert_eq!(b.read_file(Path::new("/a/f")).unwrap(), "hello");
+  assert_eq!(b.stat(Path::new("/a/f")).unwrap().len, 5);
+
+  b.set_xattr(Path::new("/a/f"), "user.tag", b"x".to_vec()).unwrap();
+  assert_eq!(a.get_xattr(Path::new("/a/f"), "user.tag").unwrap(), b"x");
+  assert_eq!(a.list_xattr(Path::new("/a/f")).unwrap(), ["user.tag"]);
+}
+
+#[test]
+fn client_current_directory() {
+  let socket = serve("current_directory");
+  let mut a = Client::connect(&socket).unwrap();
+  let mut b = Client::connect(&socket).unwrap();
+
+  a.create_directory(Path::new("/a")).unwrap();
+  a.change_directory(Path::new("a")).unwrap();
+  a.create_file(Path::new("f")).unwrap();
+
+  assert_eq!(a.current_directory(), Path::new("/a"));
+  assert_eq!(b.current_directory(), Path::new("/"));
+
+  b.create_file(Path::new("f")).unwrap();
+  assert!(b.stat(Path::new("/f")).is_ok());
+  assert!(b.stat(Path::new("/a/f")).is_ok());
+  assert_eq!(a.canonicalize(Path::new(".")).unwrap(), Path::new("/a"));
+}
+
+#[test]
+fn client_current_directory_moved() {
+  let socket = serve("current_directory_moved");
+  let mut client = Client::connect(&socket).unwrap();
+
+  client.create_directory(Path::new("/a")).unwrap();
+  client.create_directory(Path::new("/a/b")).unwrap();
+  client.change_directory(Path::new("/a/b")).unwrap();
+  client.move_entry(Path::new("/a"), Path::new("/c")).unwrap();
+
+  assert_eq!(client.current_directory(), Path::new("/c/b"));
+
+  client.create_file(Path::new("f")).unwrap();
+  assert!(client.stat(Path::new("/c/b/f")).is_ok());
+}
+
+#[test]
+fn client_error() {
+  let socket = serve("error");
+  let mut client = Client::connect(&socket).unwrap();
+
+  let err = client.remove(Path::new("/missing")).unwrap_err();
+  assert!(matches!(err, Error::NotExist(_)), "{err:?}");
+
+  client.create_directory(Path::new("/a")).unwrap();
+  let err = client.create_directory(Path::new("/a")).unwrap_err();
+  assert_eq!(err.code(), "exists");
+}
+
+#[test]
+fn client_find_tree() {
+  let socket = serve("find_tree");
+  let mut client = Client::connect(&socket).unwrap();
+
+  for path in ["/a", "/a/b"] {
+    client.create_directory(Path::new(path)).unwrap();
+  }
+  for path in ["/a/1.log", "/a/b/2.log", "/a/b/3.txt"] {
+    client.create_file(Path::new(path)).unwrap();
+  }
+
+  let found = client
+    .find(Path::new("/"), &Predicate::name("*.log").unwrap(), WalkOptions::new().min_depth(2))
+    .unwrap();
+  assert_eq!(found, [PathBuf::from("/a/1.log"), PathBuf::from("/a/b/2.log")]);
+
+  let tree = client.tree(Path::new("/a"), &TreeOptions::new().max_depth(1)).unwrap();
+  assert_eq!((tree.directories, tree.files), (1, 1));
+}
+
+#[test]
+fn client_response_too_large() {
+  let socket = serve("response_too_large");
+  let mut client = Client::connect(&socket).unwrap();
+
+  client.create_file(Path::new("/f")).unwrap();
+  client.truncate(Path::new("/f"), MAX_DATA_LEN + 1).unwrap();
+
+  let err = client.read_file_at(Path::new("/f"), 0, usize::MAX).unwrap_err();
+  assert!(matches!(err, Error::Connection(_)), "{err:?}");
+
+  // the connection is still usable
+  assert_eq!(client.stat(Path::new("/f")).unwrap().len, MAX_DATA_LEN + 1);
+}
+
+#[test]
+fn client_current_directory_moved_by_other() {
+  let socket = serve("current_directory_moved_by_other");
+  let mut a = Client::connect(&socket).unwrap();
+  let mut b = Client::connect(&socket).unwrap();
+
+  a.create_directory(Path::new("/a")).unwrap();
+  a.create_directory(Path::new("/a/b")).unwrap();
+  b.change_directory(Path::new("/a/b")).unwrap();
+  a.move_entry(Path::new("/a"), Path::new("/c")).unwrap();
+
+  assert_eq!(b.current_directory(), Path::new("/c/b"));
+  assert_eq!(a.current_directory(), Path::new("/"));
+
+  b.create_file(Path::new("f")).unwrap();
+  assert!(a.stat(Path::new("/c/b/f")).is_ok());
+}
@@ -0,0 +1,27 @@
+use std::io::{self, Cursor};
+
+use serde_json::Value;
+use server::protocol::{self, Request, MAX_DATA_LEN};
+
+#[test]
+fn frame_round_trip() {
+  let mut frame = Vec::new();
+  protocol::write_frame(&mut frame, &Request::FilesystemUsage, b"data").unwrap();
+
+  let (request, data) = protocol::read_frame::<_, Request>(&mut Cursor::new(frame)).unwrap().unwrap();
+
+  assert!(matches!(request, Request::FilesystemUsage));
+  assert_eq!(data, b"data");
+}
+
+#[test]
+fn frame_data_too_long() {
+  let mut frame = Vec::new();
+  frame.extend_from_slice(&2u32.to_be_bytes());
+  frame.extend_from_slice(&(MAX_DATA_LEN + 1).to_be_bytes());
+  frame.extend_from_slice(b"{}");
+
+  let err = protocol::read_frame::<_, Value>(&mut Cursor::new(frame)).unwrap_err();
+
+  assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,201 @@
+use std::path::PathBuf;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use fs::Filesystem;
+use session::{
+  generate::{FileSize, TreeGenerator},
+  walk::{Walk, WalkOptions},
+  Session,
+};
+
+/// Creates a session with a tree generated under `/tree`.
+fn tree(generator: &TreeGenerator) -> Session {
+  let mut session = Session::new(Filesystem::new());
+  generator.generate(&mut session, "/tree").unwrap();
+
+  session
+}
+
+/// Returns the path of the deepest directory of a tree generated with a fanout of 1.
+fn deepest(depth: usize) -> PathBuf {
+  let mut path = PathBuf::from("/tree");
+  path.extend((0..depth).map(|_| "d0"));
+
+  path
+}
+
+fn create_file(c: &mut Criterion) {
+  let mut group = c.benchmark_group("create_file");
+
+  for entries in [0, 10_000] {
+    group.bench_function(BenchmarkId::new("entries", entries), |b| {
+      b.iter_batched_ref(
+        || tree(&TreeGenerator::new().files(entries)),
+        |session| session.create_file("/tree/new").unwrap(),
+        BatchSize::LargeInput,
+      );
+    });
+  }
+
+  group.finish();
+}
+
+fn resolve(c: &mut Criterion) {
+  let mut group = c.benchmark_group("resolve");
+
+  for depth in [1, 10, 100] {
+    let mut session = tree(&TreeGenerator::new().depth(depth).fanout(1).files(1));
+    let directory = deepest(depth);
+    let file = directory.join("f0");
+
+    group.bench_with_input(BenchmarkId::new("absolute", depth), &file, |b, file| {
+      b.iter(|| session.resolve(file).unwrap());
+    });
+
+    session.change_directory(&directory).unwrap();
+
+    group.bench_with_input(BenchmarkId::new("relative", depth), &file, |b, _| {
+      b.iter(|| session.resolve("f0").unwrap());
+    });
+
+    group.bench_with_input(BenchmarkId::new("write_file", depth), &file, |b, _| {
+      b.iter(|| session.write_file("f0", b"x".to_vec()).unwrap());
+    });
+
+    group.bench_with_input(BenchmarkId::new("create_remove", depth), &file, |b, _| {
+      b.iter(|| {
+        session.create_file("new").unwrap();
+        session.remove("new").unwrap();
+      });
+    });
+  }
+
+  group.finish();
+}
+
+fn resolve_cache(c: &mut Criterion) {
+  let mut group = c.benchmark_group("resolve_cache");
+
+  // more directories than the path cache holds, so looking them up in turn always misses
+  let directories = 4_096;
+  let mut session = tree(&TreeGenerator::new().depth(100).fanout(1));
+  let files: Vec<_> = (0..directories)
+    .map(|i| {
+      let directory = deepest(100).join(format!("s{i}"));
+      session.create_directory(&directory).unwrap();
+      session.create_file(directory.join("f")).unwrap();
+
+      directory.join("f")
+    })
+    .collect();
+
+  group.bench_function("hit", |b| {
+    b.iter(|| session.resolve(&files[0]).unwrap());
+  });
+
+  let mut files = files.iter().cycle();
+
+  group.bench_function("miss", |b| {
+    b.iter(|| session.resolve(files.next().unwrap()).unwrap());
+  });
+
+  group.finish();
+}
+
+fn walk(c: &mut Criterion) {
+  let mut group = c.benchmark_group("walk");
+
+  let trees = [
+    ("wide", TreeGenerator::new().depth(1).fanout(100).files(100)),
+    ("deep", TreeGenerator::new().depth(1_000).fanout(1).files(10)),
+  ];
+
+  for (name, generator) in trees {
+    let session = tree(&generator);
+    let entries = session.walk_iter("/tree", WalkOptions::new()).unwrap().count();
+
+    group.throughput(Throughput::Elements(entries as u64));
+
+    group.bench_function(BenchmarkId::new("walk", name), |b| {
+      b.iter(|| session.walk("/tree", |_, _| Walk::Continue).unwrap());
+    });
+
+    group.bench_function(BenchmarkId::new("walk_iter", name), |b| {
+      b.iter(|| session.walk_iter("/tree", WalkOptions::new()).unwrap().count());
+    });
+  }
+
+  group.finish();
+}
+
+fn write_file(c: &mut Criterion) {
+  let mut group = c.benchmark_group("write_file");
+
+  for len in [1, 4096, 1024 * 1024] {
+    let content = vec![b'x'; len];
+
+    group.throughput(Throughput::Bytes(len as u64));
+
+    // every iteration appends to a new empty file so it doesn't grow without bound
+    group.bench_function(BenchmarkId::new("append", len), |b| {
+      b.iter_batched_ref(
+        || tree(&TreeGenerator::new().files(1)),
+        |session| session.write_file("/tree/f0", content.clone()).unwrap(),
+        BatchSize::SmallInput,
+      );
+    });
+  }
+
+  group.finish();
+}
+
+fn move_entry(c: &mut Criterion) {
+  let mut group = c.benchmark_group("move_entry");
+
+  for fanout in [2, 10] {
+    let generator = TreeGenerator::new()
+      .depth(3)
+      .fanout(fanout)
+      .files(10)
+      .file_size(FileSize::Fixed(64));
+    let mut session = tree(&generator);
+    let mut paths = ["/tree", "/moved"];
+
+    group.bench_function(BenchmarkId::new("fanout", fanout), |b| {
+      b.iter(|| {
+        session.move_entry(paths[0], paths[1]).unwrap();
+        paths.swap(0, 1);
+      });
+    });
+  }
+
+  group.finish();
+}
+
+fn list_directory(c: &mut Criterion) {
+  let mut group = c.benchmark_group("list_directory");
+
+  for entries in [1_000, 100_000] {
+    let session = tree(&TreeGenerator::new().files(entries));
+
+    group.throughput(Throughput::Elements(entries as u64));
+
+    group.bench_function(BenchmarkId::new("entries", entries), |b| {
+      b.iter(|| session.list_directory("/tree").unwrap().count());
+    });
+  }
+
+  group.finish();
+}
+
+criterion_group!(
+  benches,
+  create_file,
+  resolve,
+  resolve_cache,
+  walk,
+  write_file,
+  move_entry,
+  list_directory
+);
+criterion_main!(benches);
@@ -0,0 +1,304 @@
+use std::path::{Path, PathBuf};
+
+use fs::{Compression, Limits};
+
+use crate::{
+  fault::Rule,
+  find::Predicate,
+  grep::{GrepMatch, GrepOptions},
+  listing::ListEntry,
+  tree::{Tree, TreeOptions},
+  usage::{StorageStats, Usage},
+  walk::WalkOptions,
+  Result, Session,
+};
+
+/// The operations of a [`Session`], implemented by [`Session`] itself and by clients of sessions
+/// hosted in other processes.
+///
+/// Unlike the methods of [`Session`], these return owned values instead of borrowing from the
+/// filesystem, and operations that can't fail on a local session return a [`Result`] since
+/// reaching a remote one can. Each method behaves like the method of [`Session`] with the same
+/// name, which documents its errors.
+pub trait SessionApi {
+  /// Returns the current directory.
+  fn current_directory(&self) -> PathBuf;
+
+  /// Changes the current directory.
+  fn change_directory(&mut self, path: &Path) -> Result<()>;
+
+  /// Returns the canonical form of `path`.
+  fn canonicalize(&self, path: &Path) -> Result<PathBuf>;
+
+  /// Creates a new directory.
+  fn create_directory(&mut self, path: &Path) -> Result<()>;
+
+  /// Creates a new empty file.
+  fn create_file(&mut self, path: &Path) -> Result<()>;
+
+  /// Removes a file or directory.
+  fn remove(&mut self, path: &Path) -> Result<()>;
+
+  /// Moves a file or directory.
+  fn move_entry(&mut self, src: &Path, dst: &Path) -> Result<()>;
+
+  /// Copies a file or directory.
+  fn copy_entry(&mut self, src: &Path, dst: &Path) -> Result<()>;
+
+  /// Returns the metadata of the entry at `path`.
+  fn stat(&self, path: &Path) -> Result<ListEntry>;
+
+  /// Returns the entries of a directory with their metadata.
+  fn read_dir_with_metadata(&self, path: &Path) -> Result<Vec<ListEntry>>;
+
+  /// Returns the existing paths matching `pattern`.
+  fn glob(&self, pattern: &Path) -> Result<Vec<PathBuf>>;
+
+  /// Returns the paths of the entries under `root` that match `predicate`. Only the depths and
+  /// order of `options` are guaranteed to be used.
+  fn find(&self, root: &Path, predicate: &Predicate, options: WalkOptions) -> Result<Vec<PathBuf>>;
+
+  /// Builds the tree of entries under `root`.
+  fn tree(&self, root: &Path, options: &TreeOptions) -> Result<Tree>;
+
+  /// Searches the contents of files under `root` for lines matching `pattern`.
+  fn grep(&self, root: &Path, pattern: &str, options: &GrepOptions) -> Result<Vec<GrepMatch>>;
+
+  /// Returns the space used by an entry and its descendants.
+  fn disk_usage(&self, path: &Path) -> Result<Usage>;
+
+  /// Returns the space used by the whole filesystem.
+  fn filesystem_usage(&self) -> Result<Usage>;
+
+  /// Returns how many bytes are stored for the contents of all files.
+  fn storage_stats(&self) -> Result<StorageStats>;
+
+  /// Returns the capacity limits of the filesystem.
+  fn limits(&self) -> Result<Limits>;
+
+  /// Replaces the capacity limits of the filesystem.
+  fn set_limits(&mut self, limits: Limits) -> Result<()>;
+
+  /// Compresses or decompresses a file's content.
+  fn set_compression(&mut self, path: &Path, compression: Compression) -> Result<()>;
+
+  /// Appends `content` to a file, returning how many bytes were written.
+  fn write_file(&mut self, path: &Path, content: Vec<u8>) -> Result<usize>;
+
+  /// Writes `content` to a file at `offset`, returning how many bytes were written.
+  fn write_file_at(&mut self, path: &Path, offset: u64, content: Vec<u8>) -> Result<usize>;
+
+  /// Sets the length of a file.
+  fn truncate(&mut self, path: &Path, len: u64) -> Result<()>;
+
+  /// Deallocates a range of a file.
+  fn punch_hole(&mut self, path: &Path, offset: u64, len: u64) -> Result<()>;
+
+  /// Returns the next offset of a file at or after `offset` containing data.
+  fn seek_data(&self, path: &Path, offset: u64) -> Result<Option<u64>>;
+
+  /// Returns the next offset of a file at or after `offset` in a hole.
+  fn seek_hole(&self, path: &Path, offset: u64) -> Result<Option<u64>>;
+
+  /// Returns the content of a file as a string.
+  fn read_file(&self, path: &Path) -> Result<String>;
+
+  /// Returns up to `len` bytes of a file starting at `offset`.
+  fn read_file_at(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>>;
+
+  /// Sets an extended attribute.
+  fn set_xattr(&mut self, path: &Path, name: &str, value: Vec<u8>) -> Result<()>;
+
+  /// Returns the value of an extended attribute.
+  fn get_xattr(&self, path: &Path, name: &str) -> Result<Vec<u8>>;
+
+  /// Returns the names of the extended attributes of an entry.
+  fn list_xattr(&self, path: &Path) -> Result<Vec<String>>;
+
+  /// Removes an extended attribute.
+  fn remove_xattr(&mut self, path: &Path, name: &str) -> Result<()>;
+
+  /// Adds a fault injection rule, returning its id. This fails with
+  /// [`Error::InvalidProbability`] if the rule's trigger has an invalid probability.
+  ///
+  /// [`Error::InvalidProbability`]: crate::Error::InvalidProbability
+  fn add_fault(&mut self, rule: Rule) -> Result<usize>;
+
+  /// Returns the fault injection rules and their ids, along with how many operations each has
+  /// matched.
+  fn fault_rules(&self) -> Result<Vec<(usize, Rule, u64)>>;
+
+  /// Removes a fault injection rule, returning whether it existed.
+  fn remove_fault(&mut self, id: usize) -> Result<bool>;
+
+  /// Enables or disables a fault injection rule, returning whether it exists.
+  fn set_fault_enabled(&mut self, id: usize, enabled: bool) -> Result<bool>;
+
+  /// Removes all fault injection rules.
+  fn clear_faults(&mut self) -> Result<()>;
+}
+
+impl SessionApi for Session {
+  fn current_directory(&self) -> PathBuf {
+    Session::current_directory(self).to_owned()
+  }
+
+  fn change_directory(&mut self, path: &Path) -> Result<()> {
+    Session::change_directory(self, path)
+  }
+
+  fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
+    Session::canonicalize(self, path)
+  }
+
+  fn create_directory(&mut self, path: &Path) -> Result<()> {
+    Session::create_directory(self, path)
+  }
+
+  fn create_file(&mut self, path: &Path) -> Result<()> {
+    Session::create_file(self, path)
+  }
+
+  fn remove(&mut self, path: &Path) -> Result<()> {
+    Session::remove(self, path)
+  }
+
+  fn move_entry(&mut self, src: &Path, dst: &Path) -> Result<()> {
+    Session::move_entry(self, src, dst)
+  }
+
+  fn copy_entry(&mut self, src: &Path, dst: &Path) -> Result<()> {
+    Session::copy_entry(self, src, dst)
+  }
+
+  fn stat(&self, path: &Path) -> Result<ListEntry> {
+    let (_, entry) = self.resolve(path)?;
+
+    Ok(ListEntry::of(entry))
+  }
+
+  fn read_dir_with_metadata(&self, path: &Path) -> Result<Vec<ListEntry>> {
+    Session::read_dir_with_metadata(self, path)
+  }
+
+  fn glob(&self, pattern: &Path) -> Result<Vec<PathBuf>> {
+    Session::glob(self, pattern)
+  }
+
+  fn find(&self, root: &Path, predicate: &Predicate, options: WalkOptions) -> Result<Vec<PathBuf>> {
+    Ok(Session::find(self, root, predicate, options)?.map(|entry| entry.path()).collect())
+  }
+
+  fn tree(&self, root: &Path, options: &TreeOptions) -> Result<Tree> {
+    Session::tree(self, root, options)
+  }
+
+  fn grep(&self, root: &Path, pattern: &str, options: &GrepOptions) -> Result<Vec<GrepMatch>> {
+    Session::grep(self, root, pattern, options)
+  }
+
+  fn disk_usage(&self, path: &Path) -> Result<Usage> {
+    Session::disk_usage(self, path)
+  }
+
+  fn filesystem_usage(&self) -> Result<Usage> {
+    Ok(Session::filesystem_usage(self))
+  }
+
+  fn storage_stats(&self) -> Result<StorageStats> {
+    Ok(Session::storage_stats(self))
+  }
+
+  fn limits(&self) -> Result<Limits> {
+    Ok(*Session::limits(self))
+  }
+
+  fn set_limits(&mut self, limits: Limits) -> Result<()> {
+    Session::set_limits(self, limits);
+
+    Ok(())
+  }
+
+  fn set_compression(&mut self, path: &Path, compression: Compression) -> Result<()> {
+    Session::set_compression(self, path, compression)
+  }
+
+  fn write_file(&mut self, path: &Path, content: Vec<u8>) -> Result<usize> {
+    Session::write_file(self, path, content)
+  }
+
+  fn write_file_at(&mut self, path: &Path, offset: u64, content: Vec<u8>) -> Result<usize> {
+    Session::write_file_at(self, path, offset, content)
+  }
+
+  fn truncate(&mut self, path: &Path, len: u64) -> Result<()> {
+    Session::truncate(self, path, len)
+  }
+
+  fn punch_hole(&mut self, path: &Path, offset: u64, len: u64) -> Result<()> {
+    Session::punch_hole(self, path, offset, len)
+  }
+
+  fn seek_data(&self, path: &Path, offset: u64) -> Result<Option<u64>> {
+    Session::seek_data(self, path, offset)
+  }
+
+  fn seek_hole(&self, path: &Path, offset: u64) -> Result<Option<u64>> {
+    Session::seek_hole(self, path, offset)
+  }
+
+  fn read_file(&self, path: &Path) -> Result<String> {
+    Session::read_file(self, path)
+  }
+
+  fn read_file_at(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>> {
+    Session::read_file_at(self, path, offset, len)
+  }
+
+  fn set_xattr(&mut self, path: &Path, name: &str, value: Vec<u8>) -> Result<()> {
+    Session::set_xattr(self, path, name, value)
+  }
+
+  fn get_xattr(&self, path: &Path, name: &str) -> Result<Vec<u8>> {
+    Session::get_xattr(self, path, name).map(<[u8]>::to_vec)
+  }
+
+  fn list_xattr(&self, path: &Path) -> Result<Vec<String>> {
+    Ok(Session::list_xattr(self, path)?.map(str::to_owned).collect())
+  }
+
+  fn remove_xattr(&mut self, path: &Path, name: &str) -> Result<()> {
+    Session::remove_xattr(self, path, name)
+  }
+
+  fn add_fault(&mut self, rule: Rule) -> Result<usize> {
+    rule.trigger.check()?;
+
+    Ok(self.faults_mut().add(rule))
+  }
+
+  fn fault_rules(&self) -> Result<Vec<(usize, Rule, u64)>> {
+    Ok(
+      self
+        .faults
+        .lock()
+        .rules()
+        .map(|(id, rule, hits)| (id, rule.clone(), hits))
+        .collect(),
+    )
+  }
+
+  fn remove_fault(&mut self, id: usize) -> Result<bool> {
+    Ok(self.faults_mut().remove(id).is_some())
+  }
+
+  fn set_fault_enabled(&mut self, id: usize, enabled: bool) -> Result<bool> {
+    Ok(self.faults_mut().set_enabled(id, enabled))
+  }
+
+  fn clear_faults(&mut self) -> Result<()> {
+    self.faults_mut().clear();
+
+    Ok(())
+  }
+}
@@ -1,8 +1,12 @@
-use std::{ffi::OsString, path::PathBuf};
+use std::{
+  ffi::OsString,
+  path::{Path, PathBuf},
+};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(thiserror::Error, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Error {
   #[error("{0:?} is not a directory")]
   NotDirectory(PathBuf),
@@ -25,6 +29,105 @@ pub enum Error {
   #[error("{0:?} already exists")]
   Exists(OsString),
 
+  #[error("cannot move or copy {0:?} into itself")]
+  IntoSelf(PathBuf),
+
+  #[error("no space left to create or write {0:?}")]
+  NoSpace(PathBuf),
+
+  #[error("quota exceeded for {0:?}")]
+  QuotaExceeded(PathBuf),
+
+  #[error("{0:?} is too long")]
+  NameTooLong(PathBuf),
+
+  #[error("{0:?} is nested too deep")]
+  TooDeep(PathBuf),
+
+  #[error("{0:?} would be larger than the largest possible file")]
+  FileTooLarge(PathBuf),
+
+  #[error("not enough memory to read {0:?}")]
+  OutOfMemory(PathBuf),
+
+  #[error("input/output error on {0:?}")]
+  Io(PathBuf),
+
+  #[error("invalid extended attribute name {0:?}")]
+  InvalidXattrName(String),
+
+  #[error("extended attribute {0:?} is too large")]
+  XattrTooLarge(String),
+
+  #[error("extended attribute {0:?} does not exist")]
+  NoXattr(String),
+
+  #[error("invalid pattern {0:?}")]
+  InvalidPattern(String),
+
+  #[error("invalid probability {0}, expected a number between 0 and 1")]
+  InvalidProbability(f64),
+
   #[error("unsupported component {0}")]
   UnsupportedComponent(String),
+
+  /// The connection to a session in another process failed.
+  #[error("connection to the session failed: {0}")]
+  Connection(String),
+}
+
+impl Error {
+  /// Returns a stable code identifying the kind of this error, e.g. `not_exist`, for frontends
+  /// reporting errors in a machine-readable form.
+  #[must_use]
+  pub fn code(&self) -> &'static str {
+    match self {
+      Self::NotDirectory(_) => "not_directory",
+      Self::NotFile(_) => "not_file",
+      Self::NotExist(_) => "not_exist",
+      Self::NotAbsolute(_) => "not_absolute",
+      Self::NoParent(_) => "no_parent",
+      Self::NoFileName(_) => "no_file_name",
+      Self::Exists(_) => "exists",
+      Self::IntoSelf(_) => "into_self",
+      Self::NoSpace(_) => "no_space",
+      Self::QuotaExceeded(_) => "quota_exceeded",
+      Self::NameTooLong(_) => "name_too_long",
+      Self::TooDeep(_) => "too_deep",
+      Self::FileTooLarge(_) => "file_too_large",
+      Self::OutOfMemory(_) => "out_of_memory",
+      Self::Io(_) => "io",
+      Self::InvalidXattrName(_) => "invalid_xattr_name",
+      Self::XattrTooLarge(_) => "xattr_too_large",
+      Self::NoXattr(_) => "no_xattr",
+      Self::InvalidPattern(_) => "invalid_pattern",
+      Self::InvalidProbability(_) => "invalid_probability",
+      Self::UnsupportedComponent(_) => "unsupported_component",
+      Self::Connection(_) => "connection",
+    }
+  }
+
+  /// Returns the path this error is about, if any. For some errors, like [`Self::NotExist`], this
+  /// is only the name of the component that failed.
+  #[must_use]
+  pub fn path(&self) -> Option<&Path> {
+    match self {
+      Self::NotDirectory(path)
+      | Self::NotFile(path)
+      | Self::NotExist(path)
+      | Self::NotAbsolute(path)
+      | Self::NoParent(path)
+      | Self::NoFileName(path)
+      | Self::IntoSelf(path)
+      | Self::NoSpace(path)
+      | Self::QuotaExceeded(path)
+      | Self::NameTooLong(path)
+      | Self::TooDeep(path)
+      | Self::FileTooLarge(path)
+      | Self::OutOfMemory(path)
+      | Self::Io(path) => Some(path),
+      Self::Exists(name) => Some(Path::new(name)),
+      _ => None,
+    }
+  }
 }
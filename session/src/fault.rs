@@ -0,0 +1,224 @@
+use std::{
+  collections::BTreeMap,
+  path::{Path, PathBuf},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{Error, Result};
+
+/// An operation on a [`Session`] that faults can be injected into.
+///
+/// [`Session`]: crate::Session
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Operation {
+  CreateDirectory,
+  CreateFile,
+  Remove,
+  Move,
+  Copy,
+  Write,
+  Truncate,
+  PunchHole,
+  Read,
+}
+
+/// When a [`Rule`] triggers, among the operations it matches.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Trigger {
+  /// Trigger on every matching operation.
+  Always,
+
+  /// Trigger only on the nth matching operation, starting at 1.
+  Nth(u64),
+
+  /// Trigger on each matching operation with a `probability` between 0 and 1, using a random
+  /// number generator seeded with `seed`. See [`Self::random`].
+  Random { probability: f64, seed: u64 },
+}
+
+impl Trigger {
+  /// Creates a [`Self::Random`] trigger.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `probability` is not a number between 0 and 1.
+  pub fn random(probability: f64, seed: u64) -> Result<Self> {
+    if !(0.0..=1.0).contains(&probability) {
+      return Err(Error::InvalidProbability(probability));
+    }
+
+    Ok(Self::Random { probability, seed })
+  }
+
+  /// Checks that this trigger could have been created by its constructor.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if this is a [`Self::Random`] trigger with an invalid
+  /// probability.
+  pub(crate) fn check(self) -> Result<()> {
+    match self {
+      Self::Random { probability, seed } => Self::random(probability, seed).map(drop),
+      Self::Always | Self::Nth(_) => Ok(()),
+    }
+  }
+}
+
+/// What happens to an operation when a [`Rule`] triggers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fault {
+  /// Fail with [`Error::Io`].
+  ///
+  /// [`Error::Io`]: crate::Error::Io
+  Io,
+
+  /// Fail with [`Error::NoSpace`].
+  ///
+  /// [`Error::NoSpace`]: crate::Error::NoSpace
+  NoSpace,
+
+  /// Write at most this many bytes. This only applies to [`Operation::Write`].
+  ShortWrite(usize),
+}
+
+/// A rule describing which operations to inject a fault into.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rule {
+  /// The operation to match, or `None` to match all operations.
+  pub operation: Option<Operation>,
+
+  /// The absolute path that an operation's path must be equal to or under to match.
+  pub path: PathBuf,
+
+  pub trigger: Trigger,
+
+  pub fault: Fault,
+
+  /// Whether this rule is active. Disabled rules do not count matching operations.
+  pub enabled: bool,
+}
+
+impl Rule {
+  /// Creates a new enabled rule that always injects `fault` into `operation`s under `path`.
+  pub fn new<P: Into<PathBuf>>(operation: Option<Operation>, path: P, fault: Fault) -> Self {
+    Self {
+      operation,
+      path: path.into(),
+      trigger: Trigger::Always,
+      fault,
+      enabled: true,
+    }
+  }
+
+  /// Sets when this rule triggers.
+  #[must_use]
+  pub fn trigger(mut self, trigger: Trigger) -> Self {
+    self.trigger = trigger;
+    self
+  }
+
+  /// Returns whether this rule applies to `operation` on any of `paths`.
+  fn matches(&self, operation: Operation, paths: &[&Path]) -> bool {
+    self.enabled && self.operation.is_none_or(|op| op == operation) && paths.iter().any(|path| path.starts_with(&self.path))
+  }
+}
+
+/// A [`Rule`] and the state needed to decide when it triggers.
+struct Injector {
+  rule: Rule,
+
+  hits: u64,
+
+  rng: StdRng,
+}
+
+impl Injector {
+  fn new(rule: Rule) -> Self {
+    let seed = match rule.trigger {
+      Trigger::Random { seed, .. } => seed,
+      Trigger::Always | Trigger::Nth(_) => 0,
+    };
+
+    Self {
+      rule,
+      hits: 0,
+      rng: StdRng::seed_from_u64(seed),
+    }
+  }
+
+  /// Counts a matching operation and returns whether the rule triggers on it.
+  fn hit(&mut self) -> bool {
+    self.hits += 1;
+
+    match self.rule.trigger {
+      Trigger::Always => true,
+      Trigger::Nth(n) => self.hits == n,
+      // rules added with an invalid probability never trigger
+      Trigger::Random { probability, .. } => (0.0..=1.0).contains(&probability) && self.rng.gen_bool(probability),
+    }
+  }
+}
+
+/// A set of fault injection rules, identified by the id returned from [`Self::add`].
+#[derive(Default)]
+pub struct Faults {
+  injectors: BTreeMap<usize, Injector>,
+
+  next_id: usize,
+}
+
+impl Faults {
+  /// Adds a rule, returning its id.
+  pub fn add(&mut self, rule: Rule) -> usize {
+    let id = self.next_id;
+    self.next_id += 1;
+    self.injectors.insert(id, Injector::new(rule));
+
+    id
+  }
+
+  /// Removes a rule, returning it if it existed.
+  pub fn remove(&mut self, id: usize) -> Option<Rule> {
+    self.injectors.remove(&id).map(|injector| injector.rule)
+  }
+
+  /// Enables or disables a rule, returning whether it exists.
+  pub fn set_enabled(&mut self, id: usize, enabled: bool) -> bool {
+    let Some(injector) = self.injectors.get_mut(&id) else {
+      return false;
+    };
+
+    injector.rule.enabled = enabled;
+
+    true
+  }
+
+  /// Removes all rules.
+  pub fn clear(&mut self) {
+    self.injectors.clear();
+  }
+
+  /// Returns the rules and their ids, along with how many operations each has matched.
+  pub fn rules(&self) -> impl Iterator<Item = (usize, &Rule, u64)> {
+    self.injectors.iter().map(|(id, injector)| (*id, &injector.rule, injector.hits))
+  }
+
+  /// Counts `operation` on `paths` against every matching rule, returning the fault of the
+  /// first rule that triggers.
+  pub(crate) fn inject(&mut self, operation: Operation, paths: &[&Path]) -> Option<Fault> {
+    let mut fault = None;
+
+    for injector in self.injectors.values_mut() {
+      if injector.rule.matches(operation, paths) && injector.hit() && fault.is_none() {
+        fault = Some(injector.rule.fault);
+      }
+    }
+
+    fault
+  }
+}
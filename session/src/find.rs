@@ -0,0 +1,146 @@
+use std::{cmp::Ordering, ops::Not};
+
+use chrono::{DateTime, Utc};
+use fs::entry::Borrowed as BorrowedEntry;
+use globset::{Glob, GlobMatcher};
+
+use crate::{Error, Result};
+
+/// The type of an entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EntryType {
+  File,
+  Directory,
+}
+
+/// A test on an entry, like the expressions of the Unix `find` command.
+///
+/// See [`Session::find`].
+///
+/// [`Session::find`]: crate::Session::find
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Predicate {
+  /// Matches every entry.
+  True,
+
+  /// Matches entries whose name matches a glob pattern.
+  Name(#[cfg_attr(feature = "serde", serde(with = "serde_glob"))] GlobMatcher),
+
+  /// Matches entries of a type.
+  Type(EntryType),
+
+  /// Matches files whose length in bytes compares to a size with the ordering, e.g.
+  /// `Size(Ordering::Greater, 10)` matches files longer than 10 bytes. Directories never match.
+  Size(#[cfg_attr(feature = "serde", serde(with = "serde_ordering"))] Ordering, u64),
+
+  /// Matches entries whose modification time compares to a time with the ordering, e.g.
+  /// `Modified(Ordering::Greater, time)` matches entries modified after `time`.
+  Modified(
+    #[cfg_attr(feature = "serde", serde(with = "serde_ordering"))] Ordering,
+    DateTime<Utc>,
+  ),
+
+  /// Matches empty files and directories.
+  Empty,
+
+  And(Box<Predicate>, Box<Predicate>),
+
+  Or(Box<Predicate>, Box<Predicate>),
+
+  Not(Box<Predicate>),
+}
+
+impl Predicate {
+  /// Creates a predicate matching entries whose name matches the glob `pattern`.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `pattern` is not a valid glob.
+  pub fn name(pattern: &str) -> Result<Self> {
+    let glob = Glob::new(pattern).map_err(|_| Error::InvalidPattern(pattern.to_owned()))?;
+
+    Ok(Self::Name(glob.compile_matcher()))
+  }
+
+  /// Returns a predicate matching entries that match both this and `other`.
+  #[must_use]
+  pub fn and(self, other: Self) -> Self {
+    Self::And(Box::new(self), Box::new(other))
+  }
+
+  /// Returns a predicate matching entries that match either this or `other`.
+  #[must_use]
+  pub fn or(self, other: Self) -> Self {
+    Self::Or(Box::new(self), Box::new(other))
+  }
+
+  /// Returns whether `entry` matches this predicate.
+  #[must_use]
+  pub fn matches(&self, entry: &BorrowedEntry) -> bool {
+    let metadata = entry.metadata();
+
+    match self {
+      Self::True => true,
+      Self::Name(glob) => glob.is_match(entry.name()),
+      Self::Type(EntryType::File) => entry.is_file(),
+      Self::Type(EntryType::Directory) => entry.is_directory(),
+      Self::Size(ordering, size) => entry.is_file() && metadata.len.cmp(size) == *ordering,
+      Self::Modified(ordering, time) => metadata.modified_at.cmp(time) == *ordering,
+      Self::Empty => match entry {
+        BorrowedEntry::File(file) => file.metadata.len == 0,
+        BorrowedEntry::Directory(directory) => directory.entries.is_empty(),
+      },
+      Self::And(a, b) => a.matches(entry) && b.matches(entry),
+      Self::Or(a, b) => a.matches(entry) || b.matches(entry),
+      Self::Not(predicate) => !predicate.matches(entry),
+    }
+  }
+}
+
+impl Not for Predicate {
+  type Output = Self;
+
+  fn not(self) -> Self {
+    Self::Not(Box::new(self))
+  }
+}
+
+/// Serializes glob matchers as their patterns.
+#[cfg(feature = "serde")]
+mod serde_glob {
+  use globset::{Glob, GlobMatcher};
+  use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+  pub fn serialize<S: Serializer>(matcher: &GlobMatcher, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(matcher.glob().glob())
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<GlobMatcher, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+
+    Glob::new(&pattern).map(|glob| glob.compile_matcher()).map_err(D::Error::custom)
+  }
+}
+
+/// Serializes orderings as -1, 0 or 1.
+#[cfg(feature = "serde")]
+mod serde_ordering {
+  use std::cmp::Ordering;
+
+  use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+  pub fn serialize<S: Serializer>(ordering: &Ordering, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_i8(*ordering as i8)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Ordering, D::Error> {
+    match i8::deserialize(deserializer)? {
+      -1 => Ok(Ordering::Less),
+      0 => Ok(Ordering::Equal),
+      1 => Ok(Ordering::Greater),
+      n => Err(D::Error::custom(format!("invalid ordering {n}"))),
+    }
+  }
+}
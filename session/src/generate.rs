@@ -0,0 +1,323 @@
+use std::path::Path;
+
+use rand::{distributions::Alphanumeric, rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{usage::Usage, Result, SessionApi};
+
+/// The largest chunk written to a file at once while generating its content.
+const CHUNK_LEN: u64 = 64 * 1024;
+
+/// The words of [`Pattern::Lorem`] text.
+const LOREM: [&str; 32] = [
+  "lorem",
+  "ipsum",
+  "dolor",
+  "sit",
+  "amet",
+  "consectetur",
+  "adipiscing",
+  "elit",
+  "sed",
+  "do",
+  "eiusmod",
+  "tempor",
+  "incididunt",
+  "ut",
+  "labore",
+  "et",
+  "dolore",
+  "magna",
+  "aliqua",
+  "enim",
+  "ad",
+  "minim",
+  "veniam",
+  "quis",
+  "nostrud",
+  "exercitation",
+  "ullamco",
+  "laboris",
+  "nisi",
+  "aliquip",
+  "ex",
+  "commodo",
+];
+
+/// The content of generated files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pattern {
+  /// Random bytes.
+  Random,
+
+  /// Zero bytes.
+  Zeros,
+
+  /// Random alphanumeric ASCII characters.
+  #[default]
+  Ascii,
+
+  /// Lines of lorem ipsum sentences made of random words.
+  Lorem,
+
+  /// Lines of 15 digit line numbers starting at 0, so that the content at any offset is known,
+  /// e.g. `000000000000042` at offset 672.
+  Sequence,
+}
+
+/// A stream of content following a [`Pattern`], deterministic for a given seed.
+///
+/// The stream doesn't depend on how it is split into chunks, so content written in chunks is the
+/// same as content generated at once.
+pub struct ContentGenerator {
+  pattern: Pattern,
+
+  rng: StdRng,
+
+  /// Content generated past the end of the last chunk, e.g. the rest of a word.
+  pending: Vec<u8>,
+
+  /// The number of lines generated so far, for [`Pattern::Sequence`].
+  lines: u64,
+}
+
+impl ContentGenerator {
+  /// Creates a generator of content following `pattern`, seeded with `seed`.
+  #[must_use]
+  pub fn new(pattern: Pattern, seed: u64) -> Self {
+    Self {
+      pattern,
+      rng: StdRng::seed_from_u64(seed),
+      pending: Vec::new(),
+      lines: 0,
+    }
+  }
+
+  /// Returns the next `len` bytes of content.
+  pub fn next_chunk(&mut self, len: usize) -> Vec<u8> {
+    match self.pattern {
+      Pattern::Random => (0..len).map(|_| self.rng.gen()).collect(),
+      Pattern::Zeros => vec![0; len],
+      Pattern::Ascii => (0..len).map(|_| self.rng.sample(Alphanumeric)).collect(),
+      Pattern::Lorem | Pattern::Sequence => {
+        while self.pending.len() < len {
+          self.generate_line();
+        }
+
+        let rest = self.pending.split_off(len);
+        std::mem::replace(&mut self.pending, rest)
+      }
+    }
+  }
+
+  /// Appends a line of text to the pending content.
+  fn generate_line(&mut self) {
+    if self.pattern == Pattern::Sequence {
+      self.pending.extend_from_slice(format!("{:015}\n", self.lines).as_bytes());
+      self.lines += 1;
+
+      return;
+    }
+
+    let words = self.rng.gen_range(4..16);
+
+    for i in 0..words {
+      let word = LOREM.choose(&mut self.rng).expect("the word list isn't empty");
+
+      if i == 0 {
+        let mut chars = word.chars();
+        self.pending.extend(chars.next().map(|c| c.to_ascii_uppercase() as u8));
+        self.pending.extend_from_slice(chars.as_str().as_bytes());
+      } else {
+        self.pending.push(b' ');
+        self.pending.extend_from_slice(word.as_bytes());
+      }
+    }
+
+    self.pending.extend_from_slice(b".\n");
+  }
+
+  /// Appends `size` bytes of content to the file at `path`, in chunks so that large files don't
+  /// need one large allocation, and returns the number of bytes written. Stops early if a write
+  /// is short.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if writing to the file fails, for example because it
+  /// doesn't exist or the filesystem's limits are exceeded.
+  pub fn fill<S: SessionApi + ?Sized, P: AsRef<Path>>(&mut self, session: &mut S, path: P, size: u64) -> Result<u64> {
+    let mut written = 0;
+
+    while written < size {
+      let len = (size - written).min(CHUNK_LEN) as usize;
+      let chunk_written = session.write_file(path.as_ref(), self.next_chunk(len))?;
+
+      written += chunk_written as u64;
+      if chunk_written < len {
+        break;
+      }
+    }
+
+    Ok(written)
+  }
+}
+
+/// How the sizes of generated files are distributed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FileSize {
+  /// Every file has this many bytes.
+  Fixed(u64),
+
+  /// Sizes are uniformly distributed between `min` and `max` bytes, inclusive.
+  Uniform { min: u64, max: u64 },
+
+  /// Sizes are exponentially distributed with this mean, so most files are small and a few are
+  /// large.
+  Exponential { mean: u64 },
+}
+
+impl FileSize {
+  fn sample(self, rng: &mut StdRng) -> u64 {
+    match self {
+      Self::Fixed(size) => size,
+      Self::Uniform { min, max } => rng.gen_range(min..=max.max(min)),
+      Self::Exponential { mean } => (-(1.0 - rng.gen::<f64>()).ln() * mean as f64) as u64,
+    }
+  }
+}
+
+/// A generator of synthetic trees, for tests and benchmarks.
+///
+/// Every directory in the tree, including the root, gets `files` files named `f0`, `f1`, ... and,
+/// above the maximum depth, `fanout` subdirectories named `d0`, `d1`, .... Files are filled with
+/// content following a [`Pattern`], random alphanumeric characters by default. Generation is
+/// deterministic for a given seed.
+#[derive(Clone, Debug)]
+pub struct TreeGenerator {
+  depth: usize,
+
+  fanout: usize,
+
+  files: usize,
+
+  file_size: FileSize,
+
+  pattern: Pattern,
+
+  seed: u64,
+}
+
+impl TreeGenerator {
+  /// Creates a generator of a root directory with no entries.
+  #[must_use]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sets how many levels of subdirectories are below the root.
+  #[must_use]
+  pub fn depth(mut self, depth: usize) -> Self {
+    self.depth = depth;
+    self
+  }
+
+  /// Sets how many subdirectories each directory above the maximum depth has.
+  #[must_use]
+  pub fn fanout(mut self, fanout: usize) -> Self {
+    self.fanout = fanout;
+    self
+  }
+
+  /// Sets how many files each directory has.
+  #[must_use]
+  pub fn files(mut self, files: usize) -> Self {
+    self.files = files;
+    self
+  }
+
+  /// Sets how the sizes of files are distributed.
+  #[must_use]
+  pub fn file_size(mut self, file_size: FileSize) -> Self {
+    self.file_size = file_size;
+    self
+  }
+
+  /// Sets the content of files.
+  #[must_use]
+  pub fn pattern(mut self, pattern: Pattern) -> Self {
+    self.pattern = pattern;
+    self
+  }
+
+  /// Sets the seed of the random number generator used for file sizes and content.
+  #[must_use]
+  pub fn seed(mut self, seed: u64) -> Self {
+    self.seed = seed;
+    self
+  }
+
+  /// Generates the tree under `root`, creating `root` as a directory if it doesn't exist, and
+  /// returns the usage of the generated entries.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if creating an entry or writing a file fails, for example
+  /// because `root` is a file or the filesystem's limits are exceeded.
+  pub fn generate<S: SessionApi + ?Sized, P: AsRef<Path>>(&self, session: &mut S, root: P) -> Result<Usage> {
+    let mut usage = Usage::default();
+    let mut rng = StdRng::seed_from_u64(self.seed);
+    let mut content = ContentGenerator::new(self.pattern, self.seed);
+
+    let root = root.as_ref();
+    let root = match session.canonicalize(root) {
+      Ok(root) => root,
+      Err(_) => {
+        session.create_directory(root)?;
+        usage.directories += 1;
+
+        session.canonicalize(root)?
+      }
+    };
+
+    let mut stack = vec![(root, 0)];
+
+    while let Some((directory, depth)) = stack.pop() {
+      for i in 0..self.files {
+        let path = directory.join(format!("f{i}"));
+        let size = self.file_size.sample(&mut rng);
+
+        session.create_file(&path)?;
+        let written = content.fill(session, &path, size)?;
+
+        usage.files += 1;
+        usage.bytes += written;
+        usage.allocated += written;
+      }
+
+      if depth < self.depth {
+        for i in (0..self.fanout).rev() {
+          let path = directory.join(format!("d{i}"));
+
+          session.create_directory(&path)?;
+          usage.directories += 1;
+
+          stack.push((path, depth + 1));
+        }
+      }
+    }
+
+    Ok(usage)
+  }
+}
+
+impl Default for TreeGenerator {
+  fn default() -> Self {
+    Self {
+      depth: 0,
+      fanout: 0,
+      files: 0,
+      file_size: FileSize::Fixed(0),
+      pattern: Pattern::default(),
+      seed: 0,
+    }
+  }
+}
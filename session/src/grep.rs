@@ -0,0 +1,356 @@
+use std::{
+  collections::VecDeque,
+  ffi::OsStr,
+  path::{Path, PathBuf},
+};
+
+use fs::{
+  content::{OutOfMemory, BLOCK_SIZE},
+  Content,
+};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::bytes::{Regex, RegexBuilder};
+
+use crate::{Error, Result};
+
+/// The number of bytes of data at the start of a file checked for NUL bytes to decide whether it is
+/// binary.
+const BINARY_CHECK_LEN: usize = 8192;
+
+/// The number of bytes of a file read at a time while searching it.
+const READ_LEN: usize = 16 * BLOCK_SIZE;
+
+/// Options for [`Session::grep`].
+///
+/// By default, the pattern is case-sensitive, every text file is searched, files with a NUL byte
+/// in their first 8 KiB of data are skipped as binary, and no context lines are returned. Holes
+/// are never searched, so a text file with holes isn't binary.
+///
+/// [`Session::grep`]: crate::Session::grep
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GrepOptions {
+  ignore_case: bool,
+
+  include: Vec<String>,
+
+  exclude: Vec<String>,
+
+  binary: bool,
+
+  before: usize,
+
+  after: usize,
+}
+
+impl GrepOptions {
+  /// Creates the default options.
+  #[must_use]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Matches the pattern case-insensitively.
+  #[must_use]
+  pub fn ignore_case(mut self, ignore_case: bool) -> Self {
+    self.ignore_case = ignore_case;
+    self
+  }
+
+  /// Only searches files whose name matches the glob `pattern`. If called multiple times, files
+  /// matching any of the patterns are searched.
+  #[must_use]
+  pub fn include<S: Into<String>>(mut self, pattern: S) -> Self {
+    self.include.push(pattern.into());
+    self
+  }
+
+  /// Skips files and directories whose name matches the glob `pattern`.
+  #[must_use]
+  pub fn exclude<S: Into<String>>(mut self, pattern: S) -> Self {
+    self.exclude.push(pattern.into());
+    self
+  }
+
+  /// Searches binary files, which contain a NUL byte outside of holes, instead of skipping them.
+  #[must_use]
+  pub fn binary(mut self, binary: bool) -> Self {
+    self.binary = binary;
+    self
+  }
+
+  /// Returns `lines` lines of context before each match.
+  #[must_use]
+  pub fn before_context(mut self, lines: usize) -> Self {
+    self.before = lines;
+    self
+  }
+
+  /// Returns `lines` lines of context after each match.
+  #[must_use]
+  pub fn after_context(mut self, lines: usize) -> Self {
+    self.after = lines;
+    self
+  }
+
+  /// Returns `lines` lines of context before and after each match.
+  #[must_use]
+  pub fn context(self, lines: usize) -> Self {
+    self.before_context(lines).after_context(lines)
+  }
+}
+
+/// A line of a file. Invalid UTF-8 is replaced with `U+FFFD`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Line {
+  /// The line number, starting at 1.
+  pub number: usize,
+
+  pub text: String,
+}
+
+/// A line matching the pattern of [`Session::grep`].
+///
+/// [`Session::grep`]: crate::Session::grep
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GrepMatch {
+  pub path: PathBuf,
+
+  pub line: Line,
+
+  /// The lines before the match, up to the requested context. These may overlap with the
+  /// context or line of the previous match.
+  pub before: Vec<Line>,
+
+  /// The lines after the match, up to the requested context. These may overlap with the context
+  /// or line of the next match.
+  pub after: Vec<Line>,
+}
+
+/// A compiled pattern and set of options.
+pub(crate) struct Searcher {
+  regex: Regex,
+
+  include: Option<GlobSet>,
+
+  exclude: GlobSet,
+
+  binary: bool,
+
+  before: usize,
+
+  after: usize,
+}
+
+impl Searcher {
+  /// Compiles `pattern` and the globs of `options`.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `pattern` is not a valid regex or any glob is invalid.
+  pub(crate) fn new(pattern: &str, options: &GrepOptions) -> Result<Self> {
+    let regex = RegexBuilder::new(pattern)
+      .case_insensitive(options.ignore_case)
+      .build()
+      .map_err(|_| Error::InvalidPattern(pattern.to_owned()))?;
+
+    let include = if options.include.is_empty() {
+      None
+    } else {
+      Some(glob_set(&options.include)?)
+    };
+
+    Ok(Self {
+      regex,
+      include,
+      exclude: glob_set(&options.exclude)?,
+      binary: options.binary,
+      before: options.before,
+      after: options.after,
+    })
+  }
+
+  /// Returns whether a directory named `name` is entered.
+  pub(crate) fn enters(&self, name: &OsStr) -> bool {
+    !self.exclude.is_match(name)
+  }
+
+  /// Returns whether a file named `name` is searched.
+  pub(crate) fn searches(&self, name: &OsStr) -> bool {
+    self.include.as_ref().is_none_or(|include| include.is_match(name)) && !self.exclude.is_match(name)
+  }
+
+  /// Searches `content` of the file at `path`, appending matches to `matches`.
+  ///
+  /// The content is read a few blocks at a time, so searching a large file only holds its longest
+  /// line and the context lines in memory. Holes are skipped without reading them, see
+  /// [`for_each_chunk`].
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if a chunk of the content doesn't fit in memory.
+  pub(crate) fn search(&self, path: &Path, content: &Content, matches: &mut Vec<GrepMatch>) -> Result<()> {
+    let out_of_memory = |OutOfMemory| Error::OutOfMemory(path.to_owned());
+
+    if !self.binary && is_binary(content).map_err(out_of_memory)? {
+      return Ok(());
+    }
+
+    let first = matches.len();
+    // the lines before the next line, up to the requested context
+    let mut before = VecDeque::with_capacity(self.before);
+    // the matches that still need lines after them, as indices into `matches`
+    let mut pending: VecDeque<usize> = VecDeque::new();
+    let mut number = 0;
+
+    for_each_line(content, |text| {
+      number += 1;
+      let line = || Line {
+        number,
+        text: String::from_utf8_lossy(text).into_owned(),
+      };
+
+      for index in &pending {
+        matches[first + index].after.push(line());
+      }
+      while pending
+        .front()
+        .is_some_and(|index| matches[first + index].after.len() == self.after)
+      {
+        pending.pop_front();
+      }
+
+      if self.regex.is_match(text) {
+        if self.after > 0 {
+          pending.push_back(matches.len() - first);
+        }
+
+        matches.push(GrepMatch {
+          path: path.to_owned(),
+          line: line(),
+          before: before.iter().cloned().collect(),
+          after: Vec::new(),
+        });
+      }
+
+      if self.before > 0 {
+        if before.len() == self.before {
+          before.pop_front();
+        }
+        before.push_back(line());
+      }
+    })
+    .map_err(out_of_memory)
+  }
+}
+
+/// Returns whether the first [`BINARY_CHECK_LEN`] bytes of data of `content` contain a NUL byte.
+///
+/// # Errors
+///
+/// This function will return an error if a chunk of the content doesn't fit in memory.
+fn is_binary(content: &Content) -> std::result::Result<bool, OutOfMemory> {
+  let mut checked = 0;
+  let mut binary = false;
+
+  for_each_chunk(content, |chunk| {
+    let chunk = &chunk[..chunk.len().min(BINARY_CHECK_LEN - checked)];
+    checked += chunk.len();
+    binary = chunk.contains(&0);
+
+    !binary && checked < BINARY_CHECK_LEN
+  })?;
+
+  Ok(binary)
+}
+
+/// Calls `f` on the data of `content` a few blocks at a time, until it returns false.
+///
+/// Holes are skipped, along with the NUL bytes right before or after them, which are the zeroed
+/// rest of a block partly written before a hole was made next to it.
+///
+/// # Errors
+///
+/// This function will return an error if a chunk of the content doesn't fit in memory.
+fn for_each_chunk<F: FnMut(&[u8]) -> bool>(content: &Content, mut f: F) -> std::result::Result<(), OutOfMemory> {
+  let mut offset = 0;
+
+  while let Some(start) = content.seek_data(offset) {
+    let end = content.seek_hole(start).unwrap_or(content.len());
+    offset = start;
+
+    while offset < end {
+      let len = usize::try_from(end - offset).map_or(READ_LEN, |len| len.min(READ_LEN));
+      let chunk = content.read_at(offset, len)?;
+      let mut data = &chunk[..];
+
+      if offset == start && start > 0 {
+        data = &data[data.iter().position(|byte| *byte != 0).unwrap_or(data.len())..];
+      }
+
+      offset += chunk.len() as u64;
+
+      if offset == end && end < content.len() {
+        data = &data[..data.iter().rposition(|byte| *byte != 0).map_or(0, |last| last + 1)];
+      }
+
+      if !f(data) {
+        return Ok(());
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/// Calls `f` on each line of the data of `content`, without its newline, reading the content a
+/// few blocks at a time. A final newline doesn't start another line.
+///
+/// # Errors
+///
+/// This function will return an error if a chunk of the content doesn't fit in memory.
+fn for_each_line<F: FnMut(&[u8])>(content: &Content, mut f: F) -> std::result::Result<(), OutOfMemory> {
+  let mut line = Vec::new();
+
+  for_each_chunk(content, |chunk| {
+    let mut rest = chunk;
+
+    while let Some(end) = rest.iter().position(|byte| *byte == b'\n') {
+      if line.is_empty() {
+        f(&rest[..end]);
+      } else {
+        line.extend_from_slice(&rest[..end]);
+        f(&line);
+        line.clear();
+      }
+
+      rest = &rest[end + 1..];
+    }
+
+    line.extend_from_slice(rest);
+    true
+  })?;
+
+  if !line.is_empty() {
+    f(&line);
+  }
+
+  Ok(())
+}
+
+/// Compiles glob `patterns` into a set.
+///
+/// # Errors
+///
+/// This function will return an error if any pattern is not a valid glob.
+fn glob_set(patterns: &[String]) -> Result<GlobSet> {
+  let mut builder = GlobSetBuilder::new();
+
+  for pattern in patterns {
+    builder.add(Glob::new(pattern).map_err(|_| Error::InvalidPattern(pattern.clone()))?);
+  }
+
+  builder.build().map_err(|_| Error::InvalidPattern(patterns.join(",")))
+}
@@ -1,36 +1,80 @@
+pub mod api;
 pub mod error;
+pub mod fault;
+pub mod find;
+pub mod generate;
+pub mod grep;
+pub mod listing;
+mod path_cache;
+pub mod tree;
+pub mod usage;
 pub mod util;
 pub mod walk;
+pub mod xattr;
 
 use std::{
   collections::btree_map::Entry as BTreeMapEntry,
-  ffi::OsString,
+  ffi::{OsStr, OsString},
   path::{Component, Path, PathBuf},
 };
 
+use chrono::Utc;
+
 use fs::{
+  content::{OutOfMemory, TooLarge},
   entry::{Borrowed as BorrowedEntry, MutBorrowed as MutBorrowedEntry},
-  Directory, Entry, File, Filesystem,
+  storage::BlobStore,
+  Compression, Directory, Entry, File, Filesystem, Limits,
 };
+use globset::Glob;
+use parking_lot::Mutex;
 
-pub use self::error::{Error, Result};
-use self::walk::Walk;
+pub use self::{
+  api::SessionApi,
+  error::{Error, Result},
+};
+use self::{
+  fault::{Fault, Faults, Operation},
+  find::Predicate,
+  grep::{GrepMatch, GrepOptions, Searcher},
+  listing::ListEntry,
+  path_cache::{Names, PathCache},
+  tree::{Tree, TreeNode, TreeOptions},
+  usage::{StorageStats, Usage},
+  walk::{DirEntry, Walk, WalkIter, WalkOptions},
+};
 
 /// An interactive session with a [`Filesystem`].
+///
+/// Several clients can share a session, each with its own current directory, with
+/// [`Session::with_current_directory`].
 pub struct Session {
-  // TODO: Use Arc<RwLock<Filesystem>> to allow for multiple sessions.
   filesystem: Filesystem,
 
   current_directory: PathBuf,
+
+  current_names: Names,
+
+  paths: Mutex<PathCache>,
+
+  usage: Usage,
+
+  faults: Mutex<Faults>,
 }
 
 impl Session {
   /// Creates a new session.
   #[must_use]
   pub fn new(filesystem: Filesystem) -> Self {
+    let usage = Usage::of(BorrowedEntry::Directory(&filesystem.root));
+
     Self {
       filesystem,
       current_directory: PathBuf::from("/"),
+      current_names: Names::default(),
+      paths: Mutex::default(),
+      usage,
+      faults: Mutex::default(),
     }
   }
 
@@ -40,6 +84,55 @@ impl Session {
     &self.current_directory
   }
 
+  /// Runs `f` with `current_directory` as the current directory, then restores the session's own
+  /// and stores where `current_directory` ended up, e.g. after `f` changed or moved it.
+  ///
+  /// This lets several clients with their own current directories share a session. Like the
+  /// session's own, the directory may have been removed in the meantime, in which case relative
+  /// paths fail to resolve until it is changed.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error without running `f` if `current_directory` is not
+  /// absolute.
+  pub fn with_current_directory<R>(&mut self, current_directory: &mut PathBuf, f: impl FnOnce(&mut Self) -> R) -> Result<R> {
+    if !current_directory.is_absolute() {
+      return Err(Error::NotAbsolute(current_directory.clone()));
+    }
+
+    let path = self.absolute(&*current_directory)?;
+    let previous = self.replace_current_directory(path);
+    let res = f(self);
+    *current_directory = self.replace_current_directory(previous);
+
+    Ok(res)
+  }
+
+  /// Replaces the current directory with the canonical `path`, returning the previous one.
+  fn replace_current_directory(&mut self, path: PathBuf) -> PathBuf {
+    self.current_names = self.paths.get_mut().insert(&path);
+    std::mem::replace(&mut self.current_directory, path)
+  }
+
+  /// Returns the capacity limits of the filesystem.
+  #[must_use]
+  pub fn limits(&self) -> &Limits {
+    &self.filesystem.limits
+  }
+
+  /// Replaces the capacity limits of the filesystem.
+  ///
+  /// Existing entries exceeding the new limits are kept, but further writes and creations are
+  /// checked against them.
+  pub fn set_limits(&mut self, limits: Limits) {
+    self.filesystem.limits = limits;
+  }
+
+  /// Returns the fault injection rules of this session.
+  pub fn faults_mut(&mut self) -> &mut Faults {
+    self.faults.get_mut()
+  }
+
   /// Changes the current working directory.
   ///
   /// # Errors
@@ -52,7 +145,7 @@ impl Session {
       return Err(Error::NotDirectory(path));
     };
 
-    self.current_directory = path;
+    self.replace_current_directory(path);
 
     Ok(())
   }
@@ -64,14 +157,23 @@ impl Session {
   /// This function will return an error if:
   /// - an entry already exists with this name.
   /// - the parent of `path` does not exist or is not a directory.
+  /// - `path` exceeds the filesystem's name length, path depth or entry limits.
   pub fn create_directory<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
-    let (directory, name) = self.resolve_mut_directory_file_name(path)?;
+    let path = self.absolute(path)?;
+    self.inject(Operation::CreateDirectory, &[&path])?;
+    self.check_create(&path)?;
+
+    let (directory, name) = self.lookup_mut_parent(&path)?;
 
     match directory.entries.entry(name.clone()) {
       BTreeMapEntry::Occupied(_) => return Err(Error::Exists(name.clone())),
       BTreeMapEntry::Vacant(v) => v.insert(Entry::Directory(Directory::new(name))),
     };
 
+    directory.metadata.modified_at = Utc::now();
+
+    self.usage.directories += 1;
+
     Ok(())
   }
 
@@ -82,14 +184,27 @@ impl Session {
   /// This function will return an error if:
   /// - an entry already exists with this name.
   /// - the parent of `path` does not exist or is not a directory.
+  /// - `path` exceeds the filesystem's name length, path depth or entry limits.
   pub fn create_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
-    let (directory, name) = self.resolve_mut_directory_file_name(path)?;
+    let path = self.absolute(path)?;
+    self.inject(Operation::CreateFile, &[&path])?;
+    self.check_create(&path)?;
+
+    let compression = self.filesystem.compression;
+    let (directory, name) = self.lookup_mut_parent(&path)?;
+
+    let mut file = File::new(name.clone());
+    file.content.set_compression(compression);
 
     match directory.entries.entry(name.clone()) {
       BTreeMapEntry::Occupied(_) => return Err(Error::Exists(name.clone())),
-      BTreeMapEntry::Vacant(v) => v.insert(Entry::File(File::new(name))),
+      BTreeMapEntry::Vacant(v) => v.insert(Entry::File(file)),
     };
 
+    directory.metadata.modified_at = Utc::now();
+
+    self.usage.files += 1;
+
     Ok(())
   }
 
@@ -108,6 +223,15 @@ impl Session {
     Ok(directory.entries.values())
   }
 
+  /// Returns the entries of `path` with the metadata `ls -l` shows, sorted by name.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `path` does not exist or isn't a directory.
+  pub fn read_dir_with_metadata<P: AsRef<Path>>(&self, path: P) -> Result<Vec<ListEntry>> {
+    Ok(self.list_directory(path)?.map(|entry| ListEntry::of(entry.into())).collect())
+  }
+
   /// Removes a directory or file.
   ///
   /// # Errors
@@ -116,11 +240,18 @@ impl Session {
   /// - the path does not exist.
   /// - the path does not have a parent (`/`).
   pub fn remove<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
-    let (directory, name) = self.resolve_mut_directory_file_name(path)?;
+    let path = self.absolute(path)?;
+    self.inject(Operation::Remove, &[&path])?;
+
+    let (directory, name) = self.lookup_mut_parent(&path)?;
 
-    if directory.entries.remove(&name).is_none() {
+    let Some(entry) = directory.entries.remove(&name) else {
       return Err(Error::NotExist(name.into()));
-    }
+    };
+
+    directory.metadata.modified_at = Utc::now();
+
+    self.usage -= Usage::of((&entry).into());
 
     Ok(())
   }
@@ -131,24 +262,33 @@ impl Session {
   ///
   /// This function will return an error if:
   /// - the source does not exist.
+  /// - the destination is inside the source.
   /// - the destination's parent does not exist.
-  /// - the destination already exists.
+  /// - the destination exceeds the filesystem's name length or path depth limits.
   pub fn move_entry<P: AsRef<Path>>(&mut self, src: P, dst: P) -> Result<()> {
-    let src = src.as_ref();
-    let dst = dst.as_ref();
+    let src = self.absolute(src)?;
+    let dst = self.absolute(dst)?;
+    self.inject(Operation::Move, &[&src, &dst])?;
 
-    let (src_directory, src_name) = self.resolve_mut_directory_file_name(src)?;
+    let src_entry = self.lookup(&src)?;
+    check_into_self(&src, &dst)?;
+    self.check_path(&dst, height(src_entry))?;
+
+    let (src_directory, src_name) = self.lookup_mut_parent(&src)?;
 
     let Some(mut src_entry) = src_directory.entries.remove(&src_name) else {
-      return Err(Error::NotExist(src.to_path_buf()));
+      return Err(Error::NotExist(src));
     };
 
-    let (dst_directory, dst_name) = match self.resolve_mut_directory_file_name(dst) {
+    let now = Utc::now();
+    src_directory.metadata.modified_at = now;
+
+    let (dst_directory, dst_name) = match self.lookup_mut_parent(&dst) {
       Ok(ok) => ok,
 
       // reinsert src_entry since it was removed
       Err(err) => {
-        let (src_directory, src_name) = self.resolve_mut_directory_file_name(src)?;
+        let (src_directory, src_name) = self.lookup_mut_parent(&src)?;
         src_directory.entries.insert(src_name, src_entry);
 
         return Err(err);
@@ -156,86 +296,693 @@ impl Session {
     };
 
     src_entry.rename(dst_name.clone());
-    dst_directory.entries.insert(dst_name, src_entry);
+    dst_directory.metadata.modified_at = now;
+
+    if let Some(replaced) = dst_directory.entries.insert(dst_name, src_entry) {
+      self.usage -= Usage::of((&replaced).into());
+    }
+
+    // like an open directory handle, the current directory follows it being moved
+    if let Ok(suffix) = self.current_directory.strip_prefix(&src) {
+      let mut current_directory = dst;
+      if !suffix.as_os_str().is_empty() {
+        current_directory.push(suffix);
+      }
+
+      self.replace_current_directory(current_directory);
+    }
+
+    Ok(())
+  }
+
+  /// Copies a directory or file and all of its descendants to a new location, replacing the
+  /// destination if it exists. Metadata, including extended attributes, is preserved.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if:
+  /// - the source does not exist.
+  /// - the destination is inside the source.
+  /// - the destination's parent does not exist.
+  /// - the destination exceeds the filesystem's name length or path depth limits.
+  /// - the copy would exceed the filesystem's total size or entry limits.
+  pub fn copy_entry<P: AsRef<Path>>(&mut self, src: P, dst: P) -> Result<()> {
+    let src = self.absolute(src)?;
+    let dst = self.absolute(dst)?;
+    self.inject(Operation::Copy, &[&src, &dst])?;
+
+    let src_entry = self.lookup(&src)?;
+    check_into_self(&src, &dst)?;
+    let usage = Usage::of(src_entry.clone());
+    self.check_path(&dst, height(src_entry.clone()))?;
+
+    // the entry replaced at the destination frees its space
+    let replaced = self.lookup(&dst).map_or_else(|_| Usage::default(), Usage::of);
+    self.check_space(
+      &dst,
+      usage.bytes.saturating_sub(replaced.bytes),
+      usage.entries().saturating_sub(replaced.entries()),
+    )?;
+
+    let mut entry = src_entry.cloned();
+
+    let (dst_directory, dst_name) = self.lookup_mut_parent(&dst)?;
+    entry.rename(dst_name.clone());
+    dst_directory.metadata.modified_at = Utc::now();
+
+    if let Some(replaced) = dst_directory.entries.insert(dst_name, entry) {
+      self.usage -= Usage::of((&replaced).into());
+    }
+
+    self.usage += usage;
 
     Ok(())
   }
 
-  /// Calls a function `f` on every descendant of `root`.
-  /// - If `f` returns `false` on a directory entry then it will not be entered.
-  /// - The return value of `f` is ignored for file entries.
+  /// Calls a function `f` on `root` and every descendant of `root`.
+  /// - If `f` returns [`Walk::Skip`] on a directory entry then it will not be entered.
+  /// - If `f` returns [`Walk::Stop`] then no more entries are visited.
   ///
   /// # Errors
   ///
   /// This function will return an error if `root` does not exist.
-  pub fn walk<P: AsRef<Path>, F, S>(&self, root: P, f: F) -> Result<()>
+  pub fn walk<P: AsRef<Path>, F, S>(&self, root: P, mut f: F) -> Result<()>
   where
     S: Into<Walk>,
-    F: Fn(&Path, &BorrowedEntry) -> S,
+    F: FnMut(&Path, &BorrowedEntry) -> S,
   {
+    let mut entries = self.walk_iter(root, WalkOptions::default())?;
+
+    while let Some(entry) = entries.next() {
+      match f(&entry.path(), entry.entry()).into() {
+        Walk::Skip if entry.is_directory() => entries.skip_current_dir(),
+        Walk::Skip | Walk::Continue => (),
+        Walk::Stop => break,
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Returns an iterator over `root` and its descendants, as configured by `options`.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `root` does not exist.
+  pub fn walk_iter<P: AsRef<Path>>(&self, root: P, options: WalkOptions) -> Result<WalkIter<'_>> {
     let (root, entry) = self.resolve(root)?;
-    let mut stack = vec![(root, entry)];
 
-    while let Some((path, entry)) = stack.pop() {
-      if let Walk::Skip = f(&path, &entry).into() {
+    Ok(WalkIter::new(root, entry, options))
+  }
+
+  /// Returns an iterator over the entries under `root` that match `predicate`, including `root`
+  /// itself, walked as configured by `options`.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `root` does not exist.
+  pub fn find<'a, P: AsRef<Path>>(
+    &'a self,
+    root: P,
+    predicate: &'a Predicate,
+    options: WalkOptions,
+  ) -> Result<impl Iterator<Item = DirEntry<'a>> + 'a> {
+    Ok(self.walk_iter(root, options)?.filter(|entry| predicate.matches(entry.entry())))
+  }
+
+  /// Returns the existing paths matching `pattern`, sorted, where each component of the pattern
+  /// can be a glob, e.g. `/logs/*/*.log`. Wildcards don't match a leading `.` unless the
+  /// component starts with one. Matches of a relative pattern are relative too.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if a component of `pattern` is an invalid glob.
+  pub fn glob<P: AsRef<Path>>(&self, pattern: P) -> Result<Vec<PathBuf>> {
+    let mut matches = vec![PathBuf::new()];
+
+    for component in pattern.as_ref().components() {
+      let name = component.as_os_str();
+      let glob = name.to_str().filter(|name| name.contains(['*', '?', '[']));
+
+      let Some(glob) = glob else {
+        matches.iter_mut().for_each(|path| path.push(name));
         continue;
-      }
+      };
+
+      let matcher = Glob::new(glob)
+        .map_err(|_| Error::InvalidPattern(glob.to_owned()))?
+        .compile_matcher();
+
+      matches = matches
+        .iter()
+        .filter_map(|path| {
+          let directory = if path.as_os_str().is_empty() { Path::new(".") } else { path };
+          let entries = self.list_directory(directory).ok()?;
+
+          Some(
+            entries
+              .map(Entry::name)
+              .filter(|name| glob.starts_with('.') || !name.as_encoded_bytes().starts_with(b"."))
+              .filter(|name| matcher.is_match(name))
+              .map(|name| path.join(name))
+              .collect::<Vec<_>>(),
+          )
+        })
+        .flatten()
+        .collect();
+    }
+
+    matches.retain(|path| !path.as_os_str().is_empty() && self.resolve(path).is_ok());
+    matches.sort();
+
+    Ok(matches)
+  }
+
+  /// Builds the tree of entries under `root` for display, with the root named after `root` as
+  /// given.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `root` does not exist.
+  pub fn tree<P: AsRef<Path>>(&self, root: P, options: &TreeOptions) -> Result<Tree> {
+    let (_, entry) = self.resolve(&root)?;
+    let node = TreeNode::build(root.as_ref().display().to_string(), entry, options);
+
+    Ok(Tree::new(node, options))
+  }
+
+  /// Searches the lines of `root` and the files under it for matches of the regex `pattern`,
+  /// returning the matching lines in walk order.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if:
+  /// - `root` does not exist.
+  /// - `pattern` or any of the globs of `options` is invalid.
+  pub fn grep<P: AsRef<Path>>(&self, root: P, pattern: &str, options: &GrepOptions) -> Result<Vec<GrepMatch>> {
+    let searcher = Searcher::new(pattern, options)?;
+    let mut entries = self.walk_iter(root, WalkOptions::new())?;
+    let mut matches = Vec::new();
 
-      if let BorrowedEntry::Directory(directory) = entry {
-        for (name, entry) in directory.entries.iter().rev() {
-          stack.push((path.join(name), entry.into()));
+    while let Some(entry) = entries.next() {
+      match entry.entry() {
+        BorrowedEntry::Directory(_) => {
+          if entry.depth() > 0 && !searcher.enters(entry.name()) {
+            entries.skip_current_dir();
+          }
+        }
+        BorrowedEntry::File(file) => {
+          if entry.depth() == 0 || searcher.searches(entry.name()) {
+            searcher.search(&entry.path(), &file.content, &mut matches)?;
+          }
         }
       }
     }
 
+    Ok(matches)
+  }
+
+  /// Calls a function `f` with mutable access to `root` and its descendants in a single traversal,
+  /// as configured by `options`.
+  /// - If `f` returns [`Walk::Skip`] on a directory entry then it will not be entered.
+  /// - If `f` returns [`Walk::Stop`] then no more entries are visited.
+  ///
+  /// `f` may change the content and metadata of entries and add or remove entries of directories
+  /// before they are entered. Changes to an entry's name are reverted, as entries must be renamed
+  /// with [`Self::move_entry`]. The length and allocation of a file are recomputed from its content
+  /// after each call. Changes are not checked against the filesystem's limits, but the
+  /// filesystem's usage is updated once the walk ends, so later operations are.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `root` does not exist.
+  pub fn walk_mut<P: AsRef<Path>, F, S>(&mut self, root: P, mut options: WalkOptions, mut f: F) -> Result<()>
+  where
+    S: Into<Walk>,
+    F: FnMut(&Path, &mut MutBorrowedEntry) -> S,
+  {
+    let (mut path, mut entry) = self.resolve_mut(root)?;
+    let name = entry.name().to_owned();
+    let before = Usage::of(entry.as_borrowed());
+
+    walk::walk_mut(&mut path, &mut entry, &mut options, &mut f);
+
+    entry.metadata_mut().name = name;
+    let after = Usage::of(entry.as_borrowed());
+
+    self.usage -= before;
+    self.usage += after;
+
     Ok(())
   }
 
-  /// Appends `content` to a file at `path`.
+  /// Returns the space used by `path` and all of its descendants.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `path` does not exist.
+  pub fn disk_usage<P: AsRef<Path>>(&self, path: P) -> Result<Usage> {
+    let (_, entry) = self.resolve(path)?;
+
+    Ok(Usage::of(entry))
+  }
+
+  /// Returns the space used by the entire filesystem.
+  #[must_use]
+  pub fn filesystem_usage(&self) -> Usage {
+    self.usage
+  }
+
+  /// Returns how much memory file contents take up.
+  ///
+  /// This is linear in the number of blocks of all files.
+  #[must_use]
+  pub fn storage_stats(&self) -> StorageStats {
+    let mut owned_bytes = 0;
+    let mut stack = vec![&self.filesystem.root];
+
+    while let Some(directory) = stack.pop() {
+      for entry in directory.entries.values() {
+        match entry {
+          Entry::File(file) => owned_bytes += file.content.stored_len().owned,
+          Entry::Directory(directory) => stack.push(directory),
+        }
+      }
+    }
+
+    let blob_stats = self.filesystem.storage.blob_store().map(BlobStore::stats);
+
+    StorageStats {
+      logical_bytes: self.usage.bytes,
+      stored_bytes: owned_bytes + blob_stats.map_or(0, |stats| stats.bytes),
+      blobs: blob_stats.map(|stats| stats.blobs),
+    }
+  }
+
+  /// Sets how the content of the file at `path` is compressed, compressing or decompressing its
+  /// existing content.
   ///
   /// # Errors
   ///
   /// This function will return an error if `path` does not exist or is not a file.
-  pub fn write_file<P: AsRef<Path>>(&mut self, path: P, content: Vec<u8>) -> Result<()> {
+  pub fn set_compression<P: AsRef<Path>>(&mut self, path: P, compression: Compression) -> Result<()> {
     let (path, entry) = self.resolve_mut(path)?;
     let MutBorrowedEntry::File(file) = entry else {
       return Err(Error::NotFile(path));
     };
 
-    file.content.extend(content);
+    file.content.set_compression(compression);
 
     Ok(())
   }
 
-  /// Returns a file's content as a string.
+  /// Appends `content` to a file at `path`, returning the number of bytes written.
+  ///
+  /// Fewer bytes than the length of `content` are only written if a short write fault is injected.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if:
+  /// - `path` does not exist or is not a file.
+  /// - the write would exceed the filesystem's total or per-file size limits.
+  pub fn write_file<P: AsRef<Path>>(&mut self, path: P, content: Vec<u8>) -> Result<usize> {
+    self.write(path.as_ref(), None, &content)
+  }
+
+  /// Writes `content` to a file at `path` starting at `offset`, returning the number of bytes
+  /// written.
+  ///
+  /// Writing past the end of the file extends it, leaving a hole between the previous end and
+  /// `offset` that reads as zeros and isn't allocated. Fewer bytes than the length of `content` are
+  /// only written if a short write fault is injected.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if:
+  /// - `path` does not exist or is not a file.
+  /// - the write would exceed the filesystem's total or per-file size limits.
+  pub fn write_file_at<P: AsRef<Path>>(&mut self, path: P, offset: u64, content: Vec<u8>) -> Result<usize> {
+    self.write(path.as_ref(), Some(offset), &content)
+  }
+
+  /// Sets the length of a file at `path`, discarding anything past `len` or extending the file
+  /// with an unallocated hole.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if:
+  /// - `path` does not exist or is not a file.
+  /// - extending the file would exceed the filesystem's total or per-file size limits.
+  pub fn truncate<P: AsRef<Path>>(&mut self, path: P, len: u64) -> Result<()> {
+    let path = self.absolute(path)?;
+    self.inject(Operation::Truncate, &[&path])?;
+
+    let limits = self.filesystem.limits;
+    let used = self.usage.bytes;
+
+    self.update_file(path, |path, file, store| {
+      check_file_len(&limits, used, path, file, len)?;
+      file.truncate(len, store);
+
+      Ok(())
+    })
+  }
+
+  /// Deallocates `len` bytes of a file at `path` starting at `offset`, turning them into a hole
+  /// that reads as zeros. The length of the file is unchanged.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `path` does not exist or is not a file.
+  pub fn punch_hole<P: AsRef<Path>>(&mut self, path: P, offset: u64, len: u64) -> Result<()> {
+    let path = self.absolute(path)?;
+    self.inject(Operation::PunchHole, &[&path])?;
+
+    self.update_file(path, |_, file, store| {
+      file.punch_hole(offset, len, store);
+
+      Ok(())
+    })
+  }
+
+  /// Returns the first offset at or after `offset` in a file at `path` that contains data, or
+  /// `None` if there is no data past `offset`. This is like `lseek` with `SEEK_DATA`.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `path` does not exist or is not a file.
+  pub fn seek_data<P: AsRef<Path>>(&self, path: P, offset: u64) -> Result<Option<u64>> {
+    let (path, entry) = self.resolve(path)?;
+    let BorrowedEntry::File(file) = entry else {
+      return Err(Error::NotFile(path));
+    };
+
+    Ok(file.content.seek_data(offset))
+  }
+
+  /// Returns the first offset at or after `offset` in a file at `path` that is in a hole, or `None`
+  /// if `offset` is past the end of the file. The end of the file is always considered a hole.
+  /// This is like `lseek` with `SEEK_HOLE`.
   ///
   /// # Errors
   ///
   /// This function will return an error if `path` does not exist or is not a file.
+  pub fn seek_hole<P: AsRef<Path>>(&self, path: P, offset: u64) -> Result<Option<u64>> {
+    let (path, entry) = self.resolve(path)?;
+    let BorrowedEntry::File(file) = entry else {
+      return Err(Error::NotFile(path));
+    };
+
+    Ok(file.content.seek_hole(offset))
+  }
+
+  /// Returns a file's content as a string.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `path` does not exist or is not a file, or its content
+  /// doesn't fit in memory.
   pub fn read_file<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+    let path = self.absolute(path)?;
+    self.inject(Operation::Read, &[&path])?;
+
     let (path, entry) = self.resolve(path)?;
     let BorrowedEntry::File(file) = entry else {
       return Err(Error::NotFile(path));
     };
 
-    Ok(String::from_utf8_lossy(&file.content).into_owned())
+    let content = file.content.to_vec().map_err(|OutOfMemory| Error::OutOfMemory(path))?;
+
+    Ok(String::from_utf8_lossy(&content).into_owned())
+  }
+
+  /// Sets the extended attribute `name` of `path` to `value`, replacing any existing value.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if:
+  /// - `path` does not exist.
+  /// - `name` is not a valid extended attribute name.
+  /// - `value` is too large.
+  pub fn set_xattr<P: AsRef<Path>>(&mut self, path: P, name: &str, value: Vec<u8>) -> Result<()> {
+    xattr::validate_name(name)?;
+    xattr::validate_value(name, &value)?;
+
+    let (_, mut entry) = self.resolve_mut(path)?;
+    entry.metadata_mut().xattrs.insert(name.to_owned(), value);
+
+    Ok(())
+  }
+
+  /// Returns the value of the extended attribute `name` of `path`.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `path` does not exist or has no attribute `name`.
+  pub fn get_xattr<P: AsRef<Path>>(&self, path: P, name: &str) -> Result<&[u8]> {
+    let (_, entry) = self.resolve(path)?;
+
+    entry
+      .into_metadata()
+      .xattrs
+      .get(name)
+      .map(Vec::as_slice)
+      .ok_or_else(|| Error::NoXattr(name.to_owned()))
+  }
+
+  /// Returns the names of the extended attributes of `path`, in sorted order.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `path` does not exist.
+  pub fn list_xattr<P: AsRef<Path>>(&self, path: P) -> Result<impl Iterator<Item = &str>> {
+    let (_, entry) = self.resolve(path)?;
+
+    Ok(entry.into_metadata().xattrs.keys().map(String::as_str))
+  }
+
+  /// Removes the extended attribute `name` of `path`.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `path` does not exist or has no attribute `name`.
+  pub fn remove_xattr<P: AsRef<Path>>(&mut self, path: P, name: &str) -> Result<()> {
+    let (_, mut entry) = self.resolve_mut(path)?;
+
+    if entry.metadata_mut().xattrs.remove(name).is_none() {
+      return Err(Error::NoXattr(name.to_owned()));
+    }
+
+    Ok(())
+  }
+
+  /// Writes `content` to a file at `path` starting at `offset`, or at the end of the file if
+  /// `offset` is `None`, returning the number of bytes written.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if:
+  /// - `path` does not exist or is not a file.
+  /// - the write would exceed the filesystem's total or per-file size limits.
+  fn write(&mut self, path: &Path, offset: Option<u64>, content: &[u8]) -> Result<usize> {
+    let path = self.absolute(path)?;
+    let written = self
+      .inject(Operation::Write, &[&path])?
+      .map_or(content.len(), |max| max.min(content.len()));
+    let content = &content[..written];
+
+    let limits = self.filesystem.limits;
+    let used = self.usage.bytes;
+
+    self.update_file(path, |path, file, store| {
+      let offset = offset.unwrap_or(file.metadata.len);
+      let end = offset
+        .checked_add(written as u64)
+        .ok_or_else(|| Error::FileTooLarge(path.to_owned()))?;
+
+      check_file_len(&limits, used, path, file, end)?;
+      file
+        .write_at(offset, content, store)
+        .map_err(|TooLarge| Error::FileTooLarge(path.to_owned()))?;
+
+      Ok(written)
+    })
+  }
+
+  /// Calls `f` on the file at the canonical `path` and the filesystem's blob store, keeping the
+  /// session's usage in sync with any change to the file's length or allocation.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `path` does not exist or is not a file, or if `f`
+  /// returns an error.
+  fn update_file<T, F>(&mut self, path: PathBuf, f: F) -> Result<T>
+  where
+    F: FnOnce(&Path, &mut File, Option<&BlobStore>) -> Result<T>,
+  {
+    let store = self.filesystem.storage.blob_store().cloned();
+
+    let MutBorrowedEntry::File(file) = self.lookup_mut(&path)? else {
+      return Err(Error::NotFile(path));
+    };
+
+    let (len, allocated) = (file.metadata.len, file.metadata.allocated);
+    let res = f(&path, file, store.as_ref());
+
+    if res.is_ok() {
+      file.metadata.modified_at = Utc::now();
+    }
+    let (new_len, new_allocated) = (file.metadata.len, file.metadata.allocated);
+
+    self.usage.bytes = self.usage.bytes - len + new_len;
+    self.usage.allocated = self.usage.allocated - allocated + new_allocated;
+
+    res
+  }
+
+  /// Counts `operation` on the canonical `paths` against the fault injection rules, failing if an
+  /// error fault is triggered.
+  ///
+  /// Returns the maximum number of bytes to write if a short write fault is triggered.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if an [`Fault::Io`] or [`Fault::NoSpace`] fault is triggered.
+  fn inject(&self, operation: Operation, paths: &[&Path]) -> Result<Option<usize>> {
+    match self.faults.lock().inject(operation, paths) {
+      None => Ok(None),
+      Some(Fault::Io) => Err(Error::Io(paths[0].to_owned())),
+      Some(Fault::NoSpace) => Err(Error::NoSpace(paths[0].to_owned())),
+      Some(Fault::ShortWrite(max)) => Ok(Some(max)),
+    }
+  }
+
+  /// Returns at most `len` bytes of a file's content starting at `offset`.
+  ///
+  /// Only the blocks of the file overlapping the requested range are read.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `path` does not exist or is not a file, or the bytes
+  /// read don't fit in memory.
+  pub fn read_file_at<P: AsRef<Path>>(&self, path: P, offset: u64, len: usize) -> Result<Vec<u8>> {
+    let path = self.absolute(path)?;
+    self.inject(Operation::Read, &[&path])?;
+
+    let (path, entry) = self.resolve(path)?;
+    let BorrowedEntry::File(file) = entry else {
+      return Err(Error::NotFile(path));
+    };
+
+    file.content.read_at(offset, len).map_err(|OutOfMemory| Error::OutOfMemory(path))
+  }
+
+  /// Checks that a new entry can be created at the canonical `path` without exceeding the
+  /// filesystem's limits.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if:
+  /// - `path` exceeds the name length or path depth limits.
+  /// - the filesystem has no more room for entries.
+  fn check_create(&self, path: &Path) -> Result<()> {
+    self.check_path(path, 0)?;
+    self.check_space(path, 0, 1)
+  }
+
+  /// Checks that adding `bytes` and `entries` at the canonical `path` does not exceed the
+  /// filesystem's total size or entry limits.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if the filesystem has no more room.
+  fn check_space(&self, path: &Path, bytes: u64, entries: u64) -> Result<()> {
+    let limits = &self.filesystem.limits;
+
+    if limits.max_bytes.is_some_and(|max| self.usage.bytes + bytes > max)
+      || limits.max_entries.is_some_and(|max| self.usage.entries() + entries > max)
+    {
+      return Err(Error::NoSpace(path.to_owned()));
+    }
+
+    Ok(())
+  }
+
+  /// Checks that an entry at the canonical `path`, with descendants up to `height` levels below
+  /// it, does not exceed the filesystem's name length or path depth limits.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if:
+  /// - the file name of `path` is longer than the maximum name length.
+  /// - the depth of `path` plus `height` is larger than the maximum path depth.
+  fn check_path(&self, path: &Path, height: usize) -> Result<()> {
+    let limits = &self.filesystem.limits;
+
+    let name_len = path.file_name().map_or(0, OsStr::len);
+    if limits.max_name_len.is_some_and(|max| name_len > max) {
+      return Err(Error::NameTooLong(path.to_owned()));
+    }
+
+    if let Some(max) = limits.max_depth {
+      if path.components().count() - 1 + height > max {
+        return Err(Error::TooDeep(path.to_owned()));
+      }
+    }
+
+    Ok(())
   }
 
-  /// Returns the absolute form of this path.
+  /// Returns the absolute form of this path, relative to the current directory, with `.` and `..`
+  /// applied lexically in a single pass over its components. See [`Session::canonicalize`] for a
+  /// form resolved against the tree.
   ///
   /// # Errors
   ///
   /// This function will return an error if cleaning the path fails.
-  fn canonicalize<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+  pub fn absolute<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
     let path = path.as_ref();
+    let len = path.as_os_str().len();
+
+    if let Some(path) = self.absolute_cached(path) {
+      return Ok(path);
+    }
 
-    let path = if path.is_absolute() {
-      path.to_owned()
+    let base = if path.is_absolute() {
+      PathBuf::with_capacity(len)
     } else {
-      self.current_directory.join(path)
+      let mut base = PathBuf::with_capacity(self.current_directory.as_os_str().len() + 1 + len);
+      base.push(&self.current_directory);
+      base
     };
 
-    crate::util::clean_path(path)
+    crate::util::join_clean(base, path)
+  }
+
+  /// Returns the absolute form of `path` without cleaning it if it names an entry in the current
+  /// directory or a cached one, which are known to be canonical.
+  fn absolute_cached(&self, path: &Path) -> Option<PathBuf> {
+    let bytes = path.as_os_str().as_encoded_bytes();
+
+    if path_cache::is_name(bytes) {
+      return Some(self.current_directory.join(path));
+    }
+
+    let (parent, name) = path_cache::split(bytes)?;
+    let canonical =
+      path_cache::is_name(name) && (parent == self.current_directory.as_os_str().as_encoded_bytes() || self.paths.lock().contains(parent));
+
+    canonical.then(|| path.to_owned())
+  }
+
+  /// Returns the names of the canonical directory `path` if it is the current directory or in the
+  /// path cache.
+  fn directory_names(&self, path: &Path) -> Option<Names> {
+    if path.as_os_str() == self.current_directory.as_os_str() {
+      return Some(self.current_names.clone());
+    }
+
+    self.paths.lock().get(path)
   }
 
   /// Resolves a path to its canonical path and its [`Entry`].
@@ -245,28 +992,85 @@ impl Session {
   /// # Errors
   ///
   /// This function will return an error if any component of `path` does not exist.
-  pub fn resolve<P: AsRef<Path>>(&self, path: P) -> Result<(PathBuf, BorrowedEntry)> {
-    let path = self.canonicalize(path)?;
+  pub fn resolve<P: AsRef<Path>>(&self, path: P) -> Result<(PathBuf, BorrowedEntry<'_>)> {
+    let path = self.absolute(path)?;
+    let entry = self.lookup(&path)?;
 
-    let mut parent = BorrowedEntry::Directory(&self.filesystem.root);
+    Ok((path, entry))
+  }
 
-    for component in path.components().skip(1) {
-      let Component::Normal(component) = component else {
-        return Err(Error::UnsupportedComponent(format!("{component:?}")));
-      };
+  /// Returns the canonical form of `path`, like the `realpath` command.
+  ///
+  /// Unlike [`Session::resolve`], which applies `..` lexically, `path` is resolved against the
+  /// tree one component at a time, so every component before a `..` must exist and be a
+  /// directory. `..` at the root stays at the root.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if:
+  /// - any component of `path` or the current directory does not exist.
+  /// - any component followed by `..` or a name is a file.
+  pub fn canonicalize<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+    let path = path.as_ref();
+    let base = if path.is_absolute() {
+      Path::new("/")
+    } else {
+      &self.current_directory
+    };
 
-      let BorrowedEntry::Directory(directory) = parent else {
-        return Err(Error::NotDirectory(parent.name().into()));
-      };
+    let mut canonical = PathBuf::from("/");
+    let mut directories = vec![&self.filesystem.root];
+    let mut is_file = false;
 
-      let Some(next) = directory.entries.get(component) else {
-        return Err(Error::NotExist(component.into()));
-      };
+    for component in base.components().chain(path.components()) {
+      if is_file && matches!(component, Component::ParentDir | Component::Normal(_)) {
+        return Err(Error::NotDirectory(canonical));
+      }
 
-      parent = BorrowedEntry::from(next);
+      match component {
+        Component::RootDir => {
+          canonical.push(component);
+          directories.truncate(1);
+        }
+        Component::CurDir => (),
+        Component::ParentDir => {
+          if directories.len() > 1 {
+            canonical.pop();
+            directories.pop();
+          }
+        }
+        Component::Normal(name) => {
+          let directory = directories[directories.len() - 1];
+          canonical.push(name);
+
+          match directory.entries.get(name) {
+            Some(Entry::Directory(directory)) => directories.push(directory),
+            Some(Entry::File(_)) => is_file = true,
+            None => return Err(Error::NotExist(canonical)),
+          }
+        }
+        Component::Prefix(_) => return Err(Error::UnsupportedComponent(format!("{component:?}"))),
+      }
     }
 
-    Ok((path, parent))
+    Ok(canonical)
+  }
+
+  /// Looks up the entry at the canonical `path`, walking from the root with the names of its
+  /// parent from the current directory or the path cache if possible.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if any component of `path` does not exist.
+  fn lookup(&self, path: &Path) -> Result<BorrowedEntry<'_>> {
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+      return Ok(BorrowedEntry::Directory(&self.filesystem.root));
+    };
+
+    match self.directory_names(parent) {
+      Some(names) => path_cache::descend(&self.filesystem.root, names.iter().map(OsString::as_os_str).chain([name])),
+      None => path_cache::descend(&self.filesystem.root, path_cache::components(path)),
+    }
   }
 
   /// Resolves a path to its canonical path and a mutable reference to its [`Entry`].
@@ -276,31 +1080,32 @@ impl Session {
   /// # Errors
   ///
   /// This function will return an error if any component of `path` does not exist.
-  fn resolve_mut<P: AsRef<Path>>(&mut self, path: P) -> Result<(PathBuf, MutBorrowedEntry)> {
-    let path = self.canonicalize(path)?;
-
-    let mut parent = MutBorrowedEntry::Directory(&mut self.filesystem.root);
+  fn resolve_mut<P: AsRef<Path>>(&mut self, path: P) -> Result<(PathBuf, MutBorrowedEntry<'_>)> {
+    let path = self.absolute(path)?;
+    let entry = self.lookup_mut(&path)?;
 
-    for component in path.components().skip(1) {
-      let Component::Normal(component) = component else {
-        return Err(Error::UnsupportedComponent(format!("{component:?}")));
-      };
-
-      let MutBorrowedEntry::Directory(directory) = parent else {
-        return Err(Error::NotDirectory(parent.name().into()));
-      };
+    Ok((path, entry))
+  }
 
-      let Some(next) = directory.entries.get_mut(component) else {
-        return Err(Error::NotExist(component.into()));
-      };
+  /// Looks up a mutable reference to the entry at the canonical `path`, like
+  /// [`Session::lookup`].
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if any component of `path` does not exist.
+  fn lookup_mut(&mut self, path: &Path) -> Result<MutBorrowedEntry<'_>> {
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+      return Ok(MutBorrowedEntry::Directory(&mut self.filesystem.root));
+    };
 
-      parent = MutBorrowedEntry::from(next);
+    match self.directory_names(parent) {
+      Some(names) => path_cache::descend_mut(&mut self.filesystem.root, names.iter().map(OsString::as_os_str).chain([name])),
+      None => path_cache::descend_mut(&mut self.filesystem.root, path_cache::components(path)),
     }
-
-    Ok((path, parent))
   }
 
-  /// Resolves a path to a mutable reference to the parent [`Directory`] and its file name.
+  /// Looks up a mutable reference to the parent [`Directory`] of the canonical `path` and its
+  /// file name.
   ///
   /// # Errors
   ///
@@ -308,22 +1113,73 @@ impl Session {
   /// - the parent directory does not exist
   /// - the parent is not a directory
   /// - `path` has no parent or file name
-  fn resolve_mut_directory_file_name<P: AsRef<Path>>(&mut self, path: P) -> Result<(&mut Directory, OsString)> {
-    let path = self.canonicalize(path.as_ref())?;
-
+  fn lookup_mut_parent(&mut self, path: &Path) -> Result<(&mut Directory, OsString)> {
     let Some(parent) = path.parent() else {
-      return Err(Error::NoParent(path));
+      return Err(Error::NoParent(path.to_owned()));
     };
 
-    let (_, entry) = self.resolve_mut(parent)?;
-    let MutBorrowedEntry::Directory(directory) = entry else {
+    let parent_entry = match self.directory_names(parent) {
+      Some(names) => path_cache::descend_mut(&mut self.filesystem.root, names.iter().map(OsString::as_os_str))?,
+      None => path_cache::descend_mut(&mut self.filesystem.root, path_cache::components(parent))?,
+    };
+
+    let MutBorrowedEntry::Directory(directory) = parent_entry else {
       return Err(Error::NotDirectory(parent.to_owned()));
     };
 
     let Some(name) = path.file_name() else {
-      return Err(Error::NoFileName(path));
+      return Err(Error::NoFileName(path.to_owned()));
     };
 
     Ok((directory, name.to_os_string()))
   }
 }
+
+/// Checks that setting the length of `file` to `len` does not exceed the total or per-file size
+/// `limits`, given that `used` bytes are in use.
+///
+/// # Errors
+///
+/// This function will return an error if growing `file` exceeds either limit.
+fn check_file_len(limits: &Limits, used: u64, path: &Path, file: &File, len: u64) -> Result<()> {
+  let growth = len.saturating_sub(file.metadata.len);
+
+  if limits.max_bytes.is_some_and(|max| used + growth > max) {
+    return Err(Error::NoSpace(path.to_owned()));
+  }
+
+  if growth > 0 && limits.max_file_size.is_some_and(|max| len > max) {
+    return Err(Error::QuotaExceeded(path.to_owned()));
+  }
+
+  Ok(())
+}
+
+/// Checks that the canonical `dst` is not a descendant of the canonical `src`.
+///
+/// # Errors
+///
+/// This function will return an error if `dst` is inside `src`.
+fn check_into_self(src: &Path, dst: &Path) -> Result<()> {
+  if dst != src && dst.starts_with(src) {
+    return Err(Error::IntoSelf(src.to_owned()));
+  }
+
+  Ok(())
+}
+
+/// Returns the number of levels of descendants below `entry`.
+fn height(entry: BorrowedEntry) -> usize {
+  let mut height = 0;
+  let mut stack = vec![(entry, 0)];
+
+  while let Some((entry, depth)) = stack.pop() {
+    height = height.max(depth);
+
+    if let BorrowedEntry::Directory(directory) = entry {
+      stack.extend(directory.entries.values().map(|entry| (entry.into(), depth + 1)));
+    }
+  }
+
+  height
+}
@@ -0,0 +1,113 @@
+use std::{collections::BTreeMap, ffi::OsString};
+
+use chrono::{DateTime, Utc};
+use fs::{entry::Borrowed as BorrowedEntry, Compression};
+
+/// The owner reported for every entry, since the filesystem has no users.
+pub const OWNER: &str = "root";
+
+/// An entry of a directory with the metadata `ls -l` shows.
+///
+/// See [`Session::read_dir_with_metadata`].
+///
+/// [`Session::read_dir_with_metadata`]: crate::Session::read_dir_with_metadata
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ListEntry {
+  pub name: OsString,
+
+  pub is_directory: bool,
+
+  /// The Unix permission bits. The filesystem has no permissions, so these are `0o755` for
+  /// directories and `0o644` for files.
+  pub mode: u32,
+
+  /// The number of hard links, like on Unix: 1 for files, and 2 plus the number of
+  /// subdirectories for directories.
+  pub links: u64,
+
+  /// The owner of the entry, always [`OWNER`].
+  #[cfg_attr(feature = "serde", serde(skip_deserializing, default = "owner"))]
+  pub owner: &'static str,
+
+  /// The length in bytes of a file's content, or `0` for directories.
+  pub len: u64,
+
+  /// The number of bytes of a file's content backed by stored blocks.
+  pub allocated: u64,
+
+  /// The number of bytes a file's blocks take up in memory, after compression and deduplication.
+  pub stored: u64,
+
+  /// How a file's content is compressed, or [`Compression::None`] for directories.
+  pub compression: Compression,
+
+  pub created_at: DateTime<Utc>,
+
+  pub modified_at: DateTime<Utc>,
+
+  /// Extended attributes, mapping names to values.
+  pub xattrs: BTreeMap<String, Vec<u8>>,
+}
+
+impl ListEntry {
+  /// Returns the listing metadata of `entry`.
+  #[must_use]
+  pub fn of(entry: BorrowedEntry) -> Self {
+    let (mode, links, stored, compression) = match entry {
+      BorrowedEntry::File(file) => (0o644, 1, file.content.stored_len().total(), file.content.compression()),
+      BorrowedEntry::Directory(directory) => {
+        let subdirectories = directory.entries.values().filter(|entry| entry.is_directory()).count();
+
+        (0o755, 2 + subdirectories as u64, 0, Compression::None)
+      }
+    };
+
+    let is_directory = entry.is_directory();
+    let metadata = entry.into_metadata();
+
+    Self {
+      name: metadata.name.clone(),
+      is_directory,
+      mode,
+      links,
+      owner: OWNER,
+      len: metadata.len,
+      allocated: metadata.allocated,
+      stored,
+      compression,
+      created_at: metadata.created_at,
+      modified_at: metadata.modified_at,
+      xattrs: metadata.xattrs.clone(),
+    }
+  }
+
+  /// Returns whether the name starts with a `.`, which hides the entry from listings by default.
+  #[must_use]
+  pub fn is_hidden(&self) -> bool {
+    self.name.as_encoded_bytes().starts_with(b".")
+  }
+
+  /// Returns the type and permissions formatted like `ls -l`, e.g. `drwxr-xr-x`.
+  #[must_use]
+  pub fn mode_string(&self) -> String {
+    let mut mode = String::with_capacity(10);
+    mode.push(if self.is_directory { 'd' } else { '-' });
+
+    for shift in [6, 3, 0] {
+      let bits = self.mode >> shift;
+
+      mode.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+      mode.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+      mode.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+    }
+
+    mode
+  }
+}
+
+/// Returns [`OWNER`], which deserialized entries always have.
+#[cfg(feature = "serde")]
+fn owner() -> &'static str {
+  OWNER
+}
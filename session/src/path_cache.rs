@@ -0,0 +1,164 @@
+use std::{
+  collections::VecDeque,
+  ffi::{OsStr, OsString},
+  path::{Component, Path},
+  sync::Arc,
+};
+
+use fs::{
+  entry::{Borrowed as BorrowedEntry, MutBorrowed as MutBorrowedEntry},
+  Directory,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{Error, Result};
+
+/// The most directories a [`PathCache`] holds, and the most misses it remembers.
+const CAPACITY: usize = 1024;
+
+/// The names of the components below the root of a canonical path, e.g. `a` and `b` for `/a/b`.
+pub(crate) type Names = Arc<[OsString]>;
+
+/// A cache splitting canonical directory paths into the names of their components.
+///
+/// This is not a dentry cache: entries are owned inline by their parent directory, so the cache
+/// holds no handles into the tree and a lookup still walks from the root one name at a time. What
+/// it saves is parsing and cleaning the path. The names are a function of the path alone, so
+/// they never go stale when directories are moved or removed, and a path being cached also means
+/// it is canonical.
+///
+/// A path is only cached the second time it misses, so looking up many directories once costs
+/// no more than without the cache. When full, the oldest path is evicted.
+#[derive(Default)]
+pub(crate) struct PathCache {
+  directories: FxHashMap<Arc<[u8]>, Names>,
+
+  /// The cached paths, oldest first.
+  order: VecDeque<Arc<[u8]>>,
+
+  /// The paths that missed once since they were last cached.
+  missed: FxHashSet<Box<[u8]>>,
+}
+
+impl PathCache {
+  /// Returns the names of the canonical directory `path` if it is cached. Otherwise, caches them
+  /// if it missed before, or remembers the miss.
+  pub(crate) fn get(&mut self, path: &Path) -> Option<Names> {
+    let key = path.as_os_str().as_encoded_bytes();
+
+    if let Some(names) = self.directories.get(key) {
+      return Some(names.clone());
+    }
+
+    if self.missed.len() >= CAPACITY {
+      self.missed.clear();
+    }
+
+    if self.missed.insert(key.into()) {
+      return None;
+    }
+
+    self.missed.remove(key);
+    Some(self.insert(path))
+  }
+
+  /// Caches the names of the canonical directory `path`, evicting the oldest path if full.
+  pub(crate) fn insert(&mut self, path: &Path) -> Names {
+    let key = path.as_os_str().as_encoded_bytes();
+
+    if let Some(names) = self.directories.get(key) {
+      return names.clone();
+    }
+
+    if self.order.len() >= CAPACITY {
+      if let Some(oldest) = self.order.pop_front() {
+        self.directories.remove(&oldest);
+      }
+    }
+
+    let key: Arc<[u8]> = key.into();
+    let names: Names = components(path).map(OsStr::to_owned).collect();
+    self.order.push_back(key.clone());
+    self.directories.insert(key, names.clone());
+
+    names
+  }
+
+  /// Returns whether the directory with the encoded path `key` is cached.
+  pub(crate) fn contains(&self, key: &[u8]) -> bool {
+    self.directories.contains_key(key)
+  }
+}
+
+/// Returns the names of the components below the root of the canonical `path`.
+pub(crate) fn components(path: &Path) -> impl Iterator<Item = &OsStr> {
+  path.components().filter_map(|component| match component {
+    Component::Normal(name) => Some(name),
+    _ => None,
+  })
+}
+
+/// Splits the encoded `path` into its parent and file name at its last separator, without parsing
+/// its components, or returns `None` if it has none or that separator is repeated.
+pub(crate) fn split(path: &[u8]) -> Option<(&[u8], &[u8])> {
+  let index = path.iter().rposition(|&byte| byte == b'/')?;
+
+  match index {
+    0 => Some((&path[..1], &path[1..])),
+    _ if path[index - 1] == b'/' => None,
+    _ => Some((&path[..index], &path[index + 1..])),
+  }
+}
+
+/// Returns whether `name` names an entry, rather than being empty, `.` or `..`.
+pub(crate) fn is_name(name: &[u8]) -> bool {
+  !matches!(name, b"" | b"." | b"..") && !name.contains(&b'/')
+}
+
+/// Walks from `root` through the entries named `names`.
+///
+/// # Errors
+///
+/// This function will return an error if any of them does not exist, or any but the last is a
+/// file.
+pub(crate) fn descend<'a, 'n>(root: &'a Directory, names: impl IntoIterator<Item = &'n OsStr>) -> Result<BorrowedEntry<'a>> {
+  let mut parent = BorrowedEntry::Directory(root);
+
+  for name in names {
+    let BorrowedEntry::Directory(directory) = parent else {
+      return Err(Error::NotDirectory(parent.name().into()));
+    };
+
+    let Some(next) = directory.entries.get(name) else {
+      return Err(Error::NotExist(name.into()));
+    };
+
+    parent = BorrowedEntry::from(next);
+  }
+
+  Ok(parent)
+}
+
+/// Walks from `root` through the entries named `names`, borrowing them mutably.
+///
+/// # Errors
+///
+/// This function will return an error if any of them does not exist, or any but the last is a
+/// file.
+pub(crate) fn descend_mut<'a, 'n>(root: &'a mut Directory, names: impl IntoIterator<Item = &'n OsStr>) -> Result<MutBorrowedEntry<'a>> {
+  let mut parent = MutBorrowedEntry::Directory(root);
+
+  for name in names {
+    let MutBorrowedEntry::Directory(directory) = parent else {
+      return Err(Error::NotDirectory(parent.name().into()));
+    };
+
+    let Some(next) = directory.entries.get_mut(name) else {
+      return Err(Error::NotExist(name.into()));
+    };
+
+    parent = MutBorrowedEntry::from(next);
+  }
+
+  Ok(parent)
+}
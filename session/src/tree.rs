@@ -0,0 +1,324 @@
+use std::{collections::btree_map::Values, ffi::OsString, fmt::Write};
+
+use fs::{entry::Borrowed as BorrowedEntry, Entry};
+
+use crate::usage::Usage;
+
+/// Options for [`Session::tree`].
+///
+/// By default, the whole tree is included except entries whose name starts with a `.`.
+///
+/// [`Session::tree`]: crate::Session::tree
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TreeOptions {
+  max_depth: Option<usize>,
+
+  directories_only: bool,
+
+  all: bool,
+}
+
+impl TreeOptions {
+  /// Creates the default options.
+  #[must_use]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Only includes entries at most `depth` levels below the root.
+  #[must_use]
+  pub fn max_depth(mut self, depth: usize) -> Self {
+    self.max_depth = Some(depth);
+    self
+  }
+
+  /// Only includes directories.
+  #[must_use]
+  pub fn directories_only(mut self, directories_only: bool) -> Self {
+    self.directories_only = directories_only;
+    self
+  }
+
+  /// Includes entries whose name starts with a `.`.
+  #[must_use]
+  pub fn all(mut self, all: bool) -> Self {
+    self.all = all;
+    self
+  }
+}
+
+/// A tree built by [`Session::tree`].
+///
+/// [`Session::tree`]: crate::Session::tree
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tree {
+  pub root: TreeNode,
+
+  /// The number of directories included below the root.
+  pub directories: u64,
+
+  /// The number of files included below the root.
+  pub files: u64,
+
+  /// Whether only directories were included, in which case summaries don't count files.
+  directories_only: bool,
+}
+
+impl Tree {
+  pub(crate) fn new(root: TreeNode, options: &TreeOptions) -> Self {
+    let mut tree = Self {
+      root,
+      directories: 0,
+      files: 0,
+      directories_only: options.directories_only,
+    };
+
+    let mut stack: Vec<_> = tree.root.children.iter().collect();
+
+    while let Some(node) = stack.pop() {
+      if node.is_directory {
+        tree.directories += 1;
+      } else {
+        tree.files += 1;
+      }
+
+      stack.extend(&node.children);
+    }
+
+    tree
+  }
+
+  /// Renders the tree with box-drawing connectors, followed by a summary like
+  /// `2 directories, 3 files`. With `sizes`, each line starts with the entry's size in brackets.
+  #[must_use]
+  pub fn render(&self, sizes: bool) -> String {
+    let mut rendered = String::new();
+    let line = |rendered: &mut String, prefix: &str, node: &TreeNode| {
+      rendered.push_str(prefix);
+      if sizes {
+        let _ = write!(rendered, "[{:>10}]  ", node.size);
+      }
+      rendered.push_str(&node.name);
+      rendered.push('\n');
+    };
+
+    line(&mut rendered, "", &self.root);
+
+    // iterators over the children left to render, with the prefix of their lines
+    let mut stack = vec![(self.root.children.iter(), String::new())];
+
+    while let Some((children, indent)) = stack.last_mut() {
+      let Some(node) = children.next() else {
+        stack.pop();
+        continue;
+      };
+
+      let last = children.len() == 0;
+      let indent = indent.clone();
+
+      line(&mut rendered, &format!("{indent}{}", if last { "└── " } else { "├── " }), node);
+
+      if !node.children.is_empty() {
+        stack.push((node.children.iter(), format!("{indent}{}", if last { "    " } else { "│   " })));
+      }
+    }
+
+    let plural = |count: u64, singular: &str, plural: &str| format!("{count} {}", if count == 1 { singular } else { plural });
+
+    let _ = write!(rendered, "\n{}", plural(self.directories, "directory", "directories"));
+    if !self.directories_only {
+      let _ = write!(rendered, ", {}", plural(self.files, "file", "files"));
+    }
+    rendered.push('\n');
+
+    rendered
+  }
+
+  /// Returns the tree in the JSON form of `tree -J` for serializing: an array of the root, whose
+  /// directories have a `contents` array, followed by a report of the counts. With `sizes`,
+  /// entries have a `size`.
+  #[cfg(feature = "serde")]
+  #[must_use]
+  pub fn to_json(&self, sizes: bool) -> Vec<JsonEntry<'_>> {
+    let report = JsonEntry::Report {
+      directories: self.directories,
+      files: (!self.directories_only).then_some(self.files),
+    };
+
+    vec![self.root.to_json(sizes), report]
+  }
+}
+
+/// An entry of a [`Tree`], with its included children sorted by name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TreeNode {
+  /// The name of the entry, or the path the tree was built from for the root.
+  pub name: String,
+
+  pub is_directory: bool,
+
+  /// The length of a file, or the total length of all files under a directory, including those
+  /// that aren't included in the tree.
+  pub size: u64,
+
+  pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+  /// Builds the node of `entry` and its included descendants.
+  pub(crate) fn build(name: String, entry: BorrowedEntry, options: &TreeOptions) -> Self {
+    let (root, entries) = Self::start(name, entry, 0, options);
+    let Some(entries) = entries else {
+      return root;
+    };
+
+    // directories whose children are being built, with their entries left to visit
+    let mut stack = vec![(root, entries)];
+
+    loop {
+      let depth = stack.len();
+      let Some((node, entries)) = stack.last_mut() else {
+        unreachable!("the root is returned when it is complete");
+      };
+
+      let Some(child) = entries.next() else {
+        let (node, _) = stack.pop().expect("the stack is not empty");
+
+        match stack.last_mut() {
+          Some((parent, _)) => parent.push(node),
+          None => return node,
+        }
+
+        continue;
+      };
+
+      let name = child.name().to_string_lossy().into_owned();
+      let included = (options.all || !name.starts_with('.')) && (!options.directories_only || child.is_directory());
+
+      if !included {
+        node.size += Usage::of(child.into()).bytes;
+        continue;
+      }
+
+      match Self::start(name, child.into(), depth, options) {
+        (child, Some(entries)) => stack.push((child, entries)),
+        (child, None) => node.push(child),
+      }
+    }
+  }
+
+  /// Starts the node of `entry`, `depth` levels below the root, returning it with the entries
+  /// whose nodes are still to be added, or `None` if it is complete because it is a file or a
+  /// directory at the maximum depth.
+  fn start<'a>(name: String, entry: BorrowedEntry<'a>, depth: usize, options: &TreeOptions) -> (Self, Option<Values<'a, OsString, Entry>>) {
+    let BorrowedEntry::Directory(directory) = entry else {
+      let node = Self {
+        name,
+        is_directory: false,
+        size: entry.into_metadata().len,
+        children: Vec::new(),
+      };
+
+      return (node, None);
+    };
+
+    let mut node = Self {
+      name,
+      is_directory: true,
+      size: 0,
+      children: Vec::new(),
+    };
+
+    if options.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+      node.size = Usage::of(entry).bytes;
+      return (node, None);
+    }
+
+    (node, Some(directory.entries.values()))
+  }
+
+  /// Adds the complete node `child`, counting its size.
+  fn push(&mut self, child: Self) {
+    self.size += child.size;
+    self.children.push(child);
+  }
+
+  /// Returns the node without its children in the JSON form of `tree -J`.
+  #[cfg(feature = "serde")]
+  fn json_entry(&self, sizes: bool) -> JsonEntry<'_> {
+    let name = &self.name;
+    let size = sizes.then_some(self.size);
+
+    if self.is_directory {
+      JsonEntry::Directory {
+        name,
+        size,
+        contents: Vec::new(),
+      }
+    } else {
+      JsonEntry::File { name, size }
+    }
+  }
+
+  /// Returns the node and its descendants in the JSON form of `tree -J`.
+  #[cfg(feature = "serde")]
+  fn to_json(&self, sizes: bool) -> JsonEntry<'_> {
+    if !self.is_directory {
+      return self.json_entry(sizes);
+    }
+
+    // directories whose contents are being converted, with their children left to visit
+    let mut stack = vec![(self.json_entry(sizes), self.children.iter())];
+
+    loop {
+      let Some((JsonEntry::Directory { contents, .. }, children)) = stack.last_mut() else {
+        unreachable!("only directories are pushed, and the root is returned when it is complete");
+      };
+
+      match children.next() {
+        Some(child) if child.is_directory => stack.push((child.json_entry(sizes), child.children.iter())),
+        Some(child) => contents.push(child.json_entry(sizes)),
+        None => {
+          let (directory, _) = stack.pop().expect("the stack is not empty");
+
+          match stack.last_mut() {
+            Some((JsonEntry::Directory { contents, .. }, _)) => contents.push(directory),
+            _ => return directory,
+          }
+        }
+      }
+    }
+  }
+}
+
+/// An entry of the JSON form of a [`Tree`] returned by [`Tree::to_json`], like that of `tree -J`.
+#[cfg(feature = "serde")]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JsonEntry<'a> {
+  Directory {
+    name: &'a str,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+
+    contents: Vec<JsonEntry<'a>>,
+  },
+  File {
+    name: &'a str,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+  },
+
+  /// The counts of the tree, without files if only directories were included.
+  Report {
+    directories: u64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<u64>,
+  },
+}
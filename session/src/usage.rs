@@ -0,0 +1,105 @@
+use std::ops::{AddAssign, SubAssign};
+
+use fs::entry::Borrowed as BorrowedEntry;
+
+/// A summary of the space used by an entry and its descendants.
+///
+/// See [`Session::disk_usage`].
+///
+/// [`Session::disk_usage`]: crate::Session::disk_usage
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Usage {
+  /// The total length in bytes of all files.
+  pub bytes: u64,
+
+  /// The total number of bytes allocated for all files, which excludes holes in sparse files.
+  pub allocated: u64,
+
+  /// The number of files.
+  pub files: u64,
+
+  /// The number of directories, including the root of the summary if it is one.
+  pub directories: u64,
+}
+
+impl Usage {
+  /// Computes the usage of `entry` and all of its descendants.
+  #[must_use]
+  pub fn of(entry: BorrowedEntry) -> Self {
+    let mut usage = Self::default();
+    let mut stack = vec![entry];
+
+    while let Some(entry) = stack.pop() {
+      match entry {
+        BorrowedEntry::File(file) => {
+          usage.bytes += file.metadata.len;
+          usage.allocated += file.metadata.allocated;
+          usage.files += 1;
+        }
+
+        BorrowedEntry::Directory(directory) => {
+          usage.directories += 1;
+          stack.extend(directory.entries.values().map(BorrowedEntry::from));
+        }
+      }
+    }
+
+    usage
+  }
+
+  /// Returns the total number of entries, both files and directories.
+  #[must_use]
+  pub fn entries(&self) -> u64 {
+    self.files + self.directories
+  }
+}
+
+/// A summary of how much memory file contents take up.
+///
+/// See [`Session::storage_stats`].
+///
+/// [`Session::storage_stats`]: crate::Session::storage_stats
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StorageStats {
+  /// The total length in bytes of all files.
+  pub logical_bytes: u64,
+
+  /// The number of bytes actually stored for all files.
+  pub stored_bytes: u64,
+
+  /// The number of distinct blocks stored, if file contents are deduplicated.
+  pub blobs: Option<u64>,
+}
+
+impl StorageStats {
+  /// Returns the ratio of logical bytes to stored bytes, which accounts for both deduplication and
+  /// compression.
+  #[must_use]
+  pub fn dedup_ratio(&self) -> f64 {
+    if self.stored_bytes == 0 {
+      1.0
+    } else {
+      self.logical_bytes as f64 / self.stored_bytes as f64
+    }
+  }
+}
+
+impl AddAssign for Usage {
+  fn add_assign(&mut self, rhs: Self) {
+    self.bytes += rhs.bytes;
+    self.allocated += rhs.allocated;
+    self.files += rhs.files;
+    self.directories += rhs.directories;
+  }
+}
+
+impl SubAssign for Usage {
+  fn sub_assign(&mut self, rhs: Self) {
+    self.bytes -= rhs.bytes;
+    self.allocated -= rhs.allocated;
+    self.files -= rhs.files;
+    self.directories -= rhs.directories;
+  }
+}
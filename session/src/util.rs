@@ -20,18 +20,55 @@ pub fn clean_path<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
     return Err(Error::NotAbsolute(path.to_owned()));
   }
 
-  let mut components = Vec::new();
+  join_clean(PathBuf::with_capacity(path.as_os_str().len()), path)
+}
 
+/// Appends the components of `path` to the already clean `base` in a single pass, removing `.`
+/// and applying `..` lexically. An absolute `path` replaces `base`, and like POSIX, `..` at the
+/// root stays at the root.
+///
+/// # Errors
+///
+/// This function will return an error if `path` contains any unsupported [`Component`]s.
+pub(crate) fn join_clean(mut base: PathBuf, path: &Path) -> Result<PathBuf> {
   for component in path.components() {
-    match &component {
-      Component::RootDir | Component::Normal(_) => components.push(component),
+    match component {
+      Component::RootDir => {
+        base.clear();
+        base.push(component);
+      }
+      Component::Normal(name) => base.push(name),
       Component::ParentDir => {
-        components.pop().ok_or_else(|| Error::NoParent(path.to_owned()))?;
+        base.pop();
       }
       Component::CurDir => (),
       Component::Prefix(_) => return Err(Error::UnsupportedComponent(format!("{component:?}"))),
     }
   }
 
-  Ok(components.into_iter().collect())
+  Ok(base)
+}
+
+/// Returns the relative path from the directory `from` to `to`, e.g. `../c` from `/a/b` to `/a/c`,
+/// or `.` if they are the same. Both paths are cleaned first.
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - either path is not absolute.
+/// - either path contains any unsupported [`Component`]s.
+pub fn relative_path<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> Result<PathBuf> {
+  let from = clean_path(from)?;
+  let to = clean_path(to)?;
+
+  let common = from.components().zip(to.components()).take_while(|(a, b)| a == b).count();
+
+  let mut relative: PathBuf = from.components().skip(common).map(|_| Component::ParentDir).collect();
+  relative.extend(to.components().skip(common));
+
+  if relative.as_os_str().is_empty() {
+    relative.push(Component::CurDir);
+  }
+
+  Ok(relative)
 }
@@ -1,11 +1,30 @@
+use std::{
+  cmp::Ordering,
+  collections::{btree_map, BTreeMap},
+  ffi::{OsStr, OsString},
+  fmt, mem,
+  ops::ControlFlow,
+  path::{Path, PathBuf},
+  rc::Rc,
+};
+
+use fs::{
+  entry::{Borrowed as BorrowedEntry, MutBorrowed as MutBorrowedEntry},
+  metadata::Metadata,
+  Entry,
+};
+
 /// A struct describing what to do on an entry during walking.
 ///
 /// See [`Session::walk`].
 ///
 /// [`Session::walk`]: crate::Session::walk
 pub enum Walk {
+  /// Don't enter the directory. This has no effect on files.
   Skip,
   Continue,
+  /// Stop walking entirely.
+  Stop,
 }
 
 impl From<()> for Walk {
@@ -13,3 +32,540 @@ impl From<()> for Walk {
     Self::Continue
   }
 }
+
+/// A function comparing two entries in the same directory.
+type Compare = dyn FnMut(&BorrowedEntry, &BorrowedEntry) -> Ordering;
+
+/// A predicate deciding whether an entry is walked.
+type Filter = dyn FnMut(&BorrowedEntry) -> bool;
+
+/// Options for [`Session::walk_iter`] and [`Session::walk_mut`].
+///
+/// By default, every descendant of the root is yielded, including the root itself, with each
+/// directory yielded before its contents and entries within a directory yielded in name order.
+///
+/// [`Session::walk_iter`]: crate::Session::walk_iter
+/// [`Session::walk_mut`]: crate::Session::walk_mut
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WalkOptions {
+  min_depth: usize,
+
+  max_depth: usize,
+
+  contents_first: bool,
+
+  // comparisons and filters can't be sent to other processes, so only the depths and order are
+  // serialized
+  #[cfg_attr(feature = "serde", serde(skip))]
+  sort_by: Option<Box<Compare>>,
+
+  #[cfg_attr(feature = "serde", serde(skip))]
+  filter_entry: Option<Box<Filter>>,
+}
+
+impl WalkOptions {
+  /// Creates options that walk every descendant of the root.
+  #[must_use]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Only yields entries at least `depth` levels below the root. The root is at depth 0.
+  #[must_use]
+  pub fn min_depth(mut self, depth: usize) -> Self {
+    self.min_depth = depth;
+    self
+  }
+
+  /// Doesn't enter directories `depth` levels below the root, so no entries deeper than `depth`
+  /// are yielded.
+  #[must_use]
+  pub fn max_depth(mut self, depth: usize) -> Self {
+    self.max_depth = depth;
+    self
+  }
+
+  /// Yields the contents of each directory before the directory itself, i.e. walks in post-order
+  /// rather than pre-order.
+  #[must_use]
+  pub fn contents_first(mut self, contents_first: bool) -> Self {
+    self.contents_first = contents_first;
+    self
+  }
+
+  /// Yields the entries of each directory in the order given by `compare` rather than by name.
+  ///
+  /// Sorting needs to collect the entries of each directory as it is entered.
+  #[must_use]
+  pub fn sort_by<F>(mut self, compare: F) -> Self
+  where
+    F: FnMut(&BorrowedEntry, &BorrowedEntry) -> Ordering + 'static,
+  {
+    self.sort_by = Some(Box::new(compare));
+    self
+  }
+
+  /// Only walks entries for which `predicate` returns `true`. Directories that are filtered out
+  /// are neither yielded nor entered.
+  #[must_use]
+  pub fn filter_entry<F>(mut self, predicate: F) -> Self
+  where
+    F: FnMut(&BorrowedEntry) -> bool + 'static,
+  {
+    self.filter_entry = Some(Box::new(predicate));
+    self
+  }
+
+  /// Returns whether `entry` passes the filter.
+  fn filter(&mut self, entry: &BorrowedEntry) -> bool {
+    self.filter_entry.as_mut().is_none_or(|predicate| predicate(entry))
+  }
+}
+
+impl Default for WalkOptions {
+  fn default() -> Self {
+    Self {
+      min_depth: 0,
+      max_depth: usize::MAX,
+      contents_first: false,
+      sort_by: None,
+      filter_entry: None,
+    }
+  }
+}
+
+impl fmt::Debug for WalkOptions {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("WalkOptions")
+      .field("min_depth", &self.min_depth)
+      .field("max_depth", &self.max_depth)
+      .field("contents_first", &self.contents_first)
+      .field("sorted", &self.sort_by.is_some())
+      .field("filtered", &self.filter_entry.is_some())
+      .finish()
+  }
+}
+
+/// An entry yielded by [`WalkIter`].
+///
+/// Entries share the path of their parent directory, so a path is only built when
+/// [`Self::path`] is called.
+#[derive(Clone)]
+pub struct DirEntry<'a> {
+  /// The path of the parent directory, or of the entry itself if it is the root of the walk.
+  base: Rc<Path>,
+
+  entry: BorrowedEntry<'a>,
+
+  depth: usize,
+}
+
+impl<'a> DirEntry<'a> {
+  /// Returns the absolute path of this entry.
+  #[must_use]
+  pub fn path(&self) -> PathBuf {
+    if self.depth == 0 {
+      self.base.to_path_buf()
+    } else {
+      self.base.join(self.name())
+    }
+  }
+
+  /// Returns the name of this entry.
+  #[must_use]
+  pub fn name(&self) -> &'a OsStr {
+    &self.entry.clone().into_metadata().name
+  }
+
+  /// Returns the number of levels this entry is below the root of the walk.
+  #[must_use]
+  pub fn depth(&self) -> usize {
+    self.depth
+  }
+
+  #[must_use]
+  pub fn entry(&self) -> &BorrowedEntry<'a> {
+    &self.entry
+  }
+
+  #[must_use]
+  pub fn into_entry(self) -> BorrowedEntry<'a> {
+    self.entry
+  }
+
+  #[must_use]
+  pub fn metadata(&self) -> &'a Metadata {
+    self.entry.clone().into_metadata()
+  }
+
+  /// Returns whether this entry is a directory.
+  #[must_use]
+  pub fn is_directory(&self) -> bool {
+    self.entry.is_directory()
+  }
+
+  /// Returns whether this entry is a file.
+  #[must_use]
+  pub fn is_file(&self) -> bool {
+    self.entry.is_file()
+  }
+}
+
+impl fmt::Debug for DirEntry<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("DirEntry")
+      .field("path", &self.path())
+      .field("is_directory", &self.is_directory())
+      .field("depth", &self.depth)
+      .finish()
+  }
+}
+
+/// The remaining entries of a directory being walked.
+enum Entries<'a> {
+  Ordered(btree_map::Values<'a, OsString, Entry>),
+  Sorted(std::vec::IntoIter<BorrowedEntry<'a>>),
+}
+
+impl<'a> Iterator for Entries<'a> {
+  type Item = BorrowedEntry<'a>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    match self {
+      Self::Ordered(entries) => entries.next().map(BorrowedEntry::from),
+      Self::Sorted(entries) => entries.next(),
+    }
+  }
+}
+
+/// A directory that has been entered.
+struct Frame<'a> {
+  path: Rc<Path>,
+
+  entries: Entries<'a>,
+
+  /// The directory itself, if it is yielded after its contents.
+  directory: Option<DirEntry<'a>>,
+}
+
+/// A depth-first iterator over an entry and its descendants.
+///
+/// See [`Session::walk_iter`].
+///
+/// [`Session::walk_iter`]: crate::Session::walk_iter
+pub struct WalkIter<'a> {
+  options: WalkOptions,
+
+  root: Option<DirEntry<'a>>,
+
+  stack: Vec<Frame<'a>>,
+
+  /// Whether the most recently yielded entry is a directory whose contents can't be skipped,
+  /// because it wasn't entered or its contents were already yielded.
+  nothing_to_skip: bool,
+}
+
+impl<'a> WalkIter<'a> {
+  pub(crate) fn new(root: PathBuf, entry: BorrowedEntry<'a>, options: WalkOptions) -> Self {
+    Self {
+      options,
+      root: Some(DirEntry {
+        base: root.into(),
+        entry,
+        depth: 0,
+      }),
+      stack: Vec::new(),
+      nothing_to_skip: false,
+    }
+  }
+
+  /// Skips the remaining contents of the most recently yielded directory, or of the parent of the
+  /// most recently yielded file.
+  ///
+  /// When walking contents first, the skipped directory itself is still yielded.
+  pub fn skip_current_dir(&mut self) {
+    if self.nothing_to_skip {
+      return;
+    }
+
+    if let Some(frame) = self.stack.last_mut() {
+      frame.entries = Entries::Sorted(Vec::new().into_iter());
+    }
+  }
+
+  /// Enters `entry` if it is a directory within the maximum depth, returning it if it should be
+  /// yielded now.
+  fn visit(&mut self, entry: DirEntry<'a>) -> Option<DirEntry<'a>> {
+    if !self.options.filter(&entry.entry) {
+      return None;
+    }
+
+    self.nothing_to_skip = false;
+
+    if let BorrowedEntry::Directory(directory) = entry.entry {
+      if entry.depth < self.options.max_depth {
+        let entries = match &mut self.options.sort_by {
+          Some(compare) => {
+            let mut entries: Vec<_> = directory.entries.values().map(BorrowedEntry::from).collect();
+            entries.sort_by(|a, b| compare(a, b));
+
+            Entries::Sorted(entries.into_iter())
+          }
+          None => Entries::Ordered(directory.entries.values()),
+        };
+
+        let contents_first = self.options.contents_first;
+
+        self.stack.push(Frame {
+          path: entry.path().into(),
+          entries,
+          directory: contents_first.then(|| entry.clone()),
+        });
+
+        if contents_first {
+          return None;
+        }
+      } else {
+        self.nothing_to_skip = true;
+      }
+    }
+
+    (entry.depth >= self.options.min_depth).then_some(entry)
+  }
+}
+
+impl<'a> Iterator for WalkIter<'a> {
+  type Item = DirEntry<'a>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if let Some(root) = self.root.take() {
+      if let Some(root) = self.visit(root) {
+        return Some(root);
+      }
+    }
+
+    loop {
+      let depth = self.stack.len();
+      let frame = self.stack.last_mut()?;
+
+      if let Some(entry) = frame.entries.next() {
+        let entry = DirEntry {
+          base: frame.path.clone(),
+          entry,
+          depth,
+        };
+
+        if let Some(entry) = self.visit(entry) {
+          return Some(entry);
+        }
+      } else if let Some(directory) = self.stack.pop().and_then(|frame| frame.directory) {
+        if directory.depth >= self.options.min_depth {
+          self.nothing_to_skip = true;
+          return Some(directory);
+        }
+      }
+    }
+  }
+}
+
+/// A directory being walked by [`walk_mut`]. Its entries are detached from it while they are
+/// visited, so that each can be borrowed mutably without borrowing the directory.
+struct MutFrame {
+  /// The directory and its name, or `None` for the root of the walk, which stays in place.
+  directory: Option<(OsString, Entry)>,
+
+  /// The entries left to visit, in order.
+  pending: std::vec::IntoIter<(OsString, Entry)>,
+
+  /// The entries already visited, to be attached to the directory again.
+  visited: BTreeMap<OsString, Entry>,
+}
+
+/// What to do after an entry was visited before its contents.
+enum Begin {
+  /// Walk the detached entries of the directory.
+  Enter(Vec<(OsString, Entry)>),
+
+  /// The entry is done.
+  Done,
+
+  Stop,
+}
+
+/// Calls `f` on `root` at `path` and its descendants, as configured by `options`. `path` is
+/// extended in place while descending.
+///
+/// Directories are walked with an explicit stack rather than recursion, so deep trees can't
+/// overflow the call stack. Changes that `f` makes to the name of an entry below the root are
+/// reverted, so that entries stay keyed by their name, and the metadata of files is recomputed
+/// from their content after each call.
+pub(crate) fn walk_mut<F, S>(path: &mut PathBuf, root: &mut MutBorrowedEntry, options: &mut WalkOptions, f: &mut F)
+where
+  S: Into<Walk>,
+  F: FnMut(&Path, &mut MutBorrowedEntry) -> S,
+{
+  let mut stack = match begin_mut(path, root, 0, options, f) {
+    Begin::Enter(entries) => vec![MutFrame {
+      directory: None,
+      pending: entries.into_iter(),
+      visited: BTreeMap::new(),
+    }],
+    Begin::Done | Begin::Stop => return,
+  };
+
+  loop {
+    let depth = stack.len();
+    let Some(frame) = stack.last_mut() else {
+      return;
+    };
+
+    let Some((name, mut entry)) = frame.pending.next() else {
+      let MutFrame { directory, visited, .. } = stack.pop().expect("the stack is not empty");
+
+      let Some((name, mut entry)) = directory else {
+        attach(root, visited);
+        let _ = end_mut(path, root, 0, options, f);
+        return;
+      };
+
+      let mut borrowed = MutBorrowedEntry::from(&mut entry);
+      attach(&mut borrowed, visited);
+      let flow = end_mut(path, &mut borrowed, depth - 1, options, f);
+
+      rename(&mut entry, &name);
+      path.pop();
+      stack.last_mut().expect("the root is below").visited.insert(name, entry);
+
+      if flow.is_break() {
+        break;
+      }
+
+      continue;
+    };
+
+    path.push(&name);
+    let begin = begin_mut(path, &mut MutBorrowedEntry::from(&mut entry), depth, options, f);
+    rename(&mut entry, &name);
+
+    match begin {
+      Begin::Enter(entries) => stack.push(MutFrame {
+        directory: Some((name, entry)),
+        pending: entries.into_iter(),
+        visited: BTreeMap::new(),
+      }),
+      Begin::Done => {
+        path.pop();
+        frame.visited.insert(name, entry);
+      }
+      Begin::Stop => {
+        path.pop();
+        frame.visited.insert(name, entry);
+        break;
+      }
+    }
+  }
+
+  // the walk stopped early, so every directory still being walked gets its entries back
+  while let Some(MutFrame {
+    directory,
+    pending,
+    mut visited,
+  }) = stack.pop()
+  {
+    visited.extend(pending);
+
+    let Some((name, mut entry)) = directory else {
+      attach(root, visited);
+      return;
+    };
+
+    attach(&mut MutBorrowedEntry::from(&mut entry), visited);
+    path.pop();
+    stack.last_mut().expect("the root is below").visited.insert(name, entry);
+  }
+}
+
+/// Visits `entry` before its contents, returning its detached entries if it should be entered.
+fn begin_mut<F, S>(path: &Path, entry: &mut MutBorrowedEntry, depth: usize, options: &mut WalkOptions, f: &mut F) -> Begin
+where
+  S: Into<Walk>,
+  F: FnMut(&Path, &mut MutBorrowedEntry) -> S,
+{
+  if !options.filter(&entry.as_borrowed()) {
+    return Begin::Done;
+  }
+
+  let visit = depth >= options.min_depth;
+  let mut enter = depth < options.max_depth;
+
+  if visit && !options.contents_first {
+    match call_mut(path, entry, f) {
+      Walk::Skip => enter = false,
+      Walk::Continue => (),
+      Walk::Stop => return Begin::Stop,
+    }
+  }
+
+  if enter {
+    if let MutBorrowedEntry::Directory(directory) = entry {
+      let mut entries: Vec<_> = mem::take(&mut directory.entries).into_iter().collect();
+
+      if let Some(compare) = &mut options.sort_by {
+        entries.sort_by(|(_, a), (_, b)| compare(&a.into(), &b.into()));
+      }
+
+      return Begin::Enter(entries);
+    }
+  }
+
+  match end_mut(path, entry, depth, options, f) {
+    ControlFlow::Continue(()) => Begin::Done,
+    ControlFlow::Break(()) => Begin::Stop,
+  }
+}
+
+/// Visits `entry` after its contents, if the walk visits directories last.
+fn end_mut<F, S>(path: &Path, entry: &mut MutBorrowedEntry, depth: usize, options: &WalkOptions, f: &mut F) -> ControlFlow<()>
+where
+  S: Into<Walk>,
+  F: FnMut(&Path, &mut MutBorrowedEntry) -> S,
+{
+  if depth >= options.min_depth && options.contents_first {
+    if let Walk::Stop = call_mut(path, entry, f) {
+      return ControlFlow::Break(());
+    }
+  }
+
+  ControlFlow::Continue(())
+}
+
+/// Calls `f` on `entry`, then brings the metadata of a file up to date with any change `f` made
+/// to its content.
+fn call_mut<F, S>(path: &Path, entry: &mut MutBorrowedEntry, f: &mut F) -> Walk
+where
+  S: Into<Walk>,
+  F: FnMut(&Path, &mut MutBorrowedEntry) -> S,
+{
+  let walk = f(path, entry).into();
+
+  if let MutBorrowedEntry::File(file) = entry {
+    file.update_metadata();
+  }
+
+  walk
+}
+
+/// Attaches the `entries` detached by [`begin_mut`] to the directory they came from. Entries that
+/// `f` added to the directory afterwards are kept.
+fn attach(directory: &mut MutBorrowedEntry, entries: BTreeMap<OsString, Entry>) {
+  if let MutBorrowedEntry::Directory(directory) = directory {
+    directory.entries.extend(entries);
+  }
+}
+
+/// Reverts a change to the name of an entry keyed by `name`.
+fn rename(entry: &mut Entry, name: &OsStr) {
+  if entry.name() != name {
+    entry.rename(name.to_owned());
+  }
+}
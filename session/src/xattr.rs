@@ -0,0 +1,43 @@
+use crate::{Error, Result};
+
+/// The namespaces an extended attribute name may start with.
+pub const NAMESPACES: [&str; 4] = ["user.", "trusted.", "security.", "system."];
+
+/// The maximum length in bytes of an extended attribute name, including its namespace.
+pub const MAX_NAME_LEN: usize = 255;
+
+/// The maximum length in bytes of an extended attribute value.
+pub const MAX_VALUE_LEN: usize = 64 * 1024;
+
+/// Checks that `name` is a valid extended attribute name.
+///
+/// # Errors
+///
+/// This function will return an error if `name`:
+/// - does not start with one of the [`NAMESPACES`].
+/// - has nothing after its namespace.
+/// - is longer than [`MAX_NAME_LEN`].
+pub fn validate_name(name: &str) -> Result<()> {
+  let has_namespace = NAMESPACES
+    .iter()
+    .any(|namespace| name.strip_prefix(namespace).is_some_and(|rest| !rest.is_empty()));
+
+  if !has_namespace || name.len() > MAX_NAME_LEN {
+    return Err(Error::InvalidXattrName(name.to_owned()));
+  }
+
+  Ok(())
+}
+
+/// Checks that `value` is a valid value for the extended attribute `name`.
+///
+/// # Errors
+///
+/// This function will return an error if `value` is longer than [`MAX_VALUE_LEN`].
+pub fn validate_value(name: &str, value: &[u8]) -> Result<()> {
+  if value.len() > MAX_VALUE_LEN {
+    return Err(Error::XattrTooLarge(name.to_owned()));
+  }
+
+  Ok(())
+}
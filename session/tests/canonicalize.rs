@@ -0,0 +1,56 @@
+use std::path::Path;
+
+use fs::Filesystem;
+use session::{util::relative_path, *};
+
+fn session() -> Session {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/a").unwrap();
+  session.create_directory("/a/b").unwrap();
+  session.create_file("/a/f").unwrap();
+
+  session
+}
+
+#[test]
+fn canonicalize() {
+  let mut session = session();
+  session.change_directory("/a/b").unwrap();
+
+  assert_eq!(session.canonicalize(".").unwrap(), Path::new("/a/b"));
+  assert_eq!(session.canonicalize("../f").unwrap(), Path::new("/a/f"));
+  assert_eq!(session.canonicalize("/a/./b/../../a//f").unwrap(), Path::new("/a/f"));
+  assert_eq!(session.canonicalize("/..").unwrap(), Path::new("/"));
+  assert_eq!(session.canonicalize("../../../..").unwrap(), Path::new("/"));
+}
+
+#[test]
+fn canonicalize_checks_components() {
+  let session = session();
+
+  // lexically `/a/b`, but `/a/x` does not exist
+  assert!(matches!(session.canonicalize("/a/x/../b"), Err(Error::NotExist(_))));
+  assert!(matches!(session.canonicalize("/a/f/.."), Err(Error::NotDirectory(_))));
+  assert!(matches!(session.canonicalize("/a/f/g"), Err(Error::NotDirectory(_))));
+  assert_eq!(session.resolve("/a/x/../b").unwrap().0, Path::new("/a/b"));
+}
+
+#[test]
+fn canonicalize_removed_current_directory() {
+  let mut session = session();
+  session.change_directory("/a/b").unwrap();
+  session.remove("/a/b").unwrap();
+
+  assert!(matches!(session.canonicalize("."), Err(Error::NotExist(_))));
+  assert_eq!(session.canonicalize("/a").unwrap(), Path::new("/a"));
+}
+
+#[test]
+fn relative_paths() {
+  assert_eq!(relative_path("/a/b", "/a/c/d").unwrap(), Path::new("../c/d"));
+  assert_eq!(relative_path("/a/b", "/a").unwrap(), Path::new(".."));
+  assert_eq!(relative_path("/", "/a/b").unwrap(), Path::new("a/b"));
+  assert_eq!(relative_path("/a/b", "/a/b/.").unwrap(), Path::new("."));
+  assert_eq!(relative_path("/x/y", "/").unwrap(), Path::new("../.."));
+  assert!(matches!(relative_path("a", "/b"), Err(Error::NotAbsolute(_))));
+}
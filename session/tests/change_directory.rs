@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use fs::Filesystem;
 use session::*;
 
@@ -28,3 +30,34 @@ fn change_directory_not_directory() {
 
   assert!(matches!(res, Err(Error::NotDirectory(_))));
 }
+
+#[test]
+fn change_directory_follows_move() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/a").unwrap();
+  session.create_directory("/a/b").unwrap();
+  session.change_directory("/a/b").unwrap();
+
+  session.move_entry("/a", "/c").unwrap();
+  assert_eq!(session.current_directory(), Path::new("/c/b"));
+
+  session.move_entry("/c/b", "/d").unwrap();
+  assert_eq!(session.current_directory(), Path::new("/d"));
+
+  session.create_file("f").unwrap();
+  assert!(session.resolve("/d/f").is_ok());
+}
+
+#[test]
+fn relative_paths() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/a").unwrap();
+  session.create_directory("/a/b").unwrap();
+  session.change_directory("/a/b").unwrap();
+
+  assert_eq!(session.resolve(".").unwrap().0, Path::new("/a/b"));
+  assert_eq!(session.resolve("..").unwrap().0, Path::new("/a"));
+  assert_eq!(session.resolve("./../b/.").unwrap().0, Path::new("/a/b"));
+  assert_eq!(session.resolve("../../a//b/").unwrap().0.as_os_str(), "/a/b");
+  assert_eq!(session.resolve("../../..").unwrap().0, Path::new("/"));
+}
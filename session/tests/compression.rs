@@ -0,0 +1,80 @@
+use fs::{content::BLOCK_SIZE, Compression, Filesystem};
+use session::*;
+
+fn compressed_session() -> Session {
+  let mut filesystem = Filesystem::new();
+  filesystem.compression = Compression::Lz4;
+
+  Session::new(filesystem)
+}
+
+fn log_lines(n: usize) -> String {
+  (0..n).map(|i| format!("{i:08} INFO request handled\n")).collect()
+}
+
+#[test]
+fn compression_round_trip() {
+  let content = log_lines(1000);
+
+  let mut session = compressed_session();
+  session.create_file("/a").unwrap();
+  session.write_file("/a", content.clone().into()).unwrap();
+
+  let stats = session.storage_stats();
+
+  assert_eq!(session.read_file("/a").unwrap(), content);
+  assert_eq!(stats.logical_bytes, content.len() as u64);
+  assert!(stats.stored_bytes < stats.logical_bytes / 2);
+}
+
+#[test]
+fn compression_read_file_at() {
+  let content = log_lines(1000);
+
+  let mut session = compressed_session();
+  session.create_file("/a").unwrap();
+
+  for chunk in content.as_bytes().chunks(100) {
+    session.write_file("/a", chunk.to_vec()).unwrap();
+  }
+
+  let offset = BLOCK_SIZE * 3 - 10;
+  let read = session.read_file_at("/a", offset as u64, 20).unwrap();
+  assert_eq!(read, &content.as_bytes()[offset..offset + 20]);
+
+  let read = session.read_file_at("/a", content.len() as u64 - 5, 100).unwrap();
+  assert_eq!(read, &content.as_bytes()[content.len() - 5..]);
+
+  let read = session.read_file_at("/a", content.len() as u64 + 5, 100).unwrap();
+  assert!(read.is_empty());
+}
+
+#[test]
+fn set_compression() {
+  let content = log_lines(1000);
+
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+  session.write_file("/a", content.clone().into()).unwrap();
+
+  let uncompressed = session.storage_stats().stored_bytes;
+  assert_eq!(uncompressed, content.len() as u64);
+
+  session.set_compression("/a", Compression::Lz4).unwrap();
+  assert!(session.storage_stats().stored_bytes < uncompressed);
+  assert_eq!(session.read_file("/a").unwrap(), content);
+
+  session.set_compression("/a", Compression::None).unwrap();
+  assert_eq!(session.storage_stats().stored_bytes, uncompressed);
+  assert_eq!(session.read_file("/a").unwrap(), content);
+}
+
+#[test]
+fn set_compression_not_file() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/a").unwrap();
+
+  let res = session.set_compression("/a", Compression::Lz4);
+
+  assert!(matches!(res, Err(Error::NotFile(_))));
+}
@@ -0,0 +1,59 @@
+use fs::{Entry, Filesystem, Limits};
+use session::*;
+
+#[test]
+fn copy_directory() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/a").unwrap();
+  session.create_file("/a/1").unwrap();
+  session.write_file("/a/1", "some text".into()).unwrap();
+  session.copy_entry("/a", "/b").unwrap();
+  session.write_file("/a/1", " more".into()).unwrap();
+
+  let entries: Vec<_> = session.list_directory("/b").unwrap().map(Entry::name).collect();
+  assert_eq!(entries, &["1"]);
+
+  assert_eq!(session.read_file("/b/1").unwrap(), "some text");
+  assert_eq!(session.read_file("/a/1").unwrap(), "some text more");
+  assert_eq!(session.filesystem_usage().bytes, 23);
+}
+
+#[test]
+fn copy_not_exist() {
+  let mut session = Session::new(Filesystem::new());
+
+  let res = session.copy_entry("/a", "/b");
+
+  assert!(matches!(res, Err(Error::NotExist(_))));
+}
+
+#[test]
+fn copy_no_space() {
+  let mut session = Session::new(Filesystem::with_limits(Limits {
+    max_bytes: Some(10),
+    ..Limits::unlimited()
+  }));
+  session.create_file("/a").unwrap();
+  session.write_file("/a", "123456".into()).unwrap();
+
+  let res = session.copy_entry("/a", "/b");
+
+  assert!(matches!(res, Err(Error::NoSpace(_))));
+}
+
+#[test]
+fn copy_replace_near_limit() {
+  let mut session = Session::new(Filesystem::with_limits(Limits {
+    max_bytes: Some(12),
+    ..Limits::unlimited()
+  }));
+  session.create_file("/a").unwrap();
+  session.write_file("/a", "123456".into()).unwrap();
+  session.create_file("/b").unwrap();
+  session.write_file("/b", "abcdef".into()).unwrap();
+
+  session.copy_entry("/a", "/b").unwrap();
+
+  assert_eq!(session.read_file("/b").unwrap(), "123456");
+  assert_eq!(session.filesystem_usage().bytes, 12);
+}
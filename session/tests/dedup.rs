@@ -0,0 +1,57 @@
+use fs::{content::BLOCK_SIZE, Filesystem};
+use session::*;
+
+#[test]
+fn dedup_identical_files() {
+  let content = "a".repeat(BLOCK_SIZE * 2 + 10);
+
+  let mut session = Session::new(Filesystem::deduplicated());
+  session.create_file("/a").unwrap();
+  session.create_file("/b").unwrap();
+  session.write_file("/a", content.clone().into()).unwrap();
+  session.write_file("/b", content.clone().into()).unwrap();
+
+  let stats = session.storage_stats();
+
+  assert_eq!(stats.logical_bytes, 2 * content.len() as u64);
+  assert_eq!(stats.stored_bytes, BLOCK_SIZE as u64 + 10);
+  assert_eq!(stats.blobs, Some(2));
+  assert_eq!(session.read_file("/b").unwrap(), content);
+}
+
+#[test]
+fn dedup_append() {
+  let mut session = Session::new(Filesystem::deduplicated());
+  session.create_file("/a").unwrap();
+  session.create_file("/b").unwrap();
+  session.write_file("/a", "abc".into()).unwrap();
+  session.write_file("/a", "def".into()).unwrap();
+  session.write_file("/b", "abcdef".into()).unwrap();
+
+  let stats = session.storage_stats();
+
+  assert_eq!(stats.stored_bytes, 6);
+  assert_eq!(session.read_file("/a").unwrap(), "abcdef");
+}
+
+#[test]
+fn dedup_copy_and_remove() {
+  let mut session = Session::new(Filesystem::deduplicated());
+  session.create_file("/a").unwrap();
+  session.write_file("/a", "some text".into()).unwrap();
+  session.copy_entry("/a", "/b").unwrap();
+
+  assert_eq!(session.storage_stats().stored_bytes, 9);
+  assert!((session.storage_stats().dedup_ratio() - 2.0).abs() < f64::EPSILON);
+
+  session.write_file("/b", " more".into()).unwrap();
+  assert_eq!(session.read_file("/a").unwrap(), "some text");
+  assert_eq!(session.storage_stats().stored_bytes, 23);
+
+  session.remove("/a").unwrap();
+  assert_eq!(session.storage_stats().stored_bytes, 14);
+
+  session.remove("/b").unwrap();
+  assert_eq!(session.storage_stats().stored_bytes, 0);
+  assert_eq!(session.storage_stats().blobs, Some(0));
+}
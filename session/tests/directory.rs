@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::Path;
 
 use fs::Filesystem;
 use session::*;
@@ -19,7 +19,7 @@ fn create_directory_parent_not_exist() {
 
   let err = session.create_directory("/a/b").err().unwrap();
 
-  assert!(matches!(err, Error::NotExist(path) if path == PathBuf::from("a")));
+  assert!(matches!(err, Error::NotExist(path) if path == Path::new("a")));
 }
 
 #[test]
@@ -0,0 +1,63 @@
+use fs::Filesystem;
+use session::{usage::Usage, *};
+
+#[test]
+fn disk_usage_directory() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/a").unwrap();
+  session.create_directory("/a/b").unwrap();
+  session.create_file("/a/1").unwrap();
+  session.create_file("/a/b/2").unwrap();
+  session.write_file("/a/1", "abc".into()).unwrap();
+  session.write_file("/a/b/2", "de".into()).unwrap();
+
+  let usage = session.disk_usage("/a").unwrap();
+
+  assert_eq!(
+    usage,
+    Usage {
+      bytes: 5,
+      allocated: 5,
+      files: 2,
+      directories: 2,
+    }
+  );
+}
+
+#[test]
+fn disk_usage_file() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+  session.write_file("/a", "abc".into()).unwrap();
+  session.write_file("/a", "def".into()).unwrap();
+
+  let (_, entry) = session.resolve("/a").unwrap();
+  assert_eq!(entry.metadata().len, 6);
+
+  let usage = session.disk_usage("/a").unwrap();
+  assert_eq!(usage.bytes, 6);
+  assert_eq!(usage.entries(), 1);
+}
+
+#[test]
+fn disk_usage_not_exist() {
+  let session = Session::new(Filesystem::new());
+
+  let res = session.disk_usage("/a");
+
+  assert!(matches!(res, Err(Error::NotExist(_))));
+}
+
+#[test]
+fn filesystem_usage() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/a").unwrap();
+  session.create_file("/b").unwrap();
+  session.write_file("/b", "abc".into()).unwrap();
+
+  let usage = session.filesystem_usage();
+
+  assert_eq!(usage.bytes, 3);
+  assert_eq!(usage.files, 1);
+  assert_eq!(usage.directories, 2);
+}
@@ -0,0 +1,24 @@
+use std::path::Path;
+
+use fs::Filesystem;
+use session::*;
+
+#[test]
+fn error_code_and_path() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+
+  let err = session.read_file("/b").unwrap_err();
+  assert_eq!(err.code(), "not_exist");
+  assert_eq!(err.path(), Some(Path::new("b")));
+
+  let err = session.create_file("/a").unwrap_err();
+  assert_eq!(err.code(), "exists");
+
+  let err = session.list_directory("/a").map(|_| ()).unwrap_err();
+  assert_eq!(err.code(), "not_directory");
+
+  let err = session.glob("[").unwrap_err();
+  assert_eq!(err.code(), "invalid_pattern");
+  assert_eq!(err.path(), None);
+}
@@ -0,0 +1,115 @@
+use fs::Filesystem;
+use session::{
+  fault::{Fault, Operation, Rule, Trigger},
+  *,
+};
+
+#[test]
+fn fault_nth_write() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/data").unwrap();
+  session.create_file("/data/a").unwrap();
+  session.create_file("/b").unwrap();
+  session
+    .faults_mut()
+    .add(Rule::new(Some(Operation::Write), "/data", Fault::Io).trigger(Trigger::Nth(3)));
+
+  session.write_file("/data/a", "1".into()).unwrap();
+  session.write_file("/b", "1".into()).unwrap();
+  session.write_file("/data/a", "2".into()).unwrap();
+
+  let res = session.write_file("/data/a", "3".into());
+  assert!(matches!(res, Err(Error::Io(_))));
+
+  session.write_file("/data/a", "4".into()).unwrap();
+  assert_eq!(session.read_file("/data/a").unwrap(), "124");
+}
+
+#[test]
+fn fault_move() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+  session.faults_mut().add(Rule::new(Some(Operation::Move), "/a", Fault::NoSpace));
+
+  let res = session.move_entry("/a", "/b");
+
+  assert!(matches!(res, Err(Error::NoSpace(_))));
+  assert!(session.resolve("/a").is_ok());
+}
+
+#[test]
+fn fault_short_write() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+  session
+    .faults_mut()
+    .add(Rule::new(Some(Operation::Write), "/a", Fault::ShortWrite(3)));
+
+  let written = session.write_file("/a", "abcdef".into()).unwrap();
+
+  assert_eq!(written, 3);
+  assert_eq!(session.read_file("/a").unwrap(), "abc");
+}
+
+#[test]
+fn fault_random_is_deterministic() {
+  fn failures(seed: u64) -> Vec<bool> {
+    let mut session = Session::new(Filesystem::new());
+    session
+      .faults_mut()
+      .add(Rule::new(Some(Operation::CreateFile), "/", Fault::Io).trigger(Trigger::Random { probability: 0.5, seed }));
+
+    (0..32).map(|i| session.create_file(format!("/{i}")).is_err()).collect()
+  }
+
+  let first = failures(7);
+
+  assert_eq!(first, failures(7));
+  assert!(first.contains(&true));
+  assert!(first.contains(&false));
+}
+
+#[test]
+fn fault_disable() {
+  let mut session = Session::new(Filesystem::new());
+  let id = session.faults_mut().add(Rule::new(None, "/", Fault::Io));
+
+  assert!(matches!(session.create_directory("/a"), Err(Error::Io(_))));
+
+  session.faults_mut().set_enabled(id, false);
+  session.create_directory("/a").unwrap();
+
+  session.faults_mut().set_enabled(id, true);
+  assert!(matches!(session.read_file("/a"), Err(Error::Io(_))));
+
+  session.faults_mut().remove(id);
+  assert!(matches!(session.read_file("/a"), Err(Error::NotFile(_))));
+}
+
+#[test]
+fn fault_invalid_probability() {
+  for probability in [f64::NAN, f64::INFINITY, -0.5, 1.5] {
+    assert!(matches!(Trigger::random(probability, 0), Err(Error::InvalidProbability(_))));
+
+    let mut session = Session::new(Filesystem::new());
+    let rule = Rule::new(None, "/", Fault::Io).trigger(Trigger::Random { probability, seed: 0 });
+    assert!(matches!(session.add_fault(rule.clone()), Err(Error::InvalidProbability(_))));
+
+    // a rule added directly never triggers rather than panicking
+    session.faults_mut().add(rule);
+    session.create_file("/a").unwrap();
+  }
+
+  assert!(Trigger::random(1.0, 0).is_ok());
+}
+
+#[test]
+fn fault_punch_hole() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+  session.write_file("/a", b"abc".to_vec()).unwrap();
+  session.faults_mut().add(Rule::new(Some(Operation::PunchHole), "/a", Fault::Io));
+
+  assert!(matches!(session.punch_hole("/a", 0, 1), Err(Error::Io(_))));
+  session.truncate("/a", 1).unwrap();
+}
@@ -0,0 +1,109 @@
+use std::{cmp::Ordering, path::PathBuf};
+
+use chrono::Utc;
+use fs::Filesystem;
+use session::{
+  find::{EntryType, Predicate},
+  walk::WalkOptions,
+  *,
+};
+
+fn session() -> Session {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/logs").unwrap();
+  session.create_directory("/logs/old").unwrap();
+  session.create_file("/logs/old/a.log").unwrap();
+  session.write_file("/logs/old/a.log", vec![b'a'; 100]).unwrap();
+  session.create_file("/logs/b.log").unwrap();
+  session.write_file("/logs/b.log", vec![b'b'; 10]).unwrap();
+  session.create_file("/logs/c.txt").unwrap();
+  session.create_directory("/empty").unwrap();
+
+  session
+}
+
+fn find(session: &Session, predicate: &Predicate) -> Vec<PathBuf> {
+  session
+    .find("/", predicate, WalkOptions::new())
+    .unwrap()
+    .map(|entry| entry.path())
+    .collect()
+}
+
+#[test]
+fn find_name() {
+  let session = session();
+
+  assert_eq!(
+    find(&session, &Predicate::name("*.log").unwrap()),
+    ["/logs/b.log", "/logs/old/a.log"].map(PathBuf::from)
+  );
+  assert!(matches!(Predicate::name("[a"), Err(Error::InvalidPattern(_))));
+}
+
+#[test]
+fn find_type_and_size() {
+  let session = session();
+
+  assert_eq!(
+    find(&session, &Predicate::Type(EntryType::Directory)),
+    ["/", "/empty", "/logs", "/logs/old"].map(PathBuf::from)
+  );
+  assert_eq!(
+    find(&session, &Predicate::Size(Ordering::Greater, 10)),
+    [PathBuf::from("/logs/old/a.log")]
+  );
+  assert_eq!(
+    find(&session, &Predicate::Size(Ordering::Equal, 10)),
+    [PathBuf::from("/logs/b.log")]
+  );
+}
+
+#[test]
+fn find_empty() {
+  let session = session();
+
+  assert_eq!(find(&session, &Predicate::Empty), ["/empty", "/logs/c.txt"].map(PathBuf::from));
+}
+
+#[test]
+fn find_modified() {
+  let mut session = session();
+  let time = Utc::now();
+
+  session.write_file("/logs/b.log", b"more".to_vec()).unwrap();
+
+  assert_eq!(
+    find(
+      &session,
+      &Predicate::Modified(Ordering::Greater, time).and(Predicate::Type(EntryType::File))
+    ),
+    [PathBuf::from("/logs/b.log")]
+  );
+}
+
+#[test]
+fn find_operators() {
+  let session = session();
+  let predicate = Predicate::name("*.log")
+    .unwrap()
+    .or(Predicate::Empty)
+    .and(!Predicate::Type(EntryType::Directory));
+
+  assert_eq!(
+    find(&session, &predicate),
+    ["/logs/b.log", "/logs/c.txt", "/logs/old/a.log"].map(PathBuf::from)
+  );
+}
+
+#[test]
+fn find_options() {
+  let session = session();
+  let entries: Vec<_> = session
+    .find("/logs", &Predicate::True, WalkOptions::new().min_depth(1).max_depth(1))
+    .unwrap()
+    .map(|entry| entry.path())
+    .collect();
+
+  assert_eq!(entries, ["/logs/b.log", "/logs/c.txt", "/logs/old"].map(PathBuf::from));
+}
@@ -0,0 +1,137 @@
+use fs::Filesystem;
+use session::{
+  fault::{Fault, Operation, Rule},
+  generate::*,
+  walk::WalkOptions,
+  *,
+};
+
+fn generate(generator: &TreeGenerator) -> Session {
+  let mut session = Session::new(Filesystem::new());
+  generator.generate(&mut session, "/tree").unwrap();
+
+  session
+}
+
+#[test]
+fn generate_counts() {
+  let mut session = Session::new(Filesystem::new());
+  let generator = TreeGenerator::new()
+    .depth(2)
+    .fanout(3)
+    .files(2)
+    .file_size(FileSize::Uniform { min: 0, max: 100_000 });
+  let usage = generator.generate(&mut session, "/tree").unwrap();
+
+  // the root and 3 + 9 subdirectories, each with 2 files
+  assert_eq!(usage.directories, 13);
+  assert_eq!(usage.files, 26);
+  assert_eq!(usage, session.disk_usage("/tree").unwrap());
+  assert!(session.resolve("/tree/d2/d2/f1").unwrap().1.is_file());
+  assert!(session.resolve("/tree/d0/d0/d0").is_err());
+}
+
+#[test]
+fn generate_deterministic() {
+  let generator = TreeGenerator::new()
+    .depth(1)
+    .fanout(2)
+    .files(3)
+    .file_size(FileSize::Exponential { mean: 1000 })
+    .seed(42);
+
+  let contents = |session: &Session| -> Vec<_> {
+    session
+      .walk_iter("/tree", WalkOptions::new())
+      .unwrap()
+      .filter(|entry| entry.is_file())
+      .map(|entry| (entry.path(), session.read_file(entry.path()).unwrap()))
+      .collect()
+  };
+
+  let a = contents(&generate(&generator));
+
+  assert_eq!(a, contents(&generate(&generator)));
+  assert_ne!(a, contents(&generate(&generator.clone().seed(43))));
+  assert!(a
+    .iter()
+    .all(|(_, content)| content.bytes().all(|byte| byte.is_ascii_alphanumeric())));
+}
+
+#[test]
+fn generate_existing_root() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/tree").unwrap();
+
+  let usage = TreeGenerator::new()
+    .files(5)
+    .file_size(FileSize::Fixed(10))
+    .generate(&mut session, "/tree")
+    .unwrap();
+
+  assert_eq!(usage.directories, 0);
+  assert_eq!(usage.bytes, 50);
+  assert_eq!(session.list_directory("/tree").unwrap().count(), 5);
+
+  session.create_file("/file").unwrap();
+  assert!(TreeGenerator::new().files(1).generate(&mut session, "/file").is_err());
+}
+
+#[test]
+fn content_chunks() {
+  for pattern in [Pattern::Random, Pattern::Zeros, Pattern::Ascii, Pattern::Lorem, Pattern::Sequence] {
+    let whole = ContentGenerator::new(pattern, 7).next_chunk(1000);
+
+    let mut generator = ContentGenerator::new(pattern, 7);
+    let mut chunked = generator.next_chunk(1);
+    chunked.extend(generator.next_chunk(333));
+    chunked.extend(generator.next_chunk(666));
+
+    assert_eq!(whole.len(), 1000);
+    assert_eq!(whole, chunked, "{pattern:?}");
+  }
+}
+
+#[test]
+fn content_patterns() {
+  assert!(ContentGenerator::new(Pattern::Zeros, 0)
+    .next_chunk(100)
+    .iter()
+    .all(|byte| *byte == 0));
+
+  let sequence = ContentGenerator::new(Pattern::Sequence, 0).next_chunk(1024);
+  assert_eq!(&sequence[..16], b"000000000000000\n");
+  assert_eq!(&sequence[672..688], b"000000000000042\n");
+
+  let lorem = String::from_utf8(ContentGenerator::new(Pattern::Lorem, 0).next_chunk(10_000)).unwrap();
+  let mut lines: Vec<_> = lorem.lines().collect();
+
+  // the chunk ends in the middle of a line
+  lines.pop();
+  assert!(lines
+    .iter()
+    .all(|line| line.ends_with('.') && line.starts_with(|c: char| c.is_ascii_uppercase())));
+}
+
+#[test]
+fn content_fill() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/file").unwrap();
+
+  let written = ContentGenerator::new(Pattern::Sequence, 0)
+    .fill(&mut session, "/file", 1 << 20)
+    .unwrap();
+
+  assert_eq!(written, 1 << 20);
+  assert_eq!(session.read_file_at("/file", 16 * 50_000, 16).unwrap(), b"000000000050000\n");
+
+  session.create_file("/short").unwrap();
+  session
+    .faults_mut()
+    .add(Rule::new(Some(Operation::Write), "/short", Fault::ShortWrite(10)));
+
+  let written = ContentGenerator::new(Pattern::Ascii, 0).fill(&mut session, "/short", 1000).unwrap();
+
+  assert_eq!(written, 10);
+  assert_eq!(session.disk_usage("/short").unwrap().bytes, 10);
+}
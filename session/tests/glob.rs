@@ -0,0 +1,62 @@
+use std::path::PathBuf;
+
+use fs::Filesystem;
+use session::*;
+
+fn session() -> Session {
+  let mut session = Session::new(Filesystem::new());
+
+  for path in ["/logs", "/logs/a", "/logs/b", "/logs/.old"] {
+    session.create_directory(path).unwrap();
+  }
+  for path in [
+    "/logs/a/1.log",
+    "/logs/a/2.txt",
+    "/logs/b/3.log",
+    "/logs/.old/4.log",
+    "/logs/.x.log",
+  ] {
+    session.create_file(path).unwrap();
+  }
+
+  session
+}
+
+fn paths(paths: &[&str]) -> Vec<PathBuf> {
+  paths.iter().map(PathBuf::from).collect()
+}
+
+#[test]
+fn glob_components() {
+  let session = session();
+
+  assert_eq!(session.glob("/logs/*/*.log").unwrap(), paths(&["/logs/a/1.log", "/logs/b/3.log"]));
+  assert_eq!(session.glob("/logs/?/[12].*").unwrap(), paths(&["/logs/a/1.log", "/logs/a/2.txt"]));
+  assert_eq!(session.glob("/logs/a/../b").unwrap(), paths(&["/logs/a/../b"]));
+}
+
+#[test]
+fn glob_hidden() {
+  let session = session();
+
+  assert_eq!(session.glob("/logs/*").unwrap(), paths(&["/logs/a", "/logs/b"]));
+  assert_eq!(session.glob("/logs/.*").unwrap(), paths(&["/logs/.old", "/logs/.x.log"]));
+}
+
+#[test]
+fn glob_relative() {
+  let mut session = session();
+  session.change_directory("/logs").unwrap();
+
+  assert_eq!(session.glob("*/3.*").unwrap(), paths(&["b/3.log"]));
+}
+
+#[test]
+fn glob_no_match() {
+  let session = session();
+
+  assert!(session.glob("/logs/*.csv").unwrap().is_empty());
+  assert!(session.glob("/missing/*").unwrap().is_empty());
+  assert!(session.glob("/logs/missing").unwrap().is_empty());
+  assert!(matches!(session.glob("/logs/[a"), Err(Error::InvalidPattern(_))));
+}
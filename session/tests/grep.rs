@@ -0,0 +1,153 @@
+use std::path::{Path, PathBuf};
+
+use fs::Filesystem;
+use session::{grep::*, *};
+
+fn session() -> Session {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/src").unwrap();
+  session.create_file("/src/main.rs").unwrap();
+  session
+    .write_file("/src/main.rs", b"fn main() {\n  let x = 1;\n  todo!()\n}\n".to_vec())
+    .unwrap();
+  session.create_file("/src/notes.txt").unwrap();
+  session.write_file("/src/notes.txt", b"TODO: write docs\n".to_vec()).unwrap();
+  session.create_directory("/target").unwrap();
+  session.create_file("/target/out.rs").unwrap();
+  session.write_file("/target/out.rs", b"todo\n".to_vec()).unwrap();
+  session.create_file("/binary").unwrap();
+  session.write_file("/binary", b"todo\0\n".to_vec()).unwrap();
+
+  session
+}
+
+fn paths(matches: &[GrepMatch]) -> Vec<(&Path, usize)> {
+  matches.iter().map(|found| (found.path.as_path(), found.line.number)).collect()
+}
+
+#[test]
+fn grep() {
+  let session = session();
+  let matches = session.grep("/", "todo", &GrepOptions::new()).unwrap();
+
+  assert_eq!(paths(&matches), [(Path::new("/src/main.rs"), 3), (Path::new("/target/out.rs"), 1)]);
+  assert_eq!(matches[0].line.text, "  todo!()");
+}
+
+#[test]
+fn grep_regex() {
+  let session = session();
+  let matches = session.grep("/src", r"let \w+ = \d", &GrepOptions::new()).unwrap();
+
+  assert_eq!(paths(&matches), [(Path::new("/src/main.rs"), 2)]);
+  assert!(matches!(session.grep("/", "(", &GrepOptions::new()), Err(Error::InvalidPattern(_))));
+}
+
+#[test]
+fn grep_ignore_case() {
+  let session = session();
+  let matches = session.grep("/src", "todo", &GrepOptions::new().ignore_case(true)).unwrap();
+
+  assert_eq!(paths(&matches), [(Path::new("/src/main.rs"), 3), (Path::new("/src/notes.txt"), 1)]);
+}
+
+#[test]
+fn grep_include_exclude() {
+  let session = session();
+  let options = GrepOptions::new().ignore_case(true).include("*.rs").exclude("target");
+  let matches = session.grep("/", "todo", &options).unwrap();
+
+  assert_eq!(paths(&matches), [(Path::new("/src/main.rs"), 3)]);
+}
+
+#[test]
+fn grep_binary() {
+  let session = session();
+
+  assert!(session.grep("/binary", "todo", &GrepOptions::new()).unwrap().is_empty());
+  assert_eq!(
+    session.grep("/binary", "todo", &GrepOptions::new().binary(true)).unwrap()[0].path,
+    PathBuf::from("/binary")
+  );
+}
+
+#[test]
+fn grep_context() {
+  let session = session();
+  let matches = session.grep("/src/main.rs", "todo", &GrepOptions::new().context(1)).unwrap();
+
+  let lines = |lines: &[Line]| lines.iter().map(|line| line.number).collect::<Vec<_>>();
+
+  assert_eq!(lines(&matches[0].before), [2]);
+  assert_eq!(lines(&matches[0].after), [4]);
+
+  let matches = session.grep("/src/main.rs", "main", &GrepOptions::new().context(2)).unwrap();
+
+  assert!(matches[0].before.is_empty());
+  assert_eq!(lines(&matches[0].after), [2, 3]);
+}
+
+#[test]
+fn grep_sparse() {
+  let mut session = session();
+  session.write_file("/src/notes.txt", b"todo\n".to_vec()).unwrap();
+  session.truncate("/src/notes.txt", 8 << 30).unwrap();
+
+  let matches = session.grep("/src", "todo", &GrepOptions::new().ignore_case(true)).unwrap();
+
+  assert_eq!(
+    paths(&matches),
+    [
+      (Path::new("/src/main.rs"), 3),
+      (Path::new("/src/notes.txt"), 1),
+      (Path::new("/src/notes.txt"), 2)
+    ]
+  );
+  assert_eq!(matches[2].line.text, "todo");
+}
+
+#[test]
+fn grep_sparse_middle() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/sparse").unwrap();
+  session.write_file("/sparse", b"a\ntodo".to_vec()).unwrap();
+  session.write_file_at("/sparse", 1 << 20, b"done\ntodo\n".to_vec()).unwrap();
+
+  let matches = session.grep("/sparse", "todo", &GrepOptions::new()).unwrap();
+
+  assert_eq!(paths(&matches), [(Path::new("/sparse"), 2), (Path::new("/sparse"), 3)]);
+  assert_eq!(matches[0].line.text, "tododone");
+}
+
+#[test]
+fn grep_sparse_binary() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/sparse").unwrap();
+  session.write_file("/sparse", b"todo\n".to_vec()).unwrap();
+  session.write_file_at("/sparse", 1 << 20, b"to\0do\n".to_vec()).unwrap();
+
+  assert!(session.grep("/sparse", "todo", &GrepOptions::new()).unwrap().is_empty());
+}
+
+#[test]
+fn grep_long_file() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+
+  let content: String = (1..=50_000).map(|i| format!("line {i}\n")).collect();
+  session.write_file("/a", content.into_bytes()).unwrap();
+
+  let matches = session
+    .grep("/a", "^line (1|25000|50000)$", &GrepOptions::new().context(2))
+    .unwrap();
+  let lines = |lines: &[Line]| lines.iter().map(|line| line.number).collect::<Vec<_>>();
+
+  assert_eq!(
+    matches.iter().map(|found| found.line.number).collect::<Vec<_>>(),
+    [1, 25_000, 50_000]
+  );
+  assert_eq!(lines(&matches[1].before), [24_998, 24_999]);
+  assert_eq!(lines(&matches[1].after), [25_001, 25_002]);
+  assert_eq!(lines(&matches[2].after), []);
+  assert_eq!(matches[2].line.text, "line 50000");
+}
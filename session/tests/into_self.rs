@@ -0,0 +1,30 @@
+use fs::Filesystem;
+use session::*;
+
+#[test]
+fn copy_into_self() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/a").unwrap();
+
+  let res = session.copy_entry("/a", "/a/b");
+  assert!(matches!(res, Err(Error::IntoSelf(_))));
+
+  let entries: Vec<_> = session.list_directory("/a").unwrap().collect();
+  assert!(entries.is_empty());
+}
+
+#[test]
+fn move_into_self() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/a").unwrap();
+  session.create_directory("/a/b").unwrap();
+
+  let res = session.move_entry("/a", "/a/b/c");
+  assert!(matches!(res, Err(Error::IntoSelf(_))));
+
+  let res = session.move_entry("/", "/a/c");
+  assert!(matches!(res, Err(Error::IntoSelf(_))));
+
+  assert!(session.resolve("/a/b").unwrap().1.is_directory());
+  assert_eq!(session.filesystem_usage().directories, 3);
+}
@@ -0,0 +1,86 @@
+use fs::{Filesystem, Limits};
+use session::*;
+
+fn session_with_limits(limits: Limits) -> Session {
+  Session::new(Filesystem::with_limits(limits))
+}
+
+#[test]
+fn max_bytes() {
+  let mut session = session_with_limits(Limits {
+    max_bytes: Some(8),
+    ..Limits::unlimited()
+  });
+  session.create_file("/a").unwrap();
+  session.create_file("/b").unwrap();
+  session.write_file("/a", "12345".into()).unwrap();
+
+  let res = session.write_file("/b", "6789".into());
+  assert!(matches!(res, Err(Error::NoSpace(_))));
+
+  session.remove("/a").unwrap();
+  session.write_file("/b", "6789".into()).unwrap();
+}
+
+#[test]
+fn max_entries() {
+  let mut session = session_with_limits(Limits {
+    max_entries: Some(3),
+    ..Limits::unlimited()
+  });
+  session.create_directory("/a").unwrap();
+  session.create_file("/a/1").unwrap();
+
+  let res = session.create_file("/b");
+  assert!(matches!(res, Err(Error::NoSpace(_))));
+
+  session.remove("/a").unwrap();
+  session.create_file("/b").unwrap();
+}
+
+#[test]
+fn max_file_size() {
+  let mut session = session_with_limits(Limits {
+    max_file_size: Some(4),
+    ..Limits::unlimited()
+  });
+  session.create_file("/a").unwrap();
+  session.write_file("/a", "1234".into()).unwrap();
+
+  let res = session.write_file("/a", "5".into());
+
+  assert!(matches!(res, Err(Error::QuotaExceeded(_))));
+  assert_eq!(session.read_file("/a").unwrap(), "1234");
+}
+
+#[test]
+fn max_name_len() {
+  let mut session = session_with_limits(Limits {
+    max_name_len: Some(3),
+    ..Limits::unlimited()
+  });
+  session.create_file("/abc").unwrap();
+
+  let res = session.create_file("/abcd");
+  assert!(matches!(res, Err(Error::NameTooLong(_))));
+
+  let res = session.move_entry("/abc", "/abcd");
+  assert!(matches!(res, Err(Error::NameTooLong(_))));
+}
+
+#[test]
+fn max_depth() {
+  let mut session = session_with_limits(Limits {
+    max_depth: Some(2),
+    ..Limits::unlimited()
+  });
+  session.create_directory("/a").unwrap();
+  session.create_directory("/a/b").unwrap();
+  session.create_directory("/c").unwrap();
+
+  let res = session.create_directory("/a/b/c");
+  assert!(matches!(res, Err(Error::TooDeep(_))));
+
+  let res = session.move_entry("/a", "/c/a");
+  assert!(matches!(res, Err(Error::TooDeep(_))));
+}
@@ -0,0 +1,385 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use fs::Filesystem;
+use proptest::prelude::*;
+use session::{walk::WalkOptions, Error, Session};
+
+/// An operation on a session, with paths built from a few names, `.` and `..` so that operations
+/// often collide.
+#[derive(Clone, Debug)]
+enum Op {
+  CreateDirectory(String),
+  CreateFile(String),
+  Remove(String),
+  Move(String, String),
+  Copy(String, String),
+  ChangeDirectory(String),
+  Write(String, Vec<u8>),
+  Read(String),
+  List(String),
+  Canonicalize(String),
+}
+
+/// The successful result of an operation.
+#[derive(Debug, PartialEq, Eq)]
+enum Output {
+  None,
+  Content(Vec<u8>),
+  Names(Vec<String>),
+  Path(PathBuf),
+}
+
+/// The kind of an error. Kinds are compared instead of errors since their payloads are
+/// implementation details.
+#[derive(Debug, PartialEq, Eq)]
+enum Kind {
+  NotDirectory,
+  NotFile,
+  NotExist,
+  NoParent,
+  Exists,
+  IntoSelf,
+}
+
+impl From<Error> for Kind {
+  fn from(err: Error) -> Self {
+    match err {
+      Error::NotDirectory(_) => Self::NotDirectory,
+      Error::NotFile(_) => Self::NotFile,
+      Error::NotExist(_) => Self::NotExist,
+      Error::NoParent(_) => Self::NoParent,
+      Error::Exists(_) => Self::Exists,
+      Error::IntoSelf(_) => Self::IntoSelf,
+      err => panic!("unexpected error: {err}"),
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+  File(Vec<u8>),
+  Directory(BTreeMap<String, Node>),
+}
+
+/// A reference model of a session: a plain tree of nodes, with paths resolved by splitting
+/// strings.
+struct Model {
+  root: Node,
+
+  current_directory: Vec<String>,
+}
+
+impl Model {
+  fn new() -> Self {
+    Self {
+      root: Node::Directory(BTreeMap::new()),
+      current_directory: Vec::new(),
+    }
+  }
+
+  fn apply(&mut self, op: &Op) -> Result<Output, Kind> {
+    match op {
+      Op::CreateDirectory(path) => self.create(path, Node::Directory(BTreeMap::new()))?,
+      Op::CreateFile(path) => self.create(path, Node::File(Vec::new()))?,
+      Op::Remove(path) => {
+        let path = self.canonicalize(path)?;
+        let (directory, name) = self.parent_mut(&path)?;
+
+        directory.remove(&name).ok_or(Kind::NotExist)?;
+      }
+      Op::Move(src, dst) => {
+        let src = self.canonicalize(src)?;
+        let dst = self.canonicalize(dst)?;
+        self.get(&src)?;
+        check_into_self(&src, &dst)?;
+
+        let (directory, name) = self.parent_mut(&src)?;
+        let node = directory.remove(&name).unwrap();
+
+        match self.parent_mut(&dst) {
+          Ok((directory, name)) => directory.insert(name, node),
+          Err(kind) => {
+            let (directory, name) = self.parent_mut(&src)?;
+            directory.insert(name, node);
+
+            return Err(kind);
+          }
+        };
+
+        if self.current_directory.starts_with(&src) {
+          self.current_directory.splice(..src.len(), dst);
+        }
+      }
+      Op::Copy(src, dst) => {
+        let src = self.canonicalize(src)?;
+        let dst = self.canonicalize(dst)?;
+        let node = self.get(&src)?.clone();
+        check_into_self(&src, &dst)?;
+
+        let (directory, name) = self.parent_mut(&dst)?;
+        directory.insert(name, node);
+      }
+      Op::ChangeDirectory(path) => {
+        let path = self.canonicalize(path)?;
+        let Node::Directory(_) = self.get(&path)? else {
+          return Err(Kind::NotDirectory);
+        };
+
+        self.current_directory = path;
+      }
+      Op::Write(path, content) => {
+        let path = self.canonicalize(path)?;
+        let Node::File(file) = self.get_mut(&path)? else {
+          return Err(Kind::NotFile);
+        };
+
+        file.extend(content);
+      }
+      Op::Read(path) => {
+        let path = self.canonicalize(path)?;
+        let Node::File(file) = self.get(&path)? else {
+          return Err(Kind::NotFile);
+        };
+
+        return Ok(Output::Content(file.clone()));
+      }
+      Op::List(path) => {
+        let path = self.canonicalize(path)?;
+        let Node::Directory(directory) = self.get(&path)? else {
+          return Err(Kind::NotDirectory);
+        };
+
+        return Ok(Output::Names(directory.keys().cloned().collect()));
+      }
+      Op::Canonicalize(path) => return self.realpath(path).map(|path| Output::Path(to_path(&path))),
+    }
+
+    Ok(Output::None)
+  }
+
+  fn create(&mut self, path: &str, node: Node) -> Result<(), Kind> {
+    let path = self.canonicalize(path)?;
+    let (directory, name) = self.parent_mut(&path)?;
+
+    if directory.contains_key(&name) {
+      return Err(Kind::Exists);
+    }
+
+    directory.insert(name, node);
+
+    Ok(())
+  }
+
+  /// Returns the names of the components of the absolute form of `path`.
+  fn canonicalize(&self, path: &str) -> Result<Vec<String>, Kind> {
+    let mut canonical = if path.starts_with('/') {
+      Vec::new()
+    } else {
+      self.current_directory.clone()
+    };
+
+    for name in path.split('/') {
+      match name {
+        "" | "." => (),
+        ".." => {
+          canonical.pop();
+        }
+        name => canonical.push(name.to_owned()),
+      }
+    }
+
+    Ok(canonical)
+  }
+
+  /// Returns the names of the components of the canonical form of `path`, looking up each
+  /// component in the tree.
+  fn realpath(&self, path: &str) -> Result<Vec<String>, Kind> {
+    let base = if path.starts_with('/') {
+      Vec::new()
+    } else {
+      self.current_directory.clone()
+    };
+
+    let mut canonical = Vec::new();
+    let mut nodes = vec![&self.root];
+
+    for name in base.iter().map(String::as_str).chain(path.split('/')) {
+      if name.is_empty() || name == "." {
+        continue;
+      }
+
+      let Node::Directory(entries) = nodes[nodes.len() - 1] else {
+        return Err(Kind::NotDirectory);
+      };
+
+      if name == ".." {
+        if nodes.len() > 1 {
+          canonical.pop();
+          nodes.pop();
+        }
+      } else {
+        nodes.push(entries.get(name).ok_or(Kind::NotExist)?);
+        canonical.push(name.to_owned());
+      }
+    }
+
+    Ok(canonical)
+  }
+
+  fn get(&self, path: &[String]) -> Result<&Node, Kind> {
+    let mut node = &self.root;
+
+    for name in path {
+      let Node::Directory(entries) = node else {
+        return Err(Kind::NotDirectory);
+      };
+
+      node = entries.get(name).ok_or(Kind::NotExist)?;
+    }
+
+    Ok(node)
+  }
+
+  fn get_mut(&mut self, path: &[String]) -> Result<&mut Node, Kind> {
+    let mut node = &mut self.root;
+
+    for name in path {
+      let Node::Directory(entries) = node else {
+        return Err(Kind::NotDirectory);
+      };
+
+      node = entries.get_mut(name).ok_or(Kind::NotExist)?;
+    }
+
+    Ok(node)
+  }
+
+  fn parent_mut(&mut self, path: &[String]) -> Result<(&mut BTreeMap<String, Node>, String), Kind> {
+    let Some((name, parent)) = path.split_last() else {
+      return Err(Kind::NoParent);
+    };
+
+    let Node::Directory(directory) = self.get_mut(parent)? else {
+      return Err(Kind::NotDirectory);
+    };
+
+    Ok((directory, name.clone()))
+  }
+
+  fn current_directory(&self) -> PathBuf {
+    to_path(&self.current_directory)
+  }
+
+  /// Returns every entry by path, with the content of files.
+  fn snapshot(&self) -> BTreeMap<PathBuf, Option<Vec<u8>>> {
+    let mut snapshot = BTreeMap::new();
+    let mut stack = vec![(PathBuf::from("/"), &self.root)];
+
+    while let Some((path, node)) = stack.pop() {
+      match node {
+        Node::File(content) => snapshot.insert(path, Some(content.clone())),
+        Node::Directory(entries) => {
+          stack.extend(entries.iter().map(|(name, node)| (path.join(name), node)));
+          snapshot.insert(path, None)
+        }
+      };
+    }
+
+    snapshot
+  }
+}
+
+fn to_path(names: &[String]) -> PathBuf {
+  let mut path = PathBuf::from("/");
+  path.extend(names);
+
+  path
+}
+
+fn check_into_self(src: &[String], dst: &[String]) -> Result<(), Kind> {
+  if dst.len() > src.len() && dst.starts_with(src) {
+    return Err(Kind::IntoSelf);
+  }
+
+  Ok(())
+}
+
+fn apply(session: &mut Session, op: &Op) -> Result<Output, Kind> {
+  let output = match op {
+    Op::CreateDirectory(path) => session.create_directory(path).map(|()| Output::None),
+    Op::CreateFile(path) => session.create_file(path).map(|()| Output::None),
+    Op::Remove(path) => session.remove(path).map(|()| Output::None),
+    Op::Move(src, dst) => session.move_entry(src, dst).map(|()| Output::None),
+    Op::Copy(src, dst) => session.copy_entry(src, dst).map(|()| Output::None),
+    Op::ChangeDirectory(path) => session.change_directory(path).map(|()| Output::None),
+    Op::Write(path, content) => session.write_file(path, content.clone()).map(|_| Output::None),
+    Op::Read(path) => session.read_file_at(path, 0, usize::MAX).map(Output::Content),
+    Op::List(path) => session
+      .list_directory(path)
+      .map(|entries| Output::Names(entries.map(|entry| entry.name().to_string_lossy().into_owned()).collect())),
+    Op::Canonicalize(path) => session.canonicalize(path).map(Output::Path),
+  };
+
+  Ok(output?)
+}
+
+fn snapshot(session: &Session) -> BTreeMap<PathBuf, Option<Vec<u8>>> {
+  session
+    .walk_iter("/", WalkOptions::new())
+    .unwrap()
+    .map(|entry| {
+      let content = match entry.entry() {
+        fs::entry::Borrowed::File(file) => Some(file.content.to_vec().unwrap()),
+        fs::entry::Borrowed::Directory(_) => None,
+      };
+
+      (entry.path(), content)
+    })
+    .collect()
+}
+
+fn path() -> impl Strategy<Value = String> {
+  let components = prop::collection::vec(prop::sample::select(&["a", "b", "c", ".", ".."][..]), 1..4);
+
+  (any::<bool>(), components).prop_map(|(absolute, components)| {
+    let path = components.join("/");
+
+    if absolute {
+      format!("/{path}")
+    } else {
+      path
+    }
+  })
+}
+
+fn op() -> impl Strategy<Value = Op> {
+  prop_oneof![
+    3 => path().prop_map(Op::CreateDirectory),
+    3 => path().prop_map(Op::CreateFile),
+    1 => path().prop_map(Op::Remove),
+    2 => (path(), path()).prop_map(|(src, dst)| Op::Move(src, dst)),
+    1 => (path(), path()).prop_map(|(src, dst)| Op::Copy(src, dst)),
+    2 => path().prop_map(Op::ChangeDirectory),
+    2 => (path(), prop::collection::vec(any::<u8>(), 0..8)).prop_map(|(path, content)| Op::Write(path, content)),
+    1 => path().prop_map(Op::Read),
+    1 => path().prop_map(Op::List),
+    1 => path().prop_map(Op::Canonicalize),
+  ]
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig::with_cases(1024))]
+
+  #[test]
+  fn session_matches_model(ops in prop::collection::vec(op(), 1..48)) {
+    let mut session = Session::new(Filesystem::new());
+    let mut model = Model::new();
+
+    for op in &ops {
+      prop_assert_eq!(apply(&mut session, op), model.apply(op), "{:?}", op);
+      prop_assert_eq!(session.current_directory(), model.current_directory(), "{:?}", op);
+      prop_assert_eq!(snapshot(&session), model.snapshot(), "{:?}", op);
+      prop_assert_eq!(session.filesystem_usage(), session.disk_usage("/").unwrap(), "{:?}", op);
+    }
+  }
+}
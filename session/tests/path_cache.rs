@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use fs::Filesystem;
+use session::*;
+
+/// Creates a session with `/a/b/f`, having looked up `/a/b/f` enough times that `/a/b` is cached.
+fn cached() -> Session {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/a").unwrap();
+  session.create_directory("/a/b").unwrap();
+  session.create_file("/a/b/f").unwrap();
+
+  for _ in 0..2 {
+    session.resolve("/a/b/f").unwrap();
+  }
+
+  session
+}
+
+#[test]
+fn resolve_cleans_paths_in_cached_directory() {
+  let session = cached();
+
+  for path in ["/a/b/./f", "/a/b//f", "/a/b/../b/f", "//a/b/f", "/a//b/f"] {
+    let (path, _) = session.resolve(path).unwrap();
+
+    assert_eq!(path, Path::new("/a/b/f"));
+  }
+
+  let (path, entry) = session.resolve("/a/b/.").unwrap();
+
+  assert_eq!(path, Path::new("/a/b"));
+  assert!(entry.is_directory());
+}
+
+#[test]
+fn resolve_after_cached_directory_moved() {
+  let mut session = cached();
+  session.move_entry("/a/b", "/a/c").unwrap();
+
+  let res = session.resolve("/a/b/f");
+
+  assert!(matches!(res, Err(Error::NotExist(_))));
+  assert!(session.resolve("/a/c/f").is_ok());
+}
+
+#[test]
+fn resolve_after_cached_directory_replaced_with_file() {
+  let mut session = cached();
+  session.remove("/a/b").unwrap();
+  session.create_file("/a/b").unwrap();
+
+  let res = session.resolve("/a/b/f");
+
+  assert!(matches!(res, Err(Error::NotDirectory(_))));
+}
+
+#[test]
+fn resolve_relative_after_current_directory_moved() {
+  let mut session = cached();
+  session.change_directory("/a/b").unwrap();
+  session.move_entry("/a", "/d").unwrap();
+
+  let (path, _) = session.resolve("f").unwrap();
+
+  assert_eq!(path, Path::new("/d/b/f"));
+}
@@ -0,0 +1,42 @@
+use fs::Filesystem;
+use session::{listing::OWNER, *};
+
+#[test]
+fn read_dir_with_metadata() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/a").unwrap();
+  session.create_directory("/a/b").unwrap();
+  session.create_directory("/a/c").unwrap();
+  session.create_file("/a/.hidden").unwrap();
+  session.write_file("/a/.hidden", b"12345".to_vec()).unwrap();
+
+  let entries = session.read_dir_with_metadata("/").unwrap();
+  assert_eq!(entries.len(), 1);
+
+  let a = &entries[0];
+  assert_eq!(a.name, "a");
+  assert!(a.is_directory);
+  assert_eq!(a.links, 4);
+  assert_eq!(a.mode_string(), "drwxr-xr-x");
+  assert_eq!(a.owner, OWNER);
+
+  let entries = session.read_dir_with_metadata("/a").unwrap();
+  let names: Vec<_> = entries.iter().map(|entry| entry.name.clone()).collect();
+  assert_eq!(names, [".hidden", "b", "c"]);
+
+  let hidden = &entries[0];
+  assert!(hidden.is_hidden());
+  assert_eq!(hidden.len, 5);
+  assert_eq!(hidden.links, 1);
+  assert_eq!(hidden.mode_string(), "-rw-r--r--");
+  assert!(!entries[1].is_hidden());
+}
+
+#[test]
+fn read_dir_with_metadata_not_directory() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+
+  assert!(matches!(session.read_dir_with_metadata("/a"), Err(Error::NotDirectory(_))));
+  assert!(matches!(session.read_dir_with_metadata("/b"), Err(Error::NotExist(_))));
+}
@@ -0,0 +1,168 @@
+use fs::{content::BLOCK_SIZE, Filesystem, Limits};
+use session::{usage::Usage, *};
+
+const GIB: u64 = 1 << 30;
+
+fn allocated(session: &Session, path: &str) -> u64 {
+  let (_, entry) = session.resolve(path).unwrap();
+  entry.metadata().allocated
+}
+
+#[test]
+fn write_file_at_beyond_end() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+  session.write_file("/a", b"abc".to_vec()).unwrap();
+
+  let offset = 10 * BLOCK_SIZE as u64;
+  session.write_file_at("/a", offset, b"xyz".to_vec()).unwrap();
+
+  let content = session.read_file_at("/a", 0, offset as usize + 3).unwrap();
+  assert_eq!(content.len(), offset as usize + 3);
+  assert_eq!(&content[..3], b"abc");
+  assert!(content[3..offset as usize].iter().all(|byte| *byte == 0));
+  assert_eq!(&content[offset as usize..], b"xyz");
+
+  assert_eq!(allocated(&session, "/a"), BLOCK_SIZE as u64 + 3);
+  assert_eq!(session.filesystem_usage().bytes, offset + 3);
+  assert_eq!(session.filesystem_usage().allocated, BLOCK_SIZE as u64 + 3);
+}
+
+#[test]
+fn read_file_too_large() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+  session.truncate("/a", u64::MAX).unwrap();
+
+  let res = session.read_file_at("/a", 0, usize::MAX);
+  assert!(matches!(res, Err(Error::OutOfMemory(_))));
+
+  let res = session.read_file("/a");
+  assert!(matches!(res, Err(Error::OutOfMemory(_))));
+
+  assert_eq!(session.read_file_at("/a", 1 << 40, 3).unwrap(), [0; 3]);
+}
+
+#[test]
+fn write_file_at_overwrites() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+  session.write_file("/a", b"hello world".to_vec()).unwrap();
+  session.write_file_at("/a", 6, b"there".to_vec()).unwrap();
+
+  assert_eq!(session.read_file("/a").unwrap(), "hello there");
+  assert_eq!(session.filesystem_usage().bytes, 11);
+}
+
+#[test]
+fn write_file_at_overflow() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+  session.write_file("/a", b"abc".to_vec()).unwrap();
+
+  let res = session.write_file_at("/a", u64::MAX, b"x".to_vec());
+  assert!(matches!(res, Err(Error::FileTooLarge(_))));
+
+  session.truncate("/a", u64::MAX).unwrap();
+  let res = session.write_file("/a", b"x".to_vec());
+  assert!(matches!(res, Err(Error::FileTooLarge(_))));
+
+  session.truncate("/a", 3).unwrap();
+  assert_eq!(session.read_file("/a").unwrap(), "abc");
+  assert_eq!(session.filesystem_usage().bytes, 3);
+  assert_eq!(allocated(&session, "/a"), 3);
+}
+
+#[test]
+fn truncate() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+  session.write_file("/a", b"hello world".to_vec()).unwrap();
+
+  session.truncate("/a", 5).unwrap();
+  assert_eq!(session.read_file("/a").unwrap(), "hello");
+
+  session.truncate("/a", 8).unwrap();
+  assert_eq!(session.read_file("/a").unwrap(), "hello\0\0\0");
+  assert_eq!(session.filesystem_usage().bytes, 8);
+
+  session.truncate("/a", 0).unwrap();
+  assert_eq!(session.read_file("/a").unwrap(), "");
+  assert_eq!(session.filesystem_usage(), Usage::of(session.resolve("/").unwrap().1));
+}
+
+#[test]
+fn truncate_multi_gigabyte() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/disk.img").unwrap();
+  session.truncate("/disk.img", 8 * GIB).unwrap();
+  session.write_file_at("/disk.img", 4 * GIB, b"superblock".to_vec()).unwrap();
+
+  assert_eq!(session.resolve("/disk.img").unwrap().1.metadata().len, 8 * GIB);
+  assert_eq!(allocated(&session, "/disk.img"), BLOCK_SIZE as u64);
+  assert_eq!(session.read_file_at("/disk.img", 4 * GIB, 10).unwrap(), b"superblock");
+  assert_eq!(session.read_file_at("/disk.img", GIB, 4).unwrap(), [0; 4]);
+  assert!(session.storage_stats().stored_bytes <= BLOCK_SIZE as u64);
+}
+
+#[test]
+fn truncate_over_limit() {
+  let mut session = Session::new(Filesystem::with_limits(Limits {
+    max_bytes: Some(GIB),
+    ..Limits::unlimited()
+  }));
+
+  session.create_file("/a").unwrap();
+
+  assert!(matches!(session.truncate("/a", 2 * GIB), Err(Error::NoSpace(_))));
+  session.truncate("/a", GIB).unwrap();
+}
+
+#[test]
+fn punch_hole() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+  session.write_file("/a", vec![1; 4 * BLOCK_SIZE]).unwrap();
+
+  session.punch_hole("/a", BLOCK_SIZE as u64 / 2, 2 * BLOCK_SIZE as u64).unwrap();
+
+  let content = session.read_file_at("/a", 0, 4 * BLOCK_SIZE).unwrap();
+  assert_eq!(content.len(), 4 * BLOCK_SIZE);
+  assert!(content[..BLOCK_SIZE / 2].iter().all(|byte| *byte == 1));
+  assert!(content[BLOCK_SIZE / 2..BLOCK_SIZE * 5 / 2].iter().all(|byte| *byte == 0));
+  assert!(content[BLOCK_SIZE * 5 / 2..].iter().all(|byte| *byte == 1));
+
+  // only the block entirely inside the hole is deallocated
+  assert_eq!(allocated(&session, "/a"), 3 * BLOCK_SIZE as u64);
+  assert_eq!(session.filesystem_usage().bytes, 4 * BLOCK_SIZE as u64);
+  assert_eq!(session.filesystem_usage().allocated, 3 * BLOCK_SIZE as u64);
+}
+
+#[test]
+fn seek_data_and_hole() {
+  let block = BLOCK_SIZE as u64;
+
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+  session.write_file_at("/a", 2 * block, vec![1; BLOCK_SIZE]).unwrap();
+  session.write_file_at("/a", 5 * block, vec![1; 10]).unwrap();
+
+  assert_eq!(session.seek_data("/a", 0).unwrap(), Some(2 * block));
+  assert_eq!(session.seek_data("/a", 2 * block + 1).unwrap(), Some(2 * block + 1));
+  assert_eq!(session.seek_data("/a", 3 * block).unwrap(), Some(5 * block));
+  assert_eq!(session.seek_data("/a", 5 * block + 10).unwrap(), None);
+
+  assert_eq!(session.seek_hole("/a", 0).unwrap(), Some(0));
+  assert_eq!(session.seek_hole("/a", 2 * block).unwrap(), Some(3 * block));
+  assert_eq!(session.seek_hole("/a", 5 * block).unwrap(), Some(5 * block + 10));
+  assert_eq!(session.seek_hole("/a", 5 * block + 10).unwrap(), None);
+}
+
+#[test]
+fn sparse_directory() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/a").unwrap();
+
+  assert!(matches!(session.truncate("/a", 10), Err(Error::NotFile(_))));
+  assert!(matches!(session.seek_data("/a", 0), Err(Error::NotFile(_))));
+}
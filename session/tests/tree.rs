@@ -0,0 +1,118 @@
+use fs::{Directory, Entry, File, Filesystem};
+use session::{tree::TreeOptions, *};
+
+fn session() -> Session {
+  let mut session = Session::new(Filesystem::new());
+
+  for path in ["/a", "/a/b", "/a/.git", "/c"] {
+    session.create_directory(path).unwrap();
+  }
+  for (path, len) in [("/a/b/1", 10), ("/a/2", 20), ("/a/.git/3", 30), ("/c/4", 40)] {
+    session.create_file(path).unwrap();
+    session.write_file(path, vec![b'x'; len]).unwrap();
+  }
+
+  session
+}
+
+#[test]
+fn tree_render() {
+  let tree = session().tree("/", &TreeOptions::new()).unwrap();
+
+  assert_eq!(
+    tree.render(false),
+    "/
+├── a
+│   ├── 2
+│   └── b
+│       └── 1
+└── c
+    └── 4
+
+3 directories, 3 files
+"
+  );
+}
+
+#[test]
+fn tree_relative() {
+  let mut session = session();
+  session.change_directory("/a").unwrap();
+
+  let tree = session.tree("b", &TreeOptions::new()).unwrap();
+
+  assert_eq!(tree.render(false), "b\n└── 1\n\n0 directories, 1 file\n");
+}
+
+#[test]
+fn tree_options() {
+  let session = session();
+
+  let tree = session.tree("/a", &TreeOptions::new().all(true)).unwrap();
+  assert_eq!((tree.directories, tree.files), (2, 3));
+
+  let tree = session.tree("/", &TreeOptions::new().max_depth(1)).unwrap();
+  assert_eq!((tree.directories, tree.files), (2, 0));
+  assert!(tree.root.children.iter().all(|child| child.children.is_empty()));
+
+  let tree = session.tree("/", &TreeOptions::new().directories_only(true)).unwrap();
+  assert_eq!(tree.render(false), "/\n├── a\n│   └── b\n└── c\n\n3 directories\n");
+}
+
+#[test]
+fn tree_sizes() {
+  let tree = session().tree("/", &TreeOptions::new().max_depth(1)).unwrap();
+
+  // sizes include entries that aren't shown
+  assert_eq!(tree.root.size, 100);
+  assert_eq!(tree.root.children[0].size, 60);
+  assert!(tree.render(true).starts_with("[       100]  /\n├── [        60]  a\n"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn tree_json() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/d").unwrap();
+  session.create_file("/d/\"q\"").unwrap();
+
+  let tree = session.tree("/d", &TreeOptions::new()).unwrap();
+
+  assert_eq!(
+    serde_json::to_string(&tree.to_json(true)).unwrap(),
+    r#"[{"type":"directory","name":"/d","size":0,"contents":[{"type":"file","name":"\"q\"","size":0}]},{"type":"report","directories":0,"files":1}]"#
+  );
+}
+
+#[test]
+fn tree_deep() {
+  const DEPTH: usize = 100_000;
+
+  let mut directory = Directory::new("d");
+  directory.entries.insert("f".into(), Entry::File(File::new("f")));
+
+  for _ in 1..DEPTH {
+    let mut parent = Directory::new("d");
+    parent.entries.insert("d".into(), Entry::Directory(directory));
+    directory = parent;
+  }
+
+  let mut filesystem = Filesystem::new();
+  filesystem.root.entries.insert("d".into(), Entry::Directory(directory));
+  let session = Session::new(filesystem);
+
+  let tree = session.tree("/", &TreeOptions::new()).unwrap();
+  assert_eq!((tree.directories, tree.files), (DEPTH as u64, 1));
+
+  #[cfg(feature = "serde")]
+  std::mem::forget(tree.to_json(false));
+
+  // dropping the nested nodes and directories recurses once per level
+  std::mem::forget(tree);
+  std::mem::forget(session);
+}
+
+#[test]
+fn tree_not_exist() {
+  assert!(matches!(session().tree("/x", &TreeOptions::new()), Err(Error::NotExist(_))));
+}
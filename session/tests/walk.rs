@@ -0,0 +1,157 @@
+use std::path::PathBuf;
+
+use fs::Filesystem;
+use session::{walk::*, *};
+
+fn session() -> Session {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/a").unwrap();
+  session.create_directory("/a/b").unwrap();
+  session.create_file("/a/b/c").unwrap();
+  session.create_file("/a/d").unwrap();
+  session.create_directory("/e").unwrap();
+  session.create_file("/e/f").unwrap();
+
+  session
+}
+
+fn paths(session: &Session, options: WalkOptions) -> Vec<PathBuf> {
+  session.walk_iter("/", options).unwrap().map(|entry| entry.path()).collect()
+}
+
+#[test]
+fn walk_iter() {
+  let session = session();
+  let entries: Vec<_> = session
+    .walk_iter("/", WalkOptions::new())
+    .unwrap()
+    .map(|entry| (entry.path(), entry.depth()))
+    .collect();
+
+  assert_eq!(
+    entries,
+    [
+      ("/".into(), 0),
+      ("/a".into(), 1),
+      ("/a/b".into(), 2),
+      ("/a/b/c".into(), 3),
+      ("/a/d".into(), 2),
+      ("/e".into(), 1),
+      ("/e/f".into(), 2),
+    ]
+  );
+}
+
+#[test]
+fn walk_iter_depth() {
+  let session = session();
+
+  assert_eq!(
+    paths(&session, WalkOptions::new().max_depth(1)),
+    ["/", "/a", "/e"].map(PathBuf::from)
+  );
+  assert_eq!(
+    paths(&session, WalkOptions::new().min_depth(2).max_depth(2)),
+    ["/a/b", "/a/d", "/e/f"].map(PathBuf::from)
+  );
+  assert!(paths(&session, WalkOptions::new().min_depth(4)).is_empty());
+}
+
+#[test]
+fn walk_iter_contents_first() {
+  let session = session();
+
+  assert_eq!(
+    paths(&session, WalkOptions::new().contents_first(true)),
+    ["/a/b/c", "/a/b", "/a/d", "/a", "/e/f", "/e", "/"].map(PathBuf::from)
+  );
+  assert_eq!(
+    paths(&session, WalkOptions::new().contents_first(true).min_depth(1).max_depth(1)),
+    ["/a", "/e"].map(PathBuf::from)
+  );
+}
+
+#[test]
+fn walk_iter_sort_by() {
+  let session = session();
+  let options = WalkOptions::new()
+    .min_depth(1)
+    .sort_by(|a, b| a.is_directory().cmp(&b.is_directory()).then(b.name().cmp(a.name())));
+
+  assert_eq!(
+    paths(&session, options),
+    ["/e", "/e/f", "/a", "/a/d", "/a/b", "/a/b/c"].map(PathBuf::from)
+  );
+}
+
+#[test]
+fn walk_iter_skip_current_dir() {
+  let session = session();
+  let mut entries = session.walk_iter("/", WalkOptions::new()).unwrap();
+  let mut paths = Vec::new();
+
+  while let Some(entry) = entries.next() {
+    if entry.name() == "a" || entry.name() == "e" {
+      entries.skip_current_dir();
+    }
+
+    paths.push(entry.path());
+  }
+
+  assert_eq!(paths, ["/", "/a", "/e"].map(PathBuf::from));
+}
+
+#[test]
+fn walk_iter_relative_root() {
+  let mut session = session();
+  session.change_directory("/a").unwrap();
+
+  let entries: Vec<_> = session
+    .walk_iter("b", WalkOptions::new())
+    .unwrap()
+    .map(|entry| entry.path())
+    .collect();
+
+  assert_eq!(entries, ["/a/b", "/a/b/c"].map(PathBuf::from));
+  assert!(matches!(session.walk_iter("x", WalkOptions::new()), Err(Error::NotExist(_))));
+}
+
+#[test]
+fn walk_stop() {
+  let session = session();
+  let mut visited = Vec::new();
+
+  session
+    .walk("/", |path, _| {
+      visited.push(path.to_owned());
+
+      if path.ends_with("b") {
+        Walk::Stop
+      } else {
+        Walk::Continue
+      }
+    })
+    .unwrap();
+
+  assert_eq!(visited, ["/", "/a", "/a/b"].map(PathBuf::from));
+}
+
+#[test]
+fn walk_skip() {
+  let session = session();
+  let mut visited = Vec::new();
+
+  session
+    .walk("/", |path, _| {
+      visited.push(path.to_owned());
+
+      if path.ends_with("a") || path.ends_with("f") {
+        Walk::Skip
+      } else {
+        Walk::Continue
+      }
+    })
+    .unwrap();
+
+  assert_eq!(visited, ["/", "/a", "/e", "/e/f"].map(PathBuf::from));
+}
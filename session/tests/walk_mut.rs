@@ -0,0 +1,202 @@
+use std::path::PathBuf;
+
+use fs::{entry::MutBorrowed as MutBorrowedEntry, Directory, Entry, File, Filesystem, Limits};
+use session::{usage::Usage, walk::*, *};
+
+fn session() -> Session {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/etc").unwrap();
+  session.create_file("/etc/a.conf").unwrap();
+  session.write_file("/etc/a.conf", b"debug = false".to_vec()).unwrap();
+  session.create_file("/etc/b.txt").unwrap();
+  session.write_file("/etc/b.txt", b"debug = false".to_vec()).unwrap();
+  session.create_directory("/etc/skip").unwrap();
+  session.create_file("/etc/skip/c.conf").unwrap();
+  session.write_file("/etc/skip/c.conf", b"debug = false".to_vec()).unwrap();
+
+  session
+}
+
+#[test]
+fn walk_mut_rewrite_files() {
+  let mut session = session();
+
+  session
+    .walk_mut("/", WalkOptions::new(), |path, entry| {
+      if let MutBorrowedEntry::File(file) = entry {
+        if path.extension().is_some_and(|extension| extension == "conf") {
+          file.truncate(0, None);
+          file.append(b"debug = true\nverbose = true", None).unwrap();
+        }
+      }
+    })
+    .unwrap();
+
+  assert_eq!(session.read_file("/etc/a.conf").unwrap(), "debug = true\nverbose = true");
+  assert_eq!(session.read_file("/etc/b.txt").unwrap(), "debug = false");
+  assert_eq!(session.read_file("/etc/skip/c.conf").unwrap(), "debug = true\nverbose = true");
+  assert_eq!(session.filesystem_usage(), Usage::of(session.resolve("/").unwrap().1));
+}
+
+#[test]
+fn walk_mut_filter_entry() {
+  let mut session = session();
+  let mut visited = Vec::new();
+
+  let options = WalkOptions::new().min_depth(1).filter_entry(|entry| entry.name() != "skip");
+
+  session
+    .walk_mut("/", options, |path, entry| {
+      visited.push(path.to_owned());
+      entry.metadata_mut().xattrs.insert("user.visited".into(), Vec::new());
+    })
+    .unwrap();
+
+  assert_eq!(visited, ["/etc", "/etc/a.conf", "/etc/b.txt"].map(PathBuf::from));
+  assert!(session.get_xattr("/etc/a.conf", "user.visited").is_ok());
+  assert!(session.get_xattr("/etc/skip", "user.visited").is_err());
+  assert!(session.get_xattr("/", "user.visited").is_err());
+}
+
+#[test]
+fn walk_mut_contents_first() {
+  let mut session = session();
+  let mut visited = Vec::new();
+
+  session
+    .walk_mut("/etc", WalkOptions::new().contents_first(true), |path, _| {
+      visited.push(path.to_owned());
+    })
+    .unwrap();
+
+  assert_eq!(
+    visited,
+    ["/etc/a.conf", "/etc/b.txt", "/etc/skip/c.conf", "/etc/skip", "/etc"].map(PathBuf::from)
+  );
+}
+
+#[test]
+fn walk_mut_skip_and_stop() {
+  let mut session = session();
+  let mut visited = Vec::new();
+
+  session
+    .walk_mut("/", WalkOptions::new(), |path, _| {
+      visited.push(path.to_owned());
+
+      if path.ends_with("b.txt") {
+        Walk::Stop
+      } else if path.ends_with("etc") {
+        Walk::Continue
+      } else {
+        Walk::Skip
+      }
+    })
+    .unwrap();
+
+  assert_eq!(visited, ["/"].map(PathBuf::from));
+
+  visited.clear();
+  session
+    .walk_mut("/etc", WalkOptions::new(), |path, _| {
+      visited.push(path.to_owned());
+
+      if path.ends_with("b.txt") {
+        Walk::Stop
+      } else {
+        Walk::Continue
+      }
+    })
+    .unwrap();
+
+  assert_eq!(visited, ["/etc", "/etc/a.conf", "/etc/b.txt"].map(PathBuf::from));
+}
+
+#[test]
+fn walk_mut_remove_entries() {
+  let mut session = session();
+
+  session
+    .walk_mut("/", WalkOptions::new(), |_, entry| {
+      if let MutBorrowedEntry::Directory(directory) = entry {
+        directory.entries.retain(|name, _| name != "skip");
+      }
+    })
+    .unwrap();
+
+  assert!(matches!(session.resolve("/etc/skip"), Err(Error::NotExist(_))));
+  assert_eq!(session.filesystem_usage(), Usage::of(session.resolve("/").unwrap().1));
+}
+
+#[test]
+fn walk_mut_rename_reverted() {
+  let mut session = session();
+
+  session
+    .walk_mut("/etc", WalkOptions::new(), |_, entry| {
+      entry.metadata_mut().name = "renamed".into();
+    })
+    .unwrap();
+
+  assert_eq!(session.resolve("/etc/a.conf").unwrap().1.name(), "a.conf");
+  assert_eq!(session.resolve("/etc").unwrap().1.name(), "etc");
+}
+
+#[test]
+fn walk_mut_content_edits_update_usage() {
+  let mut session = session();
+  session.set_limits(Limits {
+    max_bytes: Some(64),
+    ..Limits::default()
+  });
+
+  session
+    .walk_mut("/etc", WalkOptions::new(), |_, entry| {
+      if let MutBorrowedEntry::File(file) = entry {
+        file.content.append(b" # edited", None).unwrap();
+      }
+    })
+    .unwrap();
+
+  assert_eq!(session.resolve("/etc/a.conf").unwrap().1.metadata().len, 22);
+  assert_eq!(session.filesystem_usage().bytes, 66);
+  assert_eq!(session.filesystem_usage(), Usage::of(session.resolve("/").unwrap().1));
+
+  let res = session.write_file("/etc/a.conf", b"!".to_vec());
+  assert!(matches!(res, Err(Error::NoSpace(_))));
+}
+
+#[test]
+fn walk_mut_deep() {
+  const DEPTH: usize = 100_000;
+
+  let mut directory = Directory::new("d");
+  directory.entries.insert("f".into(), Entry::File(File::new("f")));
+
+  for _ in 1..DEPTH {
+    let mut parent = Directory::new("d");
+    parent.entries.insert("d".into(), Entry::Directory(directory));
+    directory = parent;
+  }
+
+  let mut filesystem = Filesystem::new();
+  filesystem.root.entries.insert("d".into(), Entry::Directory(directory));
+  let mut session = Session::new(filesystem);
+
+  let mut visited = 0;
+  session
+    .walk_mut("/", WalkOptions::new().contents_first(true), |_, entry| {
+      visited += 1;
+
+      if let MutBorrowedEntry::File(file) = entry {
+        file.content.append(b"x", None).unwrap();
+      }
+    })
+    .unwrap();
+
+  assert_eq!(visited, DEPTH + 2);
+  assert_eq!(session.filesystem_usage().bytes, 1);
+
+  // dropping the nested directories recurses once per level
+  std::mem::forget(session);
+}
@@ -0,0 +1,89 @@
+use fs::Filesystem;
+use session::*;
+
+#[test]
+fn set_get_xattr() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+  session.set_xattr("/a", "user.tag", b"red".to_vec()).unwrap();
+  session.set_xattr("/a", "trusted.owner", b"me".to_vec()).unwrap();
+
+  assert_eq!(session.get_xattr("/a", "user.tag").unwrap(), b"red");
+
+  let names: Vec<_> = session.list_xattr("/a").unwrap().collect();
+  assert_eq!(names, &["trusted.owner", "user.tag"]);
+}
+
+#[test]
+fn remove_xattr() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/a").unwrap();
+  session.set_xattr("/a", "user.tag", b"red".to_vec()).unwrap();
+  session.remove_xattr("/a", "user.tag").unwrap();
+
+  let res = session.get_xattr("/a", "user.tag");
+  assert!(matches!(res, Err(Error::NoXattr(_))));
+
+  let res = session.remove_xattr("/a", "user.tag");
+  assert!(matches!(res, Err(Error::NoXattr(_))));
+}
+
+#[test]
+fn xattr_invalid_name() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+
+  for name in ["tag", "user.", "other.tag", &format!("user.{}", "a".repeat(256))] {
+    let res = session.set_xattr("/a", name, Vec::new());
+    assert!(matches!(res, Err(Error::InvalidXattrName(_))), "{name}");
+  }
+}
+
+#[test]
+fn xattr_too_large() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/a").unwrap();
+
+  let res = session.set_xattr("/a", "user.tag", vec![0; xattr::MAX_VALUE_LEN + 1]);
+
+  assert!(matches!(res, Err(Error::XattrTooLarge(_))));
+}
+
+#[test]
+fn xattr_preserved_by_move_and_copy() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_directory("/a").unwrap();
+  session.create_file("/a/1").unwrap();
+  session.set_xattr("/a/1", "user.tag", b"red".to_vec()).unwrap();
+  session.move_entry("/a", "/b").unwrap();
+  session.copy_entry("/b", "/c").unwrap();
+
+  assert_eq!(session.get_xattr("/b/1", "user.tag").unwrap(), b"red");
+  assert_eq!(session.get_xattr("/c/1", "user.tag").unwrap(), b"red");
+}
+
+#[test]
+fn xattr_listed() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/f").unwrap();
+  session.set_xattr("/f", "user.tag", b"red".to_vec()).unwrap();
+
+  let entries = session.read_dir_with_metadata("/").unwrap();
+
+  assert_eq!(entries[0].xattrs.len(), 1);
+  assert_eq!(entries[0].xattrs["user.tag"], b"red");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn xattr_serde_round_trip() {
+  let mut session = Session::new(Filesystem::new());
+  session.create_file("/f").unwrap();
+  session.set_xattr("/f", "user.tag", b"red".to_vec()).unwrap();
+
+  let (_, entry) = session.resolve("/f").unwrap();
+  let metadata = entry.into_metadata();
+  let json = serde_json::to_string(metadata).unwrap();
+
+  assert_eq!(&serde_json::from_str::<fs::metadata::Metadata>(&json).unwrap(), metadata);
+}